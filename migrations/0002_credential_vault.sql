@@ -0,0 +1,15 @@
+-- The keys which unlock the credential vault. The vault has one random data key, stored
+-- once per source ('passphrase' or 'key_file'), encrypted with a key derived from that
+-- source by Argon2id.
+--
+-- The existing passwords in server_items, transfer_auto_schedules and
+-- transfer_jobs_internal cannot be encrypted here, as the key is not known to SQL. They
+-- stay readable as plaintext, and are encrypted in place when the vault is first set up
+-- or unlocked (see DbController::encrypt_vault_columns).
+CREATE TABLE IF NOT EXISTS vault_keys
+(
+    source      TEXT NOT NULL,
+    salt        TEXT NOT NULL,
+    wrapped_key TEXT NOT NULL,
+    PRIMARY KEY (source)
+);
@@ -0,0 +1,4 @@
+-- Whether the client may fall back to the unauthenticated legacy CBC envelope for a
+-- server which does not understand versioned envelopes. Off unless set for a server.
+ALTER TABLE server_items
+    ADD COLUMN allow_legacy_envelope BOOLEAN NOT NULL DEFAULT FALSE;
@@ -1,5 +1,8 @@
 use std::fmt::Display;
+use std::io::{Read, Seek, SeekFrom};
 use std::ops::{AddAssign, SubAssign};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
 use std::time::Duration;
 /*
  * Neptis
@@ -10,16 +13,31 @@ use std::time::Duration;
  *
  * Generated by: https://openapi-generator.tech
  */
+use super::capabilities::{Capabilities, Feature};
 use super::dtos::*;
-use crate::apis::NeptisError;
+use super::job_wait::{JobProgress, JobWaitError, JobWaitOptions, WAIT_SLICE};
+use super::retention::{PrunePlan, RetentionPolicy};
+use super::snapshot_diff::SnapshotDiff;
+use crate::apis::{NeptisError, urlencode};
 use crate::db::sync_models::TransferJobDto;
 use crate::file_size::FileSize;
-use crate::prelude::ArduinoSecret;
-use crate::rolling_secret::RollingSecret;
+use crate::models::{
+    DataPointBrowseGetDto, DataPointDto, DataPointShareDto, DynamicConfigDto, FileDto,
+    FileOutputDto, FilePutDto, GlobalConfigPutDto, LogItemDto, RepoPointShareDto, UserPermission,
+    UserPermissionDto, WsNotificationDto,
+};
+use crate::prelude::{ArduinoSecret, WakeAction};
+use crate::rolling_secret::{
+    ENVELOPE_HEADER, EnvelopePart, EnvelopeVersion, RollingSecret, envelope_aad,
+};
 use crate::traits::ToShortIdString;
 use base64::{Engine as _, engine::general_purpose::STANDARD};
-use chrono::{NaiveDateTime, Utc};
-use reqwest::{Client, ClientBuilder, IntoUrl, Method};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use flate2::read::GzDecoder;
+use futures::{Stream, TryStreamExt, stream};
+use reqwest::header::{AUTHORIZATION, DATE, HeaderMap, RETRY_AFTER};
+use rand::{Rng, rng};
+use reqwest::{Client, ClientBuilder, IntoUrl, Method, Response, StatusCode};
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use tokio::sync::RwLock;
@@ -206,6 +224,10 @@ pub struct SystemSnapshotDto {
     pub smb_handles: Option<usize>,
     pub data_info: VGStatDto,
     pub repo_info: VGStatDto,
+    /// The optional features the server provides. Older servers do not send this, in which
+    /// case they are inferred from `api_version`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub features: Option<Vec<String>>,
 }
 
 impl SystemSnapshotDto {
@@ -301,6 +323,296 @@ pub struct WebApiConfig {
     pub secret: Option<RollingSecret>,
     pub user_agent: Option<String>,
     pub auth: RwLock<Option<AuthOutputDto>>,
+    pub retry: RetryPolicy,
+    pub capabilities: RwLock<Option<Capabilities>>,
+    pub envelope_policy: EnvelopePolicy,
+    /// The envelope format agreed with the server, once it has answered a secure request.
+    pub envelope: Mutex<Option<EnvelopeVersion>>,
+    /// Set once a response in the agreed format has been authenticated. The envelope header
+    /// itself is not authenticated, so from then on the format can no longer change.
+    pub envelope_pinned: AtomicBool,
+    /// The server clock minus the local clock in seconds, estimated from response `Date`
+    /// headers and used to pick the rolling key the server expects.
+    pub clock_offset: AtomicI64,
+}
+
+/// Clock offsets this close to the current estimate are treated as noise, as the `Date`
+/// header only has whole seconds and is set before the response travels back.
+const CLOCK_TOLERANCE_SECS: i64 = 2;
+
+impl WebApiConfig {
+    /// Returns the rolling key step the server is on, going by its clock.
+    pub(crate) fn server_step(&self) -> u64 {
+        let offset = self.clock_offset.load(Ordering::Relaxed);
+        RollingSecret::step_at(Utc::now().timestamp() + offset)
+    }
+
+    /// Updates the clock offset from the `Date` header of a response, if it has one.
+    pub(crate) fn observe_date(&self, headers: &HeaderMap) {
+        let Some(date) = headers
+            .get(DATE)
+            .and_then(|x| x.to_str().ok())
+            .and_then(|x| DateTime::parse_from_rfc2822(x).ok())
+        else {
+            return;
+        };
+        let offset = date.timestamp() - Utc::now().timestamp();
+        if (offset - self.clock_offset.load(Ordering::Relaxed)).abs() > CLOCK_TOLERANCE_SECS {
+            self.clock_offset.store(offset, Ordering::Relaxed);
+        }
+    }
+
+    /// Returns the envelope format for the next secure request: the one agreed with the
+    /// server, or the preferred one until it has answered. `None` when no secret is set.
+    pub(crate) fn envelope(&self) -> Option<EnvelopeVersion> {
+        self.secret.as_ref()?;
+        let agreed = *self.envelope.lock().unwrap();
+        Some(agreed.unwrap_or(self.envelope_policy.preferred))
+    }
+
+    /// Records the format the server answered with, where `None` means the server did not
+    /// send the envelope header. Once a format is agreed, answers without the header (such as
+    /// errors from a proxy) do not change it, and once it is pinned any other answer is
+    /// refused as a downgrade attempt.
+    pub(crate) fn accept_envelope(
+        &self,
+        answer: Option<EnvelopeVersion>,
+    ) -> Result<EnvelopeVersion, NeptisError> {
+        let mut agreed = self.envelope.lock().unwrap();
+        match (*agreed, answer) {
+            (Some(x), None) => return Ok(x),
+            (Some(x), Some(y)) if x != y && self.envelope_pinned.load(Ordering::Relaxed) => {
+                return Err(NeptisError::Str(format!(
+                    "The server asked for the {y} envelope after agreeing on {x}!"
+                )));
+            }
+            _ => {}
+        }
+        let version = answer.unwrap_or(EnvelopeVersion::LegacyCbc);
+        if !version.is_authenticated()
+            && self.envelope_policy.preferred.is_authenticated()
+            && !self.envelope_policy.allow_legacy
+        {
+            return Err(NeptisError::Str(
+                "The server does not support authenticated encryption!".into(),
+            ));
+        }
+        *agreed = Some(version);
+        Ok(version)
+    }
+
+    /// Returns the path which is encrypted into the `/secure/` URL, such as `/api/mounts`.
+    /// It is also bound into the associated data of the request and response bodies.
+    pub(crate) fn secure_path(&self, full_url: &str) -> String {
+        let mut full_query = full_url.replace(self.base_url.as_str(), "");
+        full_query = full_query
+            .strip_prefix("/")
+            .unwrap_or(full_query.as_str())
+            .to_string();
+        full_query = full_query
+            .strip_prefix("/api")
+            .unwrap_or(full_query.as_str())
+            .to_string();
+        full_query = full_query
+            .strip_prefix("api/")
+            .unwrap_or(full_query.as_str())
+            .to_string();
+
+        if !full_query.starts_with("/api/") {
+            full_query = "/api/".to_string() + full_query.as_str();
+        }
+        full_query
+    }
+
+    /// Rewrites a full URL into the encrypted `/secure/` form. The URL is returned as-is
+    /// when no secret is configured.
+    pub(crate) fn secure_url(
+        &self,
+        full_url: &str,
+        method: &Method,
+        version: EnvelopeVersion,
+    ) -> Result<String, NeptisError> {
+        let Some(ref secret) = self.secret else {
+            return Ok(full_url.to_string());
+        };
+        let full_query = self.secure_path(full_url);
+
+        // Finally, encrypt the data into the "secure api"
+        let aad = envelope_aad(EnvelopePart::Path, method.as_str(), "");
+        let enc_query = secret
+            .seal_at(version, full_query.as_bytes(), &aad, self.server_step())
+            .map(|x| STANDARD.encode(x))
+            .ok_or(NeptisError::Str("Failed to encrypt query".into()))?;
+
+        let mut enc_url = self.base_url.replace("/api", "");
+        enc_url = enc_url
+            .strip_suffix("/")
+            .unwrap_or(enc_url.as_str())
+            .to_string();
+        enc_url += format!("/secure/{}", enc_query).as_str();
+        Ok(enc_url)
+    }
+
+    /// Encrypts a request body for `path` when a secret is configured.
+    pub(crate) fn encode_body(
+        &self,
+        body: Vec<u8>,
+        version: EnvelopeVersion,
+        method: &Method,
+        path: &str,
+    ) -> Result<Vec<u8>, NeptisError> {
+        let Some(ref secret) = self.secret else {
+            return Ok(body);
+        };
+        let aad = envelope_aad(EnvelopePart::Request, method.as_str(), path);
+        secret
+            .seal_at(version, body.as_slice(), &aad, self.server_step())
+            .map(|x| STANDARD.encode(x).into_bytes())
+            .ok_or(NeptisError::Str("Failed to encrypt body!".into()))
+    }
+
+    /// Decodes a response body, decrypting it when a secret is configured. The body must use
+    /// the same envelope format as the request, so a response cannot be downgraded. The first
+    /// body which authenticates pins the agreed format.
+    pub(crate) fn decode_body(
+        &self,
+        r_body: Vec<u8>,
+        version: EnvelopeVersion,
+        method: &Method,
+        path: &str,
+    ) -> Result<Vec<u8>, NeptisError> {
+        if let Some(ref secret) = self.secret {
+            // We need to decode the body from base64.
+            let p_body = STANDARD
+                .decode(r_body.as_slice())
+                .map_err(|_| NeptisError::Str("Failed to decode!".into()))?;
+            let aad = envelope_aad(EnvelopePart::Response, method.as_str(), path);
+            let allow_legacy = !version.is_authenticated();
+            match secret.open_at(p_body.as_slice(), &aad, self.server_step(), allow_legacy) {
+                Some((x, body)) if x == version => {
+                    if x.is_authenticated() {
+                        self.envelope_pinned.store(true, Ordering::Relaxed);
+                    }
+                    Ok(body)
+                }
+                Some((x, _)) => Err(NeptisError::Str(format!(
+                    "The server answered with the {x} envelope instead of {version}!"
+                ))),
+                None => Err(NeptisError::Str("Failed to decrypt body!".into())),
+            }
+        } else {
+            Ok(r_body)
+        }
+    }
+}
+
+/// Controls how failed requests are retried. Requests which are not idempotent are only
+/// retried when the server is known to have never acted on them.
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    /// The maximum number of attempts, including the first one.
+    pub max_attempts: usize,
+    /// The delay before the first retry, doubled for every attempt after.
+    pub base_delay: Duration,
+    /// The upper bound for any single delay.
+    pub max_delay: Duration,
+    /// Whether to randomize each delay between half and the full value.
+    pub jitter: bool,
+    /// The methods which are safe to send more than once.
+    pub idempotent_methods: Vec<Method>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(8),
+            jitter: true,
+            idempotent_methods: vec![
+                Method::GET,
+                Method::HEAD,
+                Method::OPTIONS,
+                Method::PUT,
+                Method::DELETE,
+            ],
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy which never retries.
+    pub fn none() -> Self {
+        RetryPolicy {
+            max_attempts: 1,
+            ..Default::default()
+        }
+    }
+
+    pub fn is_idempotent(&self, method: &Method) -> bool {
+        self.idempotent_methods.contains(method)
+    }
+
+    /// Returns true if the error is transient and the request can safely be sent again.
+    pub fn should_retry(&self, err: &NeptisError, idempotent: bool) -> bool {
+        match err {
+            // The connection was never made, so the server could not have acted on it.
+            NeptisError::Api(e) if e.is_connect() => true,
+            NeptisError::Api(e) => idempotent && (e.is_timeout() || e.is_request()),
+            NeptisError::RateLimited(_, _) => true,
+            NeptisError::ServerError(StatusCode::SERVICE_UNAVAILABLE, _) => true,
+            NeptisError::ServerError(s, _) => {
+                idempotent && matches!(*s, StatusCode::BAD_GATEWAY | StatusCode::GATEWAY_TIMEOUT)
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns how long to wait after the given (1-based) failed attempt.
+    pub fn delay_for(&self, attempt: usize, err: &NeptisError) -> Duration {
+        if let NeptisError::RateLimited(_, Some(d)) = err {
+            return *d;
+        }
+        let exp = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1) as u32))
+            .min(self.max_delay);
+        if self.jitter && !exp.is_zero() {
+            rng().random_range(exp / 2..=exp)
+        } else {
+            exp
+        }
+    }
+}
+
+/// Controls which envelope format protects requests to the secure API.
+#[derive(Clone, Debug)]
+pub struct EnvelopePolicy {
+    /// The format offered to the server until it has answered with its own.
+    pub preferred: EnvelopeVersion,
+    /// Whether to fall back to the unauthenticated legacy format for servers which do not
+    /// understand versioned envelopes. This is off by default, as the fallback is decided by
+    /// an unauthenticated header.
+    pub allow_legacy: bool,
+}
+
+impl Default for EnvelopePolicy {
+    fn default() -> Self {
+        EnvelopePolicy {
+            preferred: EnvelopeVersion::default(),
+            allow_legacy: false,
+        }
+    }
+}
+
+impl EnvelopePolicy {
+    /// A policy which falls back to the legacy format for servers that only understand it.
+    pub fn legacy() -> Self {
+        EnvelopePolicy {
+            allow_legacy: true,
+            ..Default::default()
+        }
+    }
 }
 
 pub struct ApiBuilder<'a, U: IntoUrl> {
@@ -310,6 +622,8 @@ pub struct ApiBuilder<'a, U: IntoUrl> {
     body: Option<serde_json::Value>,
     queries: Vec<(String, String)>,
     token: Option<String>,
+    idempotent: Option<bool>,
+    reauth: Option<&'a WebApi>,
 }
 
 impl<'a, U: IntoUrl> ApiBuilder<'a, U> {
@@ -326,9 +640,17 @@ impl<'a, U: IntoUrl> ApiBuilder<'a, U> {
             body: None,
             queries: vec![],
             token,
+            idempotent: None,
+            reauth: None,
         }
     }
 
+    /// Overrides whether the request is safe to retry, regardless of its method.
+    pub fn with_idempotent(mut self, idempotent: bool) -> Self {
+        self.idempotent = Some(idempotent);
+        self
+    }
+
     pub fn with_body<T: Serialize>(mut self, body: T) -> Self {
         self.body = Some(serde_json::to_value(body).expect("Failed to serialize body"));
         self
@@ -350,115 +672,185 @@ impl<'a, U: IntoUrl> ApiBuilder<'a, U> {
         self.get_result_bytes().await.map(|_| ())
     }
 
-    pub async fn get_result_bytes(&self) -> Result<Vec<u8>, NeptisError> {
-        // First, we need to create the request.
-        let do_full_req = || async {
-            let mut final_url: String = self.full_uri.as_str().to_string();
-            let mut final_body = self
-                .body
-                .as_ref()
-                .map(|x| serde_json::to_vec(&x))
-                .transpose()?;
-
-            if let Some(ref secret) = self.config.secret {
-                let mut full_query = final_url.replace(self.config.base_url.as_str(), "".into());
-                full_query = full_query
-                    .strip_prefix("/")
-                    .unwrap_or(full_query.as_str())
-                    .to_string();
-                full_query = full_query
-                    .strip_prefix("/api")
-                    .unwrap_or(full_query.as_str())
-                    .to_string();
-                full_query = full_query
-                    .strip_prefix("api/")
-                    .unwrap_or(full_query.as_str())
-                    .to_string();
-
-                if !full_query.starts_with("/api/") {
-                    full_query = "/api/".to_string() + full_query.as_str();
-                }
+    /// Sends the request once. On the secure API, the first request also settles the
+    /// envelope format: a server which cannot read the preferred format never acted on the
+    /// request, so it is resent right away in the format the server asked for.
+    async fn send_once(&self, token: Option<&str>) -> Result<Vec<u8>, NeptisError> {
+        let version = self.config.envelope();
+        let res = self.send_with(token, version).await?;
+        self.config.observe_date(res.headers());
+        if let Some(used) = version {
+            let answer = res
+                .headers()
+                .get(ENVELOPE_HEADER)
+                .and_then(|x| x.to_str().ok())
+                .and_then(|x| x.parse::<EnvelopeVersion>().ok());
+            let agreed = self.config.accept_envelope(answer)?;
+            if agreed != used && !res.status().is_success() {
+                let res = self.send_with(token, Some(agreed)).await?;
+                self.config.observe_date(res.headers());
+                return self.read_response(res, Some(agreed)).await;
+            }
+        }
+        self.read_response(res, version).await
+    }
 
-                // Finally, encrypt the data into the "secure api"
-                let enc_query = secret
-                    .encrypt(full_query.as_bytes())
-                    .map(|x| STANDARD.encode(x))
-                    .ok_or(NeptisError::Str("Failed to encrypt query".into()))?;
-
-                let mut enc_url = self.config.base_url.replace("/api", "");
-                enc_url = enc_url
-                    .strip_suffix("/")
-                    .unwrap_or(enc_url.as_str())
-                    .to_string();
-                enc_url += format!("/secure/{}", enc_query).as_str();
-
-                if let Some(body) = final_body {
-                    // There is something in the body - we need to encrypt it as well.
-                    final_body = Some(
-                        secret
-                            .encrypt(body.as_slice())
-                            .map(|x| STANDARD.encode(x).as_bytes().to_vec())
-                            .ok_or(NeptisError::Str("Failed to encrypt body!".into()))?,
-                    );
-                }
-                final_url = enc_url
+    async fn send_with(
+        &self,
+        token: Option<&str>,
+        version: Option<EnvelopeVersion>,
+    ) -> Result<Response, NeptisError> {
+        let mut final_url: String = self.full_uri.as_str().to_string();
+        let mut final_body = self
+            .body
+            .as_ref()
+            .map(|x| serde_json::to_vec(&x))
+            .transpose()?;
+
+        if let Some(version) = version {
+            if let Some(body) = final_body {
+                // There is something in the body - we need to encrypt it as well.
+                let path = self.config.secure_path(&final_url);
+                final_body = Some(
+                    self.config
+                        .encode_body(body, version, &self.method, &path)?,
+                );
             }
+            final_url = self.config.secure_url(&final_url, &self.method, version)?;
+        }
 
-            // Finally, build the request and process.
-            let mut req_builder = self.config.client.request(self.method.clone(), final_url);
+        // Finally, build the request and process.
+        let mut req_builder = self.config.client.request(self.method.clone(), final_url);
 
-            for (k, v) in self.queries.iter() {
-                req_builder = req_builder.query(&[(k.to_owned(), v.to_owned())]);
-            }
+        for (k, v) in self.queries.iter() {
+            req_builder = req_builder.query(&[(k.to_owned(), v.to_owned())]);
+        }
 
-            if let Some(ref user_agent) = self.config.user_agent {
-                req_builder = req_builder.header(reqwest::header::USER_AGENT, user_agent.clone());
-            }
+        if let Some(ref user_agent) = self.config.user_agent {
+            req_builder = req_builder.header(reqwest::header::USER_AGENT, user_agent.clone());
+        }
 
-            if let Some(token) = self.token.clone() {
-                req_builder = req_builder.bearer_auth(token);
-            }
+        if let Some(token) = token {
+            req_builder = req_builder.bearer_auth(token);
+        }
 
-            if let Some(body) = final_body {
-                req_builder = req_builder.body(body);
-                req_builder = req_builder.header("Content-Type", "application/json");
-            }
+        if let Some(version) = version {
+            req_builder = req_builder.header(ENVELOPE_HEADER, version.to_string());
+        }
+
+        if let Some(body) = final_body {
+            req_builder = req_builder.body(body);
+            req_builder = req_builder.header("Content-Type", "application/json");
+        }
 
-            let req = req_builder.build()?;
-            let res = self.config.client.execute(req).await?.error_for_status()?;
+        let req = req_builder.build()?;
+        Ok(self.config.client.execute(req).await?)
+    }
 
-            let mut res_body = res.bytes().await.ok().map(|x| x.to_vec());
-            if let Some(r_body) = res_body {
-                if r_body.is_empty() {
-                    return Ok(vec![]);
-                }
-                if let Some(ref secret) = self.config.secret {
-                    // We need to decode the body from base64.
-                    let p_body = STANDARD
-                        .decode(r_body.as_slice())
-                        .map_err(|_| NeptisError::Str("Failed to decode!".into()))?;
-                    res_body = Some(
-                        secret
-                            .decrypt(p_body.as_slice())
-                            .ok_or(NeptisError::Str("Failed to decrypt body!".into()))?,
-                    );
-                } else {
-                    res_body = Some(r_body);
-                }
+    async fn read_response(
+        &self,
+        res: Response,
+        version: Option<EnvelopeVersion>,
+    ) -> Result<Vec<u8>, NeptisError> {
+        if !res.status().is_success() {
+            return Err(self.to_error(res, version).await);
+        }
+
+        let r_body = res.bytes().await.map(|x| x.to_vec()).unwrap_or_default();
+        if r_body.is_empty() {
+            return Ok(vec![]);
+        }
+        self.decode_body(r_body, version)
+    }
+
+    pub async fn get_result_bytes(&self) -> Result<Vec<u8>, NeptisError> {
+        let policy = &self.config.retry;
+        let idempotent = self
+            .idempotent
+            .unwrap_or_else(|| policy.is_idempotent(&self.method));
+        let mut token = self.token.clone();
+        let mut attempt = 1;
+        let mut boundary_retried = false;
+        let mut reauthed = false;
+        loop {
+            let step = self.config.server_step();
+            let err = match self.send_once(token.as_deref()).await {
+                Ok(x) => return Ok(x),
+                Err(e) => e,
+            };
+
+            // The token may have been revoked (or the server restarted) before it expired
+            // locally. Log in again and replay the request once with the new token.
+            if let (Some(api), NeptisError::Unauthorized(_)) = (self.reauth, &err)
+                && !reauthed
+            {
+                reauthed = true;
+                token = Box::pin(api.refresh_auth(token.as_deref())).await?;
+                continue;
             }
-            Ok(res_body.unwrap_or(vec![]))
-        };
 
-        // Attempt to do the request several times due to encryption.
-        let mut ret = Err(NeptisError::Str("Failed to send request".into()));
-        for _ in 0..2 {
-            ret = do_full_req().await;
-            if ret.is_ok() {
-                break;
+            // A request encrypted right before the rolling key changes, or before the server's
+            // clock offset was known, can be rejected by the server or answered with a key we
+            // did not expect. Once the step has moved, it is retried once right away and does
+            // not count as an attempt, as long as it cannot duplicate work.
+            if self.config.secret.is_some()
+                && !boundary_retried
+                && step != self.config.server_step()
+                && (idempotent || err.status().is_some_and(|s| s.is_client_error()))
+            {
+                boundary_retried = true;
+                continue;
+            }
+
+            if attempt >= policy.max_attempts || !policy.should_retry(&err, idempotent) {
+                return Err(err);
             }
-            tokio::time::sleep(Duration::from_secs(2)).await;
+            tokio::time::sleep(policy.delay_for(attempt, &err)).await;
+            attempt += 1;
         }
-        ret
+    }
+
+    fn decode_body(
+        &self,
+        r_body: Vec<u8>,
+        version: Option<EnvelopeVersion>,
+    ) -> Result<Vec<u8>, NeptisError> {
+        let Some(version) = version else {
+            return Ok(r_body);
+        };
+        let path = self.config.secure_path(self.full_uri.as_str());
+        self.config.decode_body(r_body, version, &self.method, &path)
+    }
+
+    /// Converts a failed response into a status-aware error, keeping the server's message.
+    async fn to_error(&self, res: Response, version: Option<EnvelopeVersion>) -> NeptisError {
+        let status = res.status();
+        let retry_after = res
+            .headers()
+            .get(RETRY_AFTER)
+            .and_then(|x| x.to_str().ok())
+            .and_then(|x| x.trim().parse::<u64>().ok())
+            .map(Duration::from_secs);
+        let r_body = res.bytes().await.map(|x| x.to_vec()).unwrap_or_default();
+
+        // Error bodies are encrypted on the secure path too, but fall back to the raw text
+        // in case the server failed before it could encrypt anything.
+        let body = self.decode_body(r_body.clone(), version).unwrap_or(r_body);
+        let text = String::from_utf8_lossy(&body).trim().to_string();
+        let message = match serde_json::from_str::<serde_json::Value>(&text) {
+            Ok(serde_json::Value::String(x)) => x,
+            Ok(serde_json::Value::Object(x)) => ["error", "message", "detail", "title"]
+                .iter()
+                .find_map(|k| x.get(*k).and_then(|v| v.as_str()).map(|v| v.to_string()))
+                .unwrap_or(text),
+            _ => text,
+        };
+        let message = if message.is_empty() {
+            status.canonical_reason().unwrap_or("Unknown error").to_string()
+        } else {
+            message
+        };
+        NeptisError::from_status(status, message, retry_after)
     }
 
     pub async fn get_result_str(&self) -> Result<String, NeptisError> {
@@ -480,6 +872,12 @@ impl<'a, U: IntoUrl> ApiBuilder<'a, U> {
 
 pub const IPC_PORT: u16 = 64511;
 
+/// The default window size used when streaming a file from the server.
+pub const DUMP_CHUNK_BYTES: usize = 1024 * 1024;
+
+/// The default chunk size used when uploading a file, kept small enough to fit in one request.
+pub const UPLOAD_CHUNK_BYTES: usize = 512 * 1024;
+
 pub struct WebApi {
     config: WebApiConfig,
     user_name: String,
@@ -579,26 +977,41 @@ impl WebApi {
     ) -> Result<(), NeptisError> {
         let arduino_ep_str = arduino_ep.as_ref();
         let arduino_pass_str = arduino_pass.as_ref();
-        let key = ArduinoSecret::from_string(arduino_pass_str)
+        let auth = ArduinoSecret::from_string(arduino_pass_str)
             .ok_or(NeptisError::Str("Failed to parse Arduino Key".into()))?
-            .rolling_key()
+            .authorization(WakeAction::Start)
             .ok_or(NeptisError::Str("Failed to calculate next key!".into()))?;
         Ok(ClientBuilder::new()
             .build()?
-            .post(format!("{}/{}", arduino_ep_str, "start"))
-            .bearer_auth(key.to_string())
+            .post(format!("{}/{}", arduino_ep_str, WakeAction::Start))
+            .header(AUTHORIZATION, auth)
             .send()
             .await?
             .error_for_status()
             .map(|_| ())?)
     }
 
+    fn can_login(&self) -> bool {
+        !self.user_name.is_empty() && !self.password.is_empty()
+    }
+
+    async fn login(&self) -> Result<AuthOutputDto, NeptisError> {
+        self.raw_post("/users/auth", None)
+            .with_idempotent(true)
+            .with_body(UserForLoginApi {
+                user_name: self.user_name.clone(),
+                password: self.password.clone(),
+            })
+            .get_result_json::<AuthOutputDto>()
+            .await
+    }
+
     async fn ensure_auth(&self) -> Result<(), NeptisError> {
         let needs_refresh = {
             let r_auth = self.config.auth.read().await;
             match r_auth.as_ref() {
                 Some(a) => Utc::now().naive_utc() > a.expire_date,
-                None => !self.user_name.is_empty() && !self.password.is_empty(),
+                None => self.can_login(),
             }
         };
 
@@ -606,28 +1019,34 @@ impl WebApi {
             let mut w_auth = self.config.auth.write().await;
             let still_needs_refresh = match w_auth.as_ref() {
                 Some(a) => Utc::now().naive_utc() > a.expire_date,
-                None => !self.user_name.is_empty() && !self.password.is_empty(),
+                None => self.can_login(),
             };
 
             if still_needs_refresh {
-                let user_name = self.user_name.clone();
-                let password = self.password.clone();
-
-                let ret = self
-                    .raw_post("/users/auth", None)
-                    .with_body(UserForLoginApi {
-                        user_name,
-                        password,
-                    })
-                    .get_result_json::<AuthOutputDto>()
-                    .await?;
-
-                *w_auth = Some(ret);
+                *w_auth = Some(self.login().await?);
             }
         }
         Ok(())
     }
 
+    /// Replaces a token the server rejected. Concurrent callers holding the same stale token
+    /// wait on the write lock and share the single login performed by whoever got it first.
+    async fn refresh_auth(&self, stale: Option<&str>) -> Result<Option<String>, NeptisError> {
+        let mut w_auth = self.config.auth.write().await;
+        if let Some(a) = w_auth.as_ref()
+            && Some(a.token.as_str()) != stale
+        {
+            return Ok(Some(a.token.clone()));
+        }
+        if !self.can_login() {
+            return Ok(None);
+        }
+        let ret = self.login().await?;
+        let token = ret.token.clone();
+        *w_auth = Some(ret);
+        Ok(Some(token))
+    }
+
     pub fn new(
         base_path: impl Into<String>,
         user_name: impl Into<String>,
@@ -646,10 +1065,38 @@ impl WebApi {
                 secret,
                 user_agent: None,
                 auth: RwLock::new(None),
+                retry: RetryPolicy::default(),
+                capabilities: RwLock::new(None),
+                envelope_policy: EnvelopePolicy::default(),
+                envelope: Mutex::new(None),
+                envelope_pinned: AtomicBool::new(false),
+                clock_offset: AtomicI64::new(0),
             },
         }
     }
 
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.config.retry = retry;
+        self
+    }
+
+    pub fn with_envelope_policy(mut self, policy: EnvelopePolicy) -> Self {
+        self.config.envelope_policy = policy;
+        self
+    }
+
+    /// Returns the envelope format used with the secure API, if a secret is configured. This
+    /// is the preferred format until the server has answered a request.
+    pub fn get_envelope(&self) -> Option<EnvelopeVersion> {
+        self.config.envelope()
+    }
+
+    /// Returns how far the server clock is ahead of the local one, in seconds, as estimated
+    /// from its responses.
+    pub fn get_clock_offset(&self) -> i64 {
+        self.config.clock_offset.load(Ordering::Relaxed)
+    }
+
     async fn request(
         &self,
         method: Method,
@@ -660,7 +1107,9 @@ impl WebApi {
             let r_auth = self.config.auth.read().await;
             r_auth.as_ref().map(|x| x.token.clone())
         };
-        Ok(self.raw_request(method, rel_path, token))
+        let mut ret = self.raw_request(method, rel_path, token);
+        ret.reauth = Some(self);
+        Ok(ret)
     }
 
     fn raw_request(
@@ -737,6 +1186,63 @@ impl WebApi {
             .await
     }
 
+    /// Starts a backup with tags, a snapshot label and description. A dry-run does not create
+    /// a snapshot; the job messages report what would have been backed up instead.
+    pub async fn post_one_backup_with(
+        &self,
+        name: &str,
+        lock: bool,
+        dto: PostForBackupApi,
+    ) -> Result<RepoJobDto, NeptisError> {
+        self.post(format!(
+            "/mounts/id/{}/backup?lock={}&dry_run={}",
+            name, lock, dto.dry_run
+        ))
+        .await?
+        .with_body(dto)
+        .get_result_json()
+        .await
+    }
+
+    /// Applies the policy to the current snapshots without removing anything.
+    pub async fn preview_prune(
+        &self,
+        name: &str,
+        policy: &RetentionPolicy,
+    ) -> Result<PrunePlan, NeptisError> {
+        Ok(policy.apply(&self.get_all_snapshots(name).await?))
+    }
+
+    /// Starts a prune job which removes the snapshots the policy does not keep. Locked
+    /// snapshots are never removed.
+    ///
+    /// The server prunes by policy rather than by snapshot, so the policy is applied again to
+    /// the current snapshots first, and the prune is refused if it would remove anything other
+    /// than the snapshots in `confirmed`, such as a preview from [`WebApi::preview_prune`].
+    pub async fn post_one_prune(
+        &self,
+        name: &str,
+        policy: &RetentionPolicy,
+        confirmed: &PrunePlan,
+    ) -> Result<RepoJobDto, NeptisError> {
+        self.require(Feature::Prune).await?;
+        policy.validate()?;
+        if policy.is_empty() {
+            return Err(NeptisError::Str("The retention policy is empty!".into()));
+        }
+        let plan = self.preview_prune(name, policy).await?;
+        if plan.remove_ids() != confirmed.remove_ids() {
+            return Err(NeptisError::Str(
+                "The snapshots changed since the preview, please review the prune again!".into(),
+            ));
+        }
+        self.post(format!("/mounts/id/{}/prune", name))
+            .await?
+            .with_body(policy)
+            .get_result_json()
+            .await
+    }
+
     pub async fn post_one_check(&self, name: &str) -> Result<RepoJobDto, NeptisError> {
         self.post(format!("/mounts/id/{}/check", name))
             .await?
@@ -758,9 +1264,45 @@ impl WebApi {
         .await
     }
 
+    /// Starts a restore of selected paths, optionally into a different point.
+    pub async fn post_one_restore_with(
+        &self,
+        name: &str,
+        dto: PostForRestoreApi,
+    ) -> Result<RepoJobDto, NeptisError> {
+        self.post(format!(
+            "/mounts/id/{}/snapshots/{}/restore?dry_run={}",
+            name, dto.snapshot, dto.dry_run
+        ))
+        .await?
+        .with_body(dto)
+        .get_result_json()
+        .await
+    }
+
+    /// Runs the restore as a dry-run and waits for the messages it reports.
+    pub async fn preview_restore(
+        &self,
+        name: &str,
+        mut dto: PostForRestoreApi,
+    ) -> Result<RestorePreview, NeptisError> {
+        dto.dry_run = true;
+        let id = self.post_one_restore_with(name, dto).await?.id;
+        let opts = JobWaitOptions::default().with_poll_interval(Duration::from_secs(1));
+        match self.wait_for_job(id, opts).await {
+            Ok(job) => Ok(RestorePreview::from_job(&job)),
+            Err(JobWaitError::Failed(job)) => Err(NeptisError::Str(format!(
+                "The dry-run failed: {}",
+                job.errors.join(", ")
+            ))),
+            Err(e) => Err(e.into()),
+        }
+    }
+
     pub async fn lock_one_snapshot(&self, name: &str, snapshot: &str) -> Result<(), NeptisError> {
         self.post(format!("/mounts/id/{}/snapshots/{}/lock", name, snapshot))
             .await?
+            .with_idempotent(true)
             .get_success()
             .await
     }
@@ -783,6 +1325,53 @@ impl WebApi {
             .await
     }
 
+    pub async fn put_one_snapshot(
+        &self,
+        name: &str,
+        snapshot: &str,
+        dto: PutForSnapshotApi,
+    ) -> Result<(), NeptisError> {
+        if dto.is_empty() {
+            return Ok(());
+        }
+        self.put(format!("/mounts/id/{}/snapshots/{}", name, snapshot))
+            .await?
+            .with_body(dto)
+            .get_success()
+            .await
+    }
+
+    /// Adds and removes tags on several snapshots of a point at once. Snapshots whose tags
+    /// would not change are skipped. Returns the number of snapshots which were updated.
+    pub async fn tag_snapshots(
+        &self,
+        name: &str,
+        snapshots: &[String],
+        add: &[String],
+        remove: &[String],
+    ) -> Result<usize, NeptisError> {
+        let mut ret = 0;
+        for id in snapshots {
+            let snapshot = self.get_one_snapshot(name, id).await?;
+            let tags = PutForSnapshotApi::clean_tags(
+                snapshot
+                    .tags
+                    .iter()
+                    .filter(|x| !remove.iter().any(|r| r.eq_ignore_ascii_case(x)))
+                    .chain(add.iter()),
+            );
+            if tags != snapshot.tags {
+                let dto = PutForSnapshotApi {
+                    tags: Some(tags),
+                    ..Default::default()
+                };
+                self.put_one_snapshot(name, id, dto).await?;
+                ret += 1;
+            }
+        }
+        Ok(ret)
+    }
+
     pub async fn delete_one_snapshot(&self, name: &str, snapshot: &str) -> Result<(), NeptisError> {
         self.delete(format!("/mounts/id/{}/snapshots/{}", name, snapshot))
             .await?
@@ -808,6 +1397,167 @@ impl WebApi {
             .await
     }
 
+    /// Returns one raw page of jobs with the server-side parts of `filter` applied.
+    pub async fn get_jobs_page(
+        &self,
+        filter: &JobFilter,
+        n: usize,
+        o: usize,
+    ) -> Result<Vec<RepoJobDto>, NeptisError> {
+        self.get(format!("/mounts/jobs?n={}&o={}{}", n, o, filter.to_query()))
+            .await?
+            .get_result_json()
+            .await
+    }
+
+    /// Lazily pages through every job matching `filter`, newest first, fetching `page_size`
+    /// jobs at a time. The stream ends once the server returns a short page.
+    pub fn jobs_stream<'a>(
+        &'a self,
+        filter: JobFilter,
+        page_size: usize,
+    ) -> impl Stream<Item = Result<RepoJobDto, NeptisError>> + 'a {
+        let page_size = page_size.max(1);
+        stream::try_unfold(Some(0), move |state| {
+            let filter = filter.clone();
+            async move {
+                let Some(o) = state else {
+                    return Ok::<_, NeptisError>(None);
+                };
+                let page = self.get_jobs_page(&filter, page_size, o).await?;
+                let next = (page.len() >= page_size).then_some(o + page.len());
+                let jobs = page
+                    .into_iter()
+                    .filter(|x| filter.matches(x))
+                    .map(Ok::<_, NeptisError>)
+                    .collect::<Vec<_>>();
+                Ok(Some((stream::iter(jobs), next)))
+            }
+        })
+        .try_flatten()
+    }
+
+    /// Returns the snapshots of a point which carry the given tag, newest first.
+    pub async fn get_snapshots_by_tag(
+        &self,
+        name: &str,
+        tag: &str,
+    ) -> Result<Vec<SnapshotFileDto>, NeptisError> {
+        let mut ret = self
+            .get_all_snapshots(name)
+            .await?
+            .into_iter()
+            .filter(|x| x.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)))
+            .collect::<Vec<_>>();
+        ret.sort_by_key(|x| std::cmp::Reverse(x.time));
+        Ok(ret)
+    }
+
+    /// Returns the repository browser path of a snapshot of one of our points.
+    fn snapshot_root(&self, name: &str, snapshot: &str) -> String {
+        format!("/{}/{}/{}", self.get_username(), name, snapshot)
+    }
+
+    /// Lists one directory of a snapshot through the repository browser. Snapshots are
+    /// addressed as `/<user>/<point>/<snapshot>/<path>`, and the returned paths are relative
+    /// to the snapshot root.
+    pub async fn browse_snapshot(
+        &self,
+        name: &str,
+        snapshot: &str,
+        path: &str,
+    ) -> Result<Vec<NodeDto>, NeptisError> {
+        let root = self.snapshot_root(name, snapshot);
+        let dir = format!("/{}", path.trim_matches('/'));
+        let dto = DataPointBrowseGetDto {
+            path: Some(Some(format!("{}{}", root, dir))),
+            depth: Some(1),
+        };
+        let files: Vec<FileDto> = self
+            .get("/repos/files")
+            .await?
+            .with_body(dto)
+            .get_result_json()
+            .await?;
+        Ok(files
+            .into_iter()
+            .filter_map(|x| NodeDto::from_repo_file(x, &root))
+            .filter(|x| x.path != dir)
+            .collect())
+    }
+
+    /// Downloads a whole file from a snapshot, addressed like [`WebApi::browse_snapshot`].
+    pub async fn dump_snapshot_file(
+        &self,
+        name: &str,
+        snapshot: &str,
+        path: &str,
+    ) -> Result<Vec<u8>, NeptisError> {
+        let full = format!("{}/{}", self.snapshot_root(name, snapshot), path.trim_matches('/'));
+        let dto: FileOutputDto = self
+            .get("/repos/dump")
+            .await?
+            .with_body(full)
+            .get_result_json()
+            .await?;
+        Self::decode_file_output(dto, path)
+    }
+
+    /// Decodes a downloaded file, which the server may send as Base64 and compressed.
+    fn decode_file_output(dto: FileOutputDto, path: &str) -> Result<Vec<u8>, NeptisError> {
+        if dto.is_directory == Some(true) {
+            return Err(NeptisError::Str(format!("'{}' is a directory!", path)));
+        }
+        let data = dto.data.flatten().unwrap_or_default();
+        let data = if dto.is_base64.unwrap_or(true) {
+            STANDARD
+                .decode(data)
+                .map_err(|_| NeptisError::Str("Failed to decode file!".into()))?
+        } else {
+            data.into_bytes()
+        };
+        if dto.is_gzip == Some(true) {
+            let mut ret = vec![];
+            GzDecoder::new(data.as_slice()).read_to_end(&mut ret)?;
+            Ok(ret)
+        } else {
+            Ok(data)
+        }
+    }
+
+    /// Returns every file and directory of a snapshot, walking it one directory at a time.
+    pub async fn walk_snapshot(
+        &self,
+        name: &str,
+        snapshot: &str,
+    ) -> Result<Vec<NodeDto>, NeptisError> {
+        let mut ret = vec![];
+        let mut pending = vec!["/".to_string()];
+        while let Some(dir) = pending.pop() {
+            for node in self.browse_snapshot(name, snapshot, &dir).await? {
+                if node.is_dir {
+                    pending.push(node.path.clone());
+                }
+                ret.push(node);
+            }
+        }
+        Ok(ret)
+    }
+
+    /// Compares two snapshots of a point, where `from` is the older one.
+    pub async fn diff_snapshots(
+        &self,
+        name: &str,
+        from: &str,
+        to: &str,
+    ) -> Result<SnapshotDiff, NeptisError> {
+        let (old, new) = tokio::try_join!(
+            self.walk_snapshot(name, from),
+            self.walk_snapshot(name, to)
+        )?;
+        Ok(SnapshotDiff::compare(name, from, to, &old, &new))
+    }
+
     pub async fn get_one_job(&self, id: Uuid) -> Result<RepoJobDto, NeptisError> {
         self.get(format!("/mounts/jobs/{}", id.to_string()))
             .await?
@@ -815,6 +1565,72 @@ impl WebApi {
             .await
     }
 
+    /// Polls a job until it finishes, returning the successful job or why it did not succeed.
+    pub async fn wait_for_job(
+        &self,
+        id: Uuid,
+        mut opts: JobWaitOptions<'_>,
+    ) -> Result<RepoJobDto, JobWaitError> {
+        let start = tokio::time::Instant::now();
+        let deadline = opts.timeout.map(|x| start + x);
+        let mut last: Option<RepoJobDto> = None;
+        let mut poll_errors = 0;
+        let mut seen_errors = 0;
+        let mut delay = opts.start_delay;
+        loop {
+            // Sleep in slices so a cancellation or the deadline is noticed quickly.
+            let until = tokio::time::Instant::now() + delay;
+            loop {
+                if opts.is_cancelled() {
+                    return Err(JobWaitError::Cancelled(last.map(Box::new)));
+                }
+                let now = tokio::time::Instant::now();
+                if deadline.is_some_and(|x| now >= x) {
+                    return Err(JobWaitError::TimedOut(last.map(Box::new)));
+                }
+                if now >= until {
+                    break;
+                }
+                let mut next = until.min(now + WAIT_SLICE);
+                if let Some(x) = deadline {
+                    next = next.min(x);
+                }
+                tokio::time::sleep_until(next).await;
+            }
+            delay = opts.poll_interval;
+
+            let job = match self.get_one_job(id).await {
+                Ok(job) => job,
+                Err(e) => {
+                    poll_errors += 1;
+                    if poll_errors > opts.max_poll_errors {
+                        return Err(JobWaitError::Unreachable(e));
+                    }
+                    continue;
+                }
+            };
+            poll_errors = 0;
+            if let Some(ref mut on_progress) = opts.on_progress {
+                on_progress(&JobProgress {
+                    status: job.job_status,
+                    used_bytes: job.used_bytes.max(0) as u64,
+                    total_bytes: job.total_bytes.map(|x| x.max(0) as u64),
+                    new_errors: job.errors.iter().skip(seen_errors).cloned().collect(),
+                    elapsed: start.elapsed(),
+                });
+            }
+            seen_errors = seen_errors.max(job.errors.len());
+            match job.job_status {
+                JobStatus::Successful => return Ok(job),
+                JobStatus::Failed => return Err(JobWaitError::Failed(Box::new(job))),
+                _ if opts.max_job_errors.is_some_and(|x| job.errors.len() > x) => {
+                    return Err(JobWaitError::TooManyErrors(Box::new(job)));
+                }
+                _ => last = Some(job),
+            }
+        }
+    }
+
     pub async fn get_all_jobs_for_mount(&self, name: &str) -> Result<Vec<RepoJobDto>, NeptisError> {
         self.get(format!("/mounts/id/{}/jobs", name))
             .await?
@@ -863,9 +1679,249 @@ impl WebApi {
             .await
     }
 
+    /// Returns every data point this user can access, their own and those shared with them.
+    pub async fn get_data_points(&self) -> Result<Vec<DataPointDto>, NeptisError> {
+        self.require(Feature::Shares).await?;
+        self.get("/datas").await?.get_result_json().await
+    }
+
+    /// Returns the points owned by other users which have been shared with this user.
+    ///
+    /// The data points list those shared with this user next to their own, so only the share
+    /// of each point owned by someone else is fetched, to know if it can be written. Servers
+    /// without sharing return no points.
+    pub async fn get_shared_mounts(&self) -> Result<Vec<SharedMount>, NeptisError> {
+        if self.require(Feature::Shares).await.is_err() {
+            return Ok(vec![]);
+        }
+        let mut ret = vec![];
+        for point in self.get_data_points().await? {
+            if point.user_name == self.user_name {
+                continue;
+            }
+            let shares = self
+                .get_data_shares(&point.user_name, &point.point_name, Some(&self.user_name))
+                .await?;
+            let Some(share) = shares.into_iter().find(|x| {
+                x.user_name.as_ref().and_then(|x| x.as_deref()) == Some(self.user_name.as_str())
+            }) else {
+                continue;
+            };
+            if share.can_read != Some(true) {
+                continue;
+            }
+            ret.push(SharedMount {
+                owned_by: point.user_name,
+                name: point.point_name,
+                can_write: share.can_write == Some(true),
+            });
+        }
+        Ok(ret)
+    }
+
+    /// Returns the data API path of a file in a point, addressed as `/<user>/<point>/<path>`.
+    fn data_path(point_user: &str, point_name: &str, path: &str) -> String {
+        let rel = path.trim_matches('/');
+        if rel.is_empty() {
+            format!("/{}/{}", point_user, point_name)
+        } else {
+            format!("/{}/{}/{}", point_user, point_name, rel)
+        }
+    }
+
+    /// Lists one directory of any point this user can read, including points shared by other
+    /// users. The returned paths are relative to the point root.
+    pub async fn browse_data(
+        &self,
+        point_user: &str,
+        point_name: &str,
+        path: &str,
+    ) -> Result<Vec<NodeDto>, NeptisError> {
+        let root = Self::data_path(point_user, point_name, "");
+        let dir = format!("/{}", path.trim_matches('/'));
+        let dto = DataPointBrowseGetDto {
+            path: Some(Some(Self::data_path(point_user, point_name, path))),
+            depth: Some(1),
+        };
+        let files: Vec<FileDto> = self
+            .get("/datas/files")
+            .await?
+            .with_body(dto)
+            .get_result_json()
+            .await?;
+        Ok(files
+            .into_iter()
+            .filter_map(|x| NodeDto::from_repo_file(x, &root))
+            .filter(|x| x.path != dir)
+            .collect())
+    }
+
+    /// Downloads a whole file through the data API, addressed like [`WebApi::browse_data`].
+    pub async fn dump_data_file(
+        &self,
+        point_user: &str,
+        point_name: &str,
+        path: &str,
+    ) -> Result<Vec<u8>, NeptisError> {
+        let dto: FileOutputDto = self
+            .get("/datas/dump")
+            .await?
+            .with_body(Self::data_path(point_user, point_name, path))
+            .get_result_json()
+            .await?;
+        Self::decode_file_output(dto, path)
+    }
+
+    /// Creates, replaces or renames a file through the data API. The paths in `dto` are
+    /// relative to the point, and the data API only ever replaces the whole file.
+    pub async fn put_data_file(
+        &self,
+        point_user: &str,
+        point_name: &str,
+        mut dto: FilePutDto,
+    ) -> Result<(), NeptisError> {
+        let full = |x: Option<Option<String>>| {
+            x.map(|x| x.map(|x| Self::data_path(point_user, point_name, &x)))
+        };
+        dto.path = full(dto.path);
+        dto.new_path = full(dto.new_path);
+        self.put("/datas/files")
+            .await?
+            .with_body(dto)
+            .get_success()
+            .await
+    }
+
+    pub async fn delete_data_file(
+        &self,
+        point_user: &str,
+        point_name: &str,
+        path: &str,
+    ) -> Result<(), NeptisError> {
+        self.delete("/datas/files")
+            .await?
+            .with_body(Self::data_path(point_user, point_name, path))
+            .get_success()
+            .await
+    }
+
+    fn share_query(user: Option<&str>) -> String {
+        user.map(|x| format!("?user={}", urlencode(x)))
+            .unwrap_or_default()
+    }
+
+    pub async fn get_data_shares(
+        &self,
+        point_user: &str,
+        point_name: &str,
+        user: Option<&str>,
+    ) -> Result<Vec<DataPointShareDto>, NeptisError> {
+        self.require(Feature::Shares).await?;
+        self.get(format!(
+            "/datas/{}/{}/shares{}",
+            urlencode(point_user),
+            urlencode(point_name),
+            Self::share_query(user)
+        ))
+        .await?
+        .get_result_json()
+        .await
+    }
+
+    pub async fn put_data_share(
+        &self,
+        point_user: &str,
+        point_name: &str,
+        dto: DataPointShareDto,
+    ) -> Result<DataPointShareDto, NeptisError> {
+        self.require(Feature::Shares).await?;
+        self.put(format!(
+            "/datas/{}/{}/shares",
+            urlencode(point_user),
+            urlencode(point_name)
+        ))
+            .await?
+            .with_body(dto)
+            .get_result_json()
+            .await
+    }
+
+    pub async fn delete_data_share(
+        &self,
+        point_user: &str,
+        point_name: &str,
+        user: &str,
+    ) -> Result<(), NeptisError> {
+        self.require(Feature::Shares).await?;
+        self.delete(format!(
+            "/datas/{}/{}/shares{}",
+            urlencode(point_user),
+            urlencode(point_name),
+            Self::share_query(Some(user))
+        ))
+        .await?
+        .get_success()
+        .await
+    }
+
+    pub async fn get_repo_shares(
+        &self,
+        point_user: &str,
+        point_name: &str,
+        user: Option<&str>,
+    ) -> Result<Vec<RepoPointShareDto>, NeptisError> {
+        self.require(Feature::Shares).await?;
+        self.get(format!(
+            "/repos/{}/{}/shares{}",
+            urlencode(point_user),
+            urlencode(point_name),
+            Self::share_query(user)
+        ))
+        .await?
+        .get_result_json()
+        .await
+    }
+
+    pub async fn put_repo_share(
+        &self,
+        point_user: &str,
+        point_name: &str,
+        dto: RepoPointShareDto,
+    ) -> Result<RepoPointShareDto, NeptisError> {
+        self.require(Feature::Shares).await?;
+        self.put(format!(
+            "/repos/{}/{}/shares",
+            urlencode(point_user),
+            urlencode(point_name)
+        ))
+            .await?
+            .with_body(dto)
+            .get_result_json()
+            .await
+    }
+
+    pub async fn delete_repo_share(
+        &self,
+        point_user: &str,
+        point_name: &str,
+        user: &str,
+    ) -> Result<(), NeptisError> {
+        self.require(Feature::Shares).await?;
+        self.delete(format!(
+            "/repos/{}/{}/shares{}",
+            urlencode(point_user),
+            urlencode(point_name),
+            Self::share_query(Some(user))
+        ))
+        .await?
+        .get_success()
+        .await
+    }
+
     pub async fn browse_file(&self, path: &str) -> Result<Vec<NodeDto>, NeptisError> {
         self.post("/mounts/browse")
             .await?
+            .with_idempotent(true)
             .with_body(path.to_string())
             .get_result_json()
             .await
@@ -888,6 +1944,123 @@ impl WebApi {
         .await
     }
 
+    pub async fn dump_file_bytes(
+        &self,
+        path: &str,
+        o: u64,
+        n: usize,
+    ) -> Result<Vec<u8>, NeptisError> {
+        let ret = self.dump_file(path, Some(o), Some(n)).await?;
+        STANDARD
+            .decode(ret)
+            .map_err(|_| NeptisError::Str("Failed to decode file chunk!".into()))
+    }
+
+    /// Streams a file as successive `o`/`n` windows of `chunk_size` bytes, starting at `offset`.
+    /// The stream ends once the server returns a short (or empty) window, so a partial
+    /// download can be resumed by passing the number of bytes already received as `offset`.
+    pub fn dump_file_stream<'a>(
+        &'a self,
+        path: &'a str,
+        offset: u64,
+        chunk_size: usize,
+    ) -> impl Stream<Item = Result<Vec<u8>, NeptisError>> + 'a {
+        stream::try_unfold(Some(offset), move |state| async move {
+            let Some(o) = state else {
+                return Ok(None);
+            };
+            let chunk = self.dump_file_bytes(path, o, chunk_size).await?;
+            if chunk.is_empty() {
+                return Ok(None);
+            }
+            let next = if chunk.len() < chunk_size {
+                None
+            } else {
+                Some(o + chunk.len() as u64)
+            };
+            Ok(Some((chunk, next)))
+        })
+    }
+
+    /// Returns the size of a remote file, or `None` if it does not exist yet.
+    pub async fn get_file_size(&self, path: &str) -> Result<Option<u64>, NeptisError> {
+        let parent = path
+            .rsplit_once('/')
+            .map(|x| x.0)
+            .filter(|x| !x.is_empty())
+            .unwrap_or("/");
+        Ok(self
+            .browse_file(parent)
+            .await?
+            .into_iter()
+            .find(|x| !x.is_dir && x.path == path)
+            .map(|x| x.bytes))
+    }
+
+    /// Uploads `reader` to `path` in offset-addressed chunks of `chunk_size` bytes. Each chunk
+    /// is retried according to the [`RetryPolicy`]. When `resume` is set, the upload continues
+    /// from the size the server currently reports for the file, which is the last confirmed
+    /// offset. Returns the final size.
+    pub async fn upload_file<R: Read + Seek>(
+        &self,
+        path: &str,
+        mut reader: R,
+        resume: bool,
+        chunk_size: usize,
+        mut on_progress: impl FnMut(u64),
+    ) -> Result<u64, NeptisError> {
+        let total = reader.seek(SeekFrom::End(0))?;
+        let mut offset = match self.get_file_size(path).await? {
+            Some(size) if resume && size <= total => size,
+            Some(_) => 0,
+            None => {
+                self.post_file(PostForFileApi {
+                    path: path.to_string(),
+                    is_dir: false,
+                    base64: None,
+                    offset: None,
+                })
+                .await?;
+                0
+            }
+        };
+
+        // Drop anything past the confirmed offset, since a half-written chunk cannot be trusted.
+        self.put_file(PutForFileApi {
+            path: path.to_string(),
+            base64: None,
+            new_path: None,
+            atime: None,
+            mtime: None,
+            offset: None,
+            t_len: Some(offset),
+        })
+        .await?;
+        on_progress(offset);
+
+        reader.seek(SeekFrom::Start(offset))?;
+        loop {
+            let mut buf = Vec::with_capacity(chunk_size);
+            (&mut reader).take(chunk_size as u64).read_to_end(&mut buf)?;
+            if buf.is_empty() {
+                break;
+            }
+            let dto = PutForFileApi {
+                path: path.to_string(),
+                base64: Some(STANDARD.encode(&buf)),
+                new_path: None,
+                atime: None,
+                mtime: None,
+                offset: Some(offset),
+                t_len: None,
+            };
+            self.put_file(dto).await?;
+            offset += buf.len() as u64;
+            on_progress(offset);
+        }
+        Ok(offset)
+    }
+
     pub async fn put_file(&self, dto: PutForFileApi) -> Result<(), NeptisError> {
         self.put("/mounts/file")
             .await?
@@ -912,6 +2085,34 @@ impl WebApi {
             .await
     }
 
+    /// Fetches the server version and works out which features it supports. The result is
+    /// cached and used to reject calls the server cannot handle, instead of failing with 404.
+    pub async fn negotiate(&self) -> Result<Capabilities, NeptisError> {
+        let caps = Capabilities::from_info(&self.get_info().await?);
+        *self.config.capabilities.write().await = Some(caps.clone());
+        Ok(caps)
+    }
+
+    /// Returns the negotiated capabilities, negotiating first if needed.
+    pub async fn capabilities(&self) -> Result<Capabilities, NeptisError> {
+        if let Some(ref caps) = *self.config.capabilities.read().await {
+            return Ok(caps.clone());
+        }
+        self.negotiate().await
+    }
+
+    /// Fails when negotiation found the server lacks `feature`. Nothing is checked before
+    /// [`WebApi::negotiate`] has run, so clients which never negotiate behave as before.
+    async fn require(&self, feature: Feature) -> Result<(), NeptisError> {
+        match *self.config.capabilities.read().await {
+            Some(ref caps) if !caps.supports(feature) => Err(NeptisError::Str(format!(
+                "The server (API {}) does not support {}!",
+                caps.raw_version, feature
+            ))),
+            _ => Ok(()),
+        }
+    }
+
     pub async fn get_info(&self) -> Result<SystemSnapshotDto, NeptisError> {
         self.get("/sys/info").await?.get_result_json().await
     }
@@ -978,6 +2179,50 @@ impl WebApi {
             .await
     }
 
+    pub async fn get_valid_perms(&self) -> Result<Vec<String>, NeptisError> {
+        self.require(Feature::Permissions).await?;
+        self.get("/infos/validperms").await?.get_result_json().await
+    }
+
+    pub async fn get_user_perms(&self, name: &str) -> Result<Vec<UserPermission>, NeptisError> {
+        self.require(Feature::Permissions).await?;
+        self.get(format!("/users/{}/perms", urlencode(name)))
+            .await?
+            .get_result_json()
+            .await
+    }
+
+    /// Sets several permissions at once, granting or revoking each based on `allowed`.
+    pub async fn put_user_perms(
+        &self,
+        name: &str,
+        perms: Vec<UserPermissionDto>,
+    ) -> Result<Vec<UserPermission>, NeptisError> {
+        self.require(Feature::Permissions).await?;
+        self.put(format!("/users/{}/perms", urlencode(name)))
+            .await?
+            .with_body(perms)
+            .get_result_json()
+            .await
+    }
+
+    pub async fn grant_user_perm(&self, name: &str, perm: &str) -> Result<(), NeptisError> {
+        self.require(Feature::Permissions).await?;
+        self.post(format!("/users/{}/perms/{}", urlencode(name), urlencode(perm)))
+            .await?
+            .with_idempotent(true)
+            .get_success()
+            .await
+    }
+
+    pub async fn revoke_user_perm(&self, name: &str, perm: &str) -> Result<(), NeptisError> {
+        self.require(Feature::Permissions).await?;
+        self.delete(format!("/users/{}/perms/{}", urlencode(name), urlencode(perm)))
+            .await?
+            .get_success()
+            .await
+    }
+
     pub async fn create_one_user(&self, dto: UserForCreateApi) -> Result<UserDto, NeptisError> {
         self.post("/users")
             .await?
@@ -1027,6 +2272,96 @@ impl WebApi {
             .await
     }
 
+    pub async fn get_global_config(&self) -> Result<DynamicConfigDto, NeptisError> {
+        self.require(Feature::GlobalConfig).await?;
+        self.get("/configs").await?.get_result_json().await
+    }
+
+    /// Updates the global configuration. Fields left as `None` are untouched; fields set to
+    /// `Some(None)` are only cleared when `overwrite_nulls` is set.
+    pub async fn put_global_config(
+        &self,
+        dto: GlobalConfigPutDto,
+    ) -> Result<DynamicConfigDto, NeptisError> {
+        self.require(Feature::GlobalConfig).await?;
+        self.put("/configs")
+            .await?
+            .with_idempotent(true)
+            .with_body(dto)
+            .get_result_json()
+            .await
+    }
+
+    /// Downloads every log. The server has no paging or filtering for `/logs`, so callers
+    /// should fetch once and page through the result with [`LogFilter::page`].
+    pub async fn get_all_logs(&self) -> Result<Vec<LogItemDto>, NeptisError> {
+        self.require(Feature::Logs).await?;
+        self.get("/logs").await?.get_result_json().await
+    }
+
+    /// Returns the logs matching `filter` that are newer than `last_id`, oldest first. Like
+    /// [`WebApi::get_all_logs`] this downloads the whole table, since the server cannot be
+    /// asked for only the newer logs.
+    pub async fn get_logs_since(
+        &self,
+        filter: &LogFilter,
+        last_id: Option<i64>,
+    ) -> Result<Vec<LogItemDto>, NeptisError> {
+        let mut ret = self
+            .get_all_logs()
+            .await?
+            .into_iter()
+            .filter(|x| last_id.is_none_or(|l| x.id.is_some_and(|id| id > l)))
+            .filter(|x| filter.matches(x))
+            .collect::<Vec<_>>();
+        ret.sort_by_key(|x| x.id);
+        Ok(ret)
+    }
+
+    pub async fn get_one_log(&self, id: i64) -> Result<LogItemDto, NeptisError> {
+        self.require(Feature::Logs).await?;
+        self.get(format!("/logs/{id}"))
+            .await?
+            .get_result_json()
+            .await
+    }
+
+    pub async fn delete_one_log(&self, id: i64) -> Result<(), NeptisError> {
+        self.require(Feature::Logs).await?;
+        self.delete(format!("/logs/{id}"))
+            .await?
+            .get_success()
+            .await
+    }
+
+    pub async fn get_all_notifications(
+        &self,
+        unread_only: bool,
+    ) -> Result<Vec<WsNotificationDto>, NeptisError> {
+        self.require(Feature::Notifications).await?;
+        self.get(format!("/notifications?unreadOnly={unread_only}"))
+            .await?
+            .get_result_json()
+            .await
+    }
+
+    /// Returns one notification, which the server then marks as read.
+    pub async fn get_one_notification(&self, id: Uuid) -> Result<WsNotificationDto, NeptisError> {
+        self.require(Feature::Notifications).await?;
+        self.get(format!("/notifications/{id}"))
+            .await?
+            .get_result_json()
+            .await
+    }
+
+    pub async fn delete_one_notification(&self, id: Uuid) -> Result<(), NeptisError> {
+        self.require(Feature::Notifications).await?;
+        self.delete(format!("/notifications/{id}"))
+            .await?
+            .get_success()
+            .await
+    }
+
     pub async fn get_all_messages(&self, new_only: bool) -> Result<Vec<Message>, NeptisError> {
         self.get(format!("/messages?new={new_only}"))
             .await?
@@ -1061,6 +2396,58 @@ impl Default for WebApiConfig {
                 .expect("Failed to build client!"),
             secret: None,
             auth: RwLock::new(None),
+            retry: RetryPolicy::default(),
+            capabilities: RwLock::new(None),
+            envelope_policy: EnvelopePolicy::default(),
+            envelope: Mutex::new(None),
+            envelope_pinned: AtomicBool::new(false),
+            clock_offset: AtomicI64::new(0),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use EnvelopeVersion::{AesGcm, ChaCha20Poly1305, LegacyCbc};
+
+    fn config(policy: EnvelopePolicy) -> WebApiConfig {
+        WebApiConfig {
+            secret: RollingSecret::generate(),
+            envelope_policy: policy,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn legacy_answers_need_legacy_allowed() {
+        let config = config(EnvelopePolicy::default());
+        assert!(config.accept_envelope(None).is_err());
+        assert!(config.accept_envelope(Some(LegacyCbc)).is_err());
+
+        let config = self::config(EnvelopePolicy::legacy());
+        assert_eq!(config.accept_envelope(None).unwrap(), LegacyCbc);
+    }
+
+    #[test]
+    fn pinned_envelope_refuses_downgrades() {
+        let config = config(EnvelopePolicy::legacy());
+        assert_eq!(config.accept_envelope(Some(AesGcm)).unwrap(), AesGcm);
+
+        // Before a response has been authenticated, the server can still change its answer.
+        let agreed = config.accept_envelope(Some(ChaCha20Poly1305));
+        assert_eq!(agreed.unwrap(), ChaCha20Poly1305);
+
+        let secret = config.secret.as_ref().unwrap();
+        let aad = envelope_aad(EnvelopePart::Response, "GET", "/api/mounts");
+        let step = config.server_step();
+        let body = secret.seal_at(ChaCha20Poly1305, b"[]", &aad, step).unwrap();
+        let body = STANDARD.encode(body).into_bytes();
+        let body = config.decode_body(body, ChaCha20Poly1305, &Method::GET, "/api/mounts");
+        assert_eq!(body.unwrap(), b"[]");
+
+        assert!(config.accept_envelope(Some(LegacyCbc)).is_err());
+        assert!(config.accept_envelope(Some(AesGcm)).is_err());
+        assert_eq!(config.accept_envelope(None).unwrap(), ChaCha20Poly1305);
+    }
+}
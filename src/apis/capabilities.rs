@@ -0,0 +1,260 @@
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+use super::NeptisError;
+use super::api::SystemSnapshotDto;
+
+/// The major API version this client speaks. Servers with another major version are
+/// treated as incompatible.
+pub const API_MAJOR: u32 = 2;
+
+/// The oldest server this client can talk to.
+pub const MIN_API_VERSION: ApiVersion = ApiVersion::new(API_MAJOR, 0, 0);
+
+/// A server API version, in the `major.minor.patch` form reported by `/sys/info`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ApiVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl ApiVersion {
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        ApiVersion {
+            major,
+            minor,
+            patch,
+        }
+    }
+}
+
+impl FromStr for ApiVersion {
+    type Err = NeptisError;
+
+    /// Parses versions such as `2.0.1`, `v2.1` or `2.0.2-beta+abc`. Missing parts are zero,
+    /// and any pre-release or build suffix is ignored.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || NeptisError::Str(format!("Invalid API version '{s}'"));
+        let core = s.trim().trim_start_matches(['v', 'V']);
+        let core = core.split(['-', '+']).next().unwrap_or_default();
+        let mut parts = core.split('.');
+        let mut next = |required: bool| match parts.next() {
+            Some(x) => x.parse::<u32>().map_err(|_| err()),
+            None if required => Err(err()),
+            None => Ok(0),
+        };
+        let ret = ApiVersion::new(next(true)?, next(false)?, next(false)?);
+        if parts.next().is_some() {
+            return Err(err());
+        }
+        Ok(ret)
+    }
+}
+
+impl Display for ApiVersion {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// An optional part of the server API, which older servers may not provide.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Feature {
+    Permissions,
+    GlobalConfig,
+    Shares,
+    Logs,
+    Prune,
+    Notifications,
+}
+
+impl Feature {
+    pub const ALL: [Feature; 6] = [
+        Feature::Permissions,
+        Feature::GlobalConfig,
+        Feature::Shares,
+        Feature::Logs,
+        Feature::Prune,
+        Feature::Notifications,
+    ];
+
+    /// The name used for this feature in the server's `features` list.
+    pub fn key(&self) -> &'static str {
+        match self {
+            Feature::Permissions => "permissions",
+            Feature::GlobalConfig => "config",
+            Feature::Shares => "shares",
+            Feature::Logs => "logs",
+            Feature::Prune => "prune",
+            Feature::Notifications => "notifications",
+        }
+    }
+}
+
+impl Display for Feature {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Feature::Permissions => "user permissions",
+            Feature::GlobalConfig => "global configuration",
+            Feature::Shares => "point sharing",
+            Feature::Logs => "server logs",
+            Feature::Prune => "snapshot pruning",
+            Feature::Notifications => "notifications",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// What the connected server supports, negotiated from `/sys/info` when connecting.
+///
+/// Servers which list their `features` are trusted as-is. Servers which do not list them predate
+/// the list, so every feature of [`API_MAJOR`] is assumed when they speak it, and none when they
+/// are older. A version which cannot be parsed enables everything, so a server with an unusual
+/// version string is not crippled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Capabilities {
+    /// The version string reported by the server.
+    pub raw_version: String,
+    /// The parsed server version, if it could be parsed.
+    pub version: Option<ApiVersion>,
+    pub permissions: bool,
+    pub global_config: bool,
+    pub shares: bool,
+    pub logs: bool,
+    pub prune: bool,
+    pub notifications: bool,
+}
+
+impl Capabilities {
+    pub fn from_info(info: &SystemSnapshotDto) -> Self {
+        let version = info.api_version.parse::<ApiVersion>().ok();
+        let has = |feature: Feature| match (&info.features, version) {
+            (Some(features), _) => features
+                .iter()
+                .any(|x| x.eq_ignore_ascii_case(feature.key())),
+            (None, Some(v)) => v.major >= API_MAJOR,
+            (None, None) => true,
+        };
+        Capabilities {
+            raw_version: info.api_version.clone(),
+            version,
+            permissions: has(Feature::Permissions),
+            global_config: has(Feature::GlobalConfig),
+            shares: has(Feature::Shares),
+            logs: has(Feature::Logs),
+            prune: has(Feature::Prune),
+            notifications: has(Feature::Notifications),
+        }
+    }
+
+    pub fn supports(&self, feature: Feature) -> bool {
+        match feature {
+            Feature::Permissions => self.permissions,
+            Feature::GlobalConfig => self.global_config,
+            Feature::Shares => self.shares,
+            Feature::Logs => self.logs,
+            Feature::Prune => self.prune,
+            Feature::Notifications => self.notifications,
+        }
+    }
+
+    /// Returns the features the server does not provide.
+    pub fn missing(&self) -> Vec<Feature> {
+        Feature::ALL
+            .into_iter()
+            .filter(|x| !self.supports(*x))
+            .collect()
+    }
+
+    /// Returns `false` when the server speaks another major version, or one older than
+    /// [`MIN_API_VERSION`]. Most calls will fail against such a server.
+    pub fn is_compatible(&self) -> bool {
+        self.version
+            .is_none_or(|v| v.major == API_MAJOR && v >= MIN_API_VERSION)
+    }
+
+    /// Returns a message explaining why the server is incompatible, if it is.
+    pub fn incompatible_reason(&self) -> Option<String> {
+        let v = self.version.filter(|_| !self.is_compatible())?;
+        let client = env!("CARGO_PKG_VERSION");
+        Some(if v.major > API_MAJOR {
+            format!(
+                "The server (API {v}) is newer than this client ({client}). Please update the client."
+            )
+        } else {
+            format!(
+                "The server (API {v}) is too old for this client ({client}). Please update the server to {MIN_API_VERSION} or newer."
+            )
+        })
+    }
+
+    pub fn print_info(&self) {
+        println!(
+            "API Version: {}{}",
+            self.raw_version,
+            if self.is_compatible() {
+                ""
+            } else {
+                " (INCOMPATIBLE)"
+            }
+        );
+        let missing = self.missing();
+        if !missing.is_empty() {
+            println!(
+                "Unsupported Features: {}",
+                missing
+                    .iter()
+                    .map(|x| x.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::state::MockData;
+
+    fn info(version: &str, features: Option<&[&str]>) -> SystemSnapshotDto {
+        let mut ret = MockData::default().system;
+        ret.api_version = version.into();
+        ret.features = features.map(|x| x.iter().map(|x| x.to_string()).collect());
+        ret
+    }
+
+    #[test]
+    fn versions_parse_with_prefix_and_suffix() {
+        let v = "v2.1".parse::<ApiVersion>().unwrap();
+        assert_eq!(v, ApiVersion::new(2, 1, 0));
+        let v = "2.0.2-beta+x".parse::<ApiVersion>().unwrap();
+        assert_eq!(v, ApiVersion::new(2, 0, 2));
+        assert!("2.0.0.1".parse::<ApiVersion>().is_err());
+    }
+
+    #[test]
+    fn listed_features_are_trusted() {
+        let caps = Capabilities::from_info(&info("2.0.0", Some(&["Shares", "logs"])));
+        assert!(caps.shares && caps.logs);
+        assert!(!caps.permissions && !caps.prune && !caps.notifications);
+        assert_eq!(caps.missing().len(), 4);
+    }
+
+    #[test]
+    fn unlisted_features_follow_the_major_version() {
+        let caps = Capabilities::from_info(&info("2.0.0", None));
+        assert!(caps.missing().is_empty());
+        assert!(caps.is_compatible());
+
+        let caps = Capabilities::from_info(&info("1.4.0", None));
+        assert_eq!(caps.missing().len(), Feature::ALL.len());
+        assert!(!caps.is_compatible());
+
+        let caps = Capabilities::from_info(&info("unknown", None));
+        assert_eq!(caps.version, None);
+        assert!(caps.missing().is_empty());
+        assert!(caps.is_compatible());
+    }
+}
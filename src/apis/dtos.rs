@@ -4,12 +4,13 @@ use std::{
     str::FromStr,
 };
 
-use chrono::{DateTime, Local, NaiveDateTime};
+use chrono::{DateTime, Local, NaiveDateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
+use crate::models::{DynamicConfigDto, FileDto, GlobalConfigPutDto, LogItemDto};
 use crate::traits::ToShortIdString;
 
-use super::{NeptisError, api::PointUsage};
+use super::{NeptisError, api::PointUsage, retention::RetentionPolicy, urlencode};
 
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
@@ -18,6 +19,7 @@ pub enum AutoJobType {
     Unknown,
     Backup,
     Check,
+    Prune,
 }
 
 impl FromStr for AutoJobType {
@@ -26,6 +28,7 @@ impl FromStr for AutoJobType {
         match s.to_lowercase().as_str() {
             "backup" => Ok(AutoJobType::Backup),
             "check" => Ok(AutoJobType::Check),
+            "prune" => Ok(AutoJobType::Prune),
             _ => Err(()),
         }
     }
@@ -36,6 +39,7 @@ impl ToString for AutoJobType {
         match self {
             AutoJobType::Backup => "Backup",
             AutoJobType::Check => "Check",
+            AutoJobType::Prune => "Prune",
             AutoJobType::Unknown => "Unknown",
         }
         .into()
@@ -133,7 +137,15 @@ pub struct MountDto {
     pub data_accessed: NaiveDateTime,
     pub repo_accessed: NaiveDateTime,
 }
-#[derive(Serialize, Deserialize, Clone, Default)]
+/// A point owned by another user which has been shared with this user.
+#[derive(Clone, Debug)]
+pub struct SharedMount {
+    pub owned_by: String,
+    pub name: String,
+    pub can_write: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
 pub struct RepoJobDto {
     pub id: Uuid,
     pub title: Option<String>,
@@ -209,6 +221,8 @@ pub struct AutoJobDto {
     pub date_modified: NaiveDateTime,
     pub date_last_ran: Option<NaiveDateTime>,
     pub job_type: AutoJobType,
+    /// The policy applied by `Prune` jobs.
+    pub retention: Option<RetentionPolicy>,
 }
 
 impl ToShortIdString for AutoJobDto {
@@ -233,12 +247,44 @@ pub struct NodeDto {
     pub bytes: u64,
 }
 
+impl NodeDto {
+    /// Converts an entry from the repository browser, making its path relative to `root`.
+    pub(crate) fn from_repo_file(dto: FileDto, root: &str) -> Option<NodeDto> {
+        fn parse_date(x: Option<String>) -> NaiveDateTime {
+            x.and_then(|x| {
+                DateTime::parse_from_rfc3339(&x)
+                    .map(|x| x.naive_utc())
+                    .or_else(|_| NaiveDateTime::parse_from_str(&x, "%Y-%m-%dT%H:%M:%S%.f"))
+                    .ok()
+            })
+            .unwrap_or_default()
+        }
+
+        let full = dto.path.flatten()?;
+        let rel = full.strip_prefix(root).unwrap_or(&full).trim_end_matches('/');
+        let path = if rel.starts_with('/') {
+            rel.to_string()
+        } else {
+            format!("/{}", rel)
+        };
+        Some(NodeDto {
+            path,
+            atime: parse_date(dto.access_date),
+            ctime: parse_date(dto.create_date),
+            mtime: parse_date(dto.modify_date),
+            is_dir: dto.is_directory.unwrap_or(false),
+            bytes: dto.size_bytes.flatten().unwrap_or(0).max(0) as u64,
+        })
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct PutForAutoJobWebApi {
     pub task_name: String,
     pub cron_schedule: String,
     pub job_type: AutoJobType,
     pub enabled: bool,
+    pub retention: Option<RetentionPolicy>,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -296,20 +342,118 @@ pub struct PutForMountApi {
     pub repo_bytes: i64,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone, Default)]
 pub struct PostForBackupApi {
     pub point_user: String,
     pub point_name: String,
     pub tags: Option<Vec<String>>,
     pub dry_run: bool,
+    pub label: Option<String>,
+    pub description: Option<String>,
 }
 
-#[derive(Serialize, Deserialize)]
+impl PostForBackupApi {
+    pub fn new(point_user: impl Into<String>, point_name: impl Into<String>) -> Self {
+        PostForBackupApi {
+            point_user: point_user.into(),
+            point_name: point_name.into(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Changes the metadata of a snapshot. Fields left as `None` are not changed.
+#[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq)]
+pub struct PutForSnapshotApi {
+    pub label: Option<String>,
+    pub description: Option<String>,
+    pub tags: Option<Vec<String>>,
+}
+
+impl PutForSnapshotApi {
+    pub fn is_empty(&self) -> bool {
+        *self == PutForSnapshotApi::default()
+    }
+
+    /// Builds a request which only contains the fields that differ between the snapshots.
+    pub fn from_changes(old: &SnapshotFileDto, new: &SnapshotFileDto) -> Self {
+        PutForSnapshotApi {
+            label: (old.label != new.label).then(|| new.label.clone()),
+            description: (old.description != new.description)
+                .then(|| new.description.clone().unwrap_or_default()),
+            tags: (old.tags != new.tags).then(|| new.tags.clone()),
+        }
+    }
+
+    /// Trims the tags and removes any empty or repeated ones, keeping their order.
+    pub fn clean_tags<T: AsRef<str>>(tags: impl IntoIterator<Item = T>) -> Vec<String> {
+        let mut ret: Vec<String> = vec![];
+        for tag in tags {
+            let tag = tag.as_ref().trim();
+            if !tag.is_empty() && !ret.iter().any(|x| x.eq_ignore_ascii_case(tag)) {
+                ret.push(tag.to_string());
+            }
+        }
+        ret
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
 pub struct PostForRestoreApi {
     pub point_user: String,
     pub point_name: String,
     pub snapshot: String,
     pub dry_run: bool,
+    /// Only restore these paths from the snapshot, or everything if `None`.
+    pub paths: Option<Vec<String>>,
+    /// Restore into this user's point instead of the original one.
+    pub target_user: Option<String>,
+    /// Restore into this point instead of the original one.
+    pub target_name: Option<String>,
+}
+
+impl PostForRestoreApi {
+    pub fn new(
+        point_user: impl Into<String>,
+        point_name: impl Into<String>,
+        snapshot: impl Into<String>,
+    ) -> Self {
+        PostForRestoreApi {
+            point_user: point_user.into(),
+            point_name: point_name.into(),
+            snapshot: snapshot.into(),
+            ..Default::default()
+        }
+    }
+}
+
+/// The output of a dry-run restore job.
+///
+/// The server only reports free-form job messages for a restore, not a structured list of the
+/// files it would change, so these are the raw messages rather than an exact diff.
+#[derive(Debug, Clone, Default)]
+pub struct RestorePreview {
+    pub messages: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+impl RestorePreview {
+    pub fn from_job(job: &RepoJobDto) -> Self {
+        RestorePreview {
+            messages: job
+                .messages
+                .iter()
+                .map(|x| x.trim())
+                .filter(|x| !x.is_empty())
+                .map(|x| x.to_string())
+                .collect(),
+            errors: job.errors.clone(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty() && self.errors.is_empty()
+    }
 }
 
 #[derive(Serialize, Deserialize)]
@@ -351,9 +495,14 @@ pub struct SnapshotFileDto {
 impl ToShortIdString for SnapshotFileDto {
     fn to_short_id_string(&self) -> String {
         format!(
-            "{}{} on {}",
+            "{}{}{} on {}",
             self.id.to_string(),
             if self.locked { " <LOCKED> " } else { "" },
+            if self.tags.is_empty() {
+                String::new()
+            } else {
+                format!(" [{}]", self.tags.join(", "))
+            },
             self.summary
                 .clone()
                 .map(|x| x
@@ -618,3 +767,494 @@ impl ToShortIdString for Message {
         )
     }
 }
+
+impl ToShortIdString for LogItemDto {
+    fn to_short_id_string(&self) -> String {
+        format!(
+            "{} [{}] {}: {}",
+            self.date()
+                .map(|x| x.with_timezone(&Local).format("%Y-%m-%d %I:%M:%S %p").to_string())
+                .unwrap_or("N/A".into()),
+            self.category.clone().flatten().unwrap_or("N/A".into()),
+            self.user_name
+                .clone()
+                .flatten()
+                .unwrap_or("SYSTEM".into()),
+            self.message
+                .clone()
+                .flatten()
+                .unwrap_or_default()
+                .lines()
+                .next()
+                .unwrap_or_default()
+        )
+    }
+}
+
+impl LogItemDto {
+    /// Parses `log_date`, which the server sends as either RFC 3339 or a naive UTC time.
+    pub fn date(&self) -> Option<DateTime<Utc>> {
+        let raw = self.log_date.as_deref()?;
+        DateTime::parse_from_rfc3339(raw)
+            .map(|x| x.with_timezone(&Utc))
+            .ok()
+            .or_else(|| {
+                NaiveDateTime::from_str(raw)
+                    .ok()
+                    .map(|x| x.and_utc())
+            })
+    }
+}
+
+/// Client-side filter applied to the server logs, as `/logs` does not accept any queries.
+#[derive(Clone, Debug, Default)]
+pub struct LogFilter {
+    pub category: Option<String>,
+    pub user_name: Option<String>,
+    pub start: Option<DateTime<Utc>>,
+    pub end: Option<DateTime<Utc>>,
+}
+
+impl LogFilter {
+    pub fn matches(&self, item: &LogItemDto) -> bool {
+        fn eq_opt(want: &Option<String>, have: &Option<Option<String>>) -> bool {
+            match want {
+                Some(w) => have
+                    .as_ref()
+                    .and_then(|x| x.as_ref())
+                    .is_some_and(|x| x.eq_ignore_ascii_case(w)),
+                None => true,
+            }
+        }
+        if !eq_opt(&self.category, &item.category) || !eq_opt(&self.user_name, &item.user_name) {
+            return false;
+        }
+        if self.start.is_none() && self.end.is_none() {
+            return true;
+        }
+        match item.date() {
+            Some(date) => {
+                self.start.is_none_or(|x| date >= x) && self.end.is_none_or(|x| date <= x)
+            }
+            None => false,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.category.is_none()
+            && self.user_name.is_none()
+            && self.start.is_none()
+            && self.end.is_none()
+    }
+
+    /// Returns one page of the logs matching this filter, newest first.
+    pub fn page(&self, logs: &[LogItemDto], n: usize, o: usize) -> Vec<LogItemDto> {
+        let mut ret = logs
+            .iter()
+            .filter(|x| self.matches(x))
+            .cloned()
+            .collect::<Vec<_>>();
+        ret.sort_by_key(|x| std::cmp::Reverse(x.id));
+        ret.into_iter().skip(o).take(n).collect()
+    }
+}
+
+impl Display for LogFilter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "None");
+        }
+        let fmt_date = |x: &Option<DateTime<Utc>>| {
+            x.map(|d| d.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or("*".into())
+        };
+        write!(
+            f,
+            "Category: {}, User: {}, Dates: {} to {}",
+            self.category.as_deref().unwrap_or("*"),
+            self.user_name.as_deref().unwrap_or("*"),
+            fmt_date(&self.start),
+            fmt_date(&self.end)
+        )
+    }
+}
+
+/// Filter for the job listing. The point, status, type and dates are sent to the server, and
+/// everything is checked again on the client in case the server ignores a query.
+#[derive(Clone, Debug, Default)]
+pub struct JobFilter {
+    pub point: Option<String>,
+    pub status: Option<JobStatus>,
+    pub job_type: Option<JobType>,
+    pub start: Option<DateTime<Utc>>,
+    pub end: Option<DateTime<Utc>>,
+    /// Text matched against the ID, title, point and snapshot of a job. Client-side only.
+    pub search: Option<String>,
+}
+
+impl JobFilter {
+    pub fn matches(&self, job: &RepoJobDto) -> bool {
+        if self
+            .point
+            .as_ref()
+            .is_some_and(|x| !x.eq_ignore_ascii_case(&job.point_name))
+            || self.status.is_some_and(|x| x != job.job_status)
+            || self.job_type.is_some_and(|x| x != job.job_type)
+        {
+            return false;
+        }
+        let date = job.create_date.and_utc();
+        if self.start.is_some_and(|x| date < x) || self.end.is_some_and(|x| date > x) {
+            return false;
+        }
+        match self.search.as_deref().map(|x| x.to_lowercase()) {
+            Some(text) => [
+                Some(job.id.to_string()),
+                job.title.clone(),
+                Some(job.point_name.clone()),
+                job.snapshot_id.clone(),
+            ]
+            .into_iter()
+            .flatten()
+            .any(|x| x.to_lowercase().contains(&text)),
+            None => true,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.point.is_none()
+            && self.status.is_none()
+            && self.job_type.is_none()
+            && self.start.is_none()
+            && self.end.is_none()
+            && self.search.is_none()
+    }
+
+    /// Returns the server-side filters as query parameters, each starting with `&`.
+    pub(crate) fn to_query(&self) -> String {
+        let mut ret = String::new();
+        if let Some(ref point) = self.point {
+            ret += &format!("&point={}", urlencode(point));
+        }
+        if let Some(status) = self.status {
+            ret += &format!("&status={:?}", status);
+        }
+        if let Some(job_type) = self.job_type {
+            ret += &format!("&type={:?}", job_type);
+        }
+        if let Some(start) = self.start {
+            ret += &format!("&start={}", urlencode(start.to_rfc3339()));
+        }
+        if let Some(end) = self.end {
+            ret += &format!("&end={}", urlencode(end.to_rfc3339()));
+        }
+        ret
+    }
+}
+
+impl Display for JobFilter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "None");
+        }
+        let fmt_date = |x: &Option<DateTime<Utc>>| {
+            x.map(|d| d.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or("*".into())
+        };
+        write!(
+            f,
+            "Point: {}, Status: {}, Type: {}, Dates: {} to {}, Search: {}",
+            self.point.as_deref().unwrap_or("*"),
+            self.status.map(|x| x.to_string()).unwrap_or("*".into()),
+            self.job_type.map(|x| x.to_string()).unwrap_or("*".into()),
+            fmt_date(&self.start),
+            fmt_date(&self.end),
+            self.search.as_deref().unwrap_or("*")
+        )
+    }
+}
+
+/// The editable fields of the server's global configuration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigField {
+    RepoBaseDirectory,
+    DataBaseDirectory,
+    LogBaseDirectory,
+    MountBaseDirectory,
+    ServerSmtpUrl,
+    ServerEmailAddress,
+    ServerEmailPassword,
+    AuthMins,
+    MaxBytesPerUser,
+    MaxRequestsPerUser,
+    RateLimitResetMins,
+}
+
+impl ConfigField {
+    pub const ALL: [ConfigField; 11] = [
+        ConfigField::RepoBaseDirectory,
+        ConfigField::DataBaseDirectory,
+        ConfigField::LogBaseDirectory,
+        ConfigField::MountBaseDirectory,
+        ConfigField::ServerSmtpUrl,
+        ConfigField::ServerEmailAddress,
+        ConfigField::ServerEmailPassword,
+        ConfigField::AuthMins,
+        ConfigField::MaxBytesPerUser,
+        ConfigField::MaxRequestsPerUser,
+        ConfigField::RateLimitResetMins,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ConfigField::RepoBaseDirectory => "Repo Base Directory",
+            ConfigField::DataBaseDirectory => "Data Base Directory",
+            ConfigField::LogBaseDirectory => "Log Base Directory",
+            ConfigField::MountBaseDirectory => "Mount Base Directory",
+            ConfigField::ServerSmtpUrl => "SMTP URL",
+            ConfigField::ServerEmailAddress => "Email Address",
+            ConfigField::ServerEmailPassword => "Email Password",
+            ConfigField::AuthMins => "Auth Minutes",
+            ConfigField::MaxBytesPerUser => "Max Bytes Per User",
+            ConfigField::MaxRequestsPerUser => "Max Requests Per User",
+            ConfigField::RateLimitResetMins => "Rate Limit Reset Minutes",
+        }
+    }
+
+    pub fn is_secret(&self) -> bool {
+        *self == ConfigField::ServerEmailPassword
+    }
+
+    pub fn is_numeric(&self) -> bool {
+        matches!(
+            self,
+            ConfigField::AuthMins
+                | ConfigField::MaxBytesPerUser
+                | ConfigField::MaxRequestsPerUser
+                | ConfigField::RateLimitResetMins
+        )
+    }
+
+    /// Checks a single non-empty value before it is applied.
+    pub fn validate(&self, value: &str) -> Result<(), NeptisError> {
+        let bad = |msg: &str| Err(NeptisError::Str(format!("{}: {}", self.name(), msg)));
+        match self {
+            ConfigField::RepoBaseDirectory
+            | ConfigField::DataBaseDirectory
+            | ConfigField::LogBaseDirectory
+            | ConfigField::MountBaseDirectory => {
+                if !value.starts_with('/') && !std::path::Path::new(value).is_absolute() {
+                    return bad("the directory must be an absolute path");
+                }
+            }
+            ConfigField::ServerSmtpUrl => match url::Url::parse(value) {
+                Ok(x) if x.host_str().is_some() => {}
+                _ => return bad("the URL is invalid (ex. smtps://smtp.example.com:465)"),
+            },
+            ConfigField::ServerEmailAddress => {
+                let mut parts = value.split('@');
+                if !matches!(
+                    (parts.next(), parts.next(), parts.next()),
+                    (Some(a), Some(b), None) if !a.is_empty() && b.contains('.')
+                ) {
+                    return bad("the email address is invalid");
+                }
+            }
+            ConfigField::ServerEmailPassword => {}
+            ConfigField::AuthMins => match value.parse::<i32>() {
+                Ok(x) if x > 0 => {}
+                _ => return bad("must be a whole number above zero"),
+            },
+            ConfigField::RateLimitResetMins => match value.parse::<i64>() {
+                Ok(x) if x > 0 => {}
+                _ => return bad("must be a whole number above zero"),
+            },
+            ConfigField::MaxBytesPerUser | ConfigField::MaxRequestsPerUser => {
+                match value.parse::<i64>() {
+                    Ok(x) if x >= 0 => {}
+                    _ => return bad("must be a whole number of zero or more"),
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl DynamicConfigDto {
+    pub fn get_field(&self, field: ConfigField) -> Option<String> {
+        match field {
+            ConfigField::RepoBaseDirectory => self.repo_base_directory.clone().flatten(),
+            ConfigField::DataBaseDirectory => self.data_base_directory.clone().flatten(),
+            ConfigField::LogBaseDirectory => self.log_base_directory.clone().flatten(),
+            ConfigField::MountBaseDirectory => self.mount_base_directory.clone().flatten(),
+            ConfigField::ServerSmtpUrl => self.server_smtp_url.clone().flatten(),
+            ConfigField::ServerEmailAddress => self.server_email_address.clone().flatten(),
+            ConfigField::ServerEmailPassword => self.server_email_password.clone().flatten(),
+            ConfigField::AuthMins => self.auth_mins.flatten().map(|x| x.to_string()),
+            ConfigField::MaxBytesPerUser => self.max_bytes_per_user.flatten().map(|x| x.to_string()),
+            ConfigField::MaxRequestsPerUser => {
+                self.max_requests_per_user.flatten().map(|x| x.to_string())
+            }
+            ConfigField::RateLimitResetMins => {
+                self.rate_limit_reset_mins.flatten().map(|x| x.to_string())
+            }
+        }
+    }
+
+    /// Validates and sets a field. A `None` value clears it.
+    pub fn set_field(&mut self, field: ConfigField, value: Option<&str>) -> Result<(), NeptisError> {
+        let value = value.map(|x| x.trim()).filter(|x| !x.is_empty());
+        if let Some(v) = value {
+            field.validate(v)?;
+        }
+        let s = || Some(value.map(|x| x.to_string()));
+        let n = || Some(value.and_then(|x| x.parse::<i64>().ok()));
+        match field {
+            ConfigField::RepoBaseDirectory => self.repo_base_directory = s(),
+            ConfigField::DataBaseDirectory => self.data_base_directory = s(),
+            ConfigField::LogBaseDirectory => self.log_base_directory = s(),
+            ConfigField::MountBaseDirectory => self.mount_base_directory = s(),
+            ConfigField::ServerSmtpUrl => self.server_smtp_url = s(),
+            ConfigField::ServerEmailAddress => self.server_email_address = s(),
+            ConfigField::ServerEmailPassword => self.server_email_password = s(),
+            ConfigField::AuthMins => self.auth_mins = Some(value.and_then(|x| x.parse().ok())),
+            ConfigField::MaxBytesPerUser => self.max_bytes_per_user = n(),
+            ConfigField::MaxRequestsPerUser => self.max_requests_per_user = n(),
+            ConfigField::RateLimitResetMins => self.rate_limit_reset_mins = n(),
+        }
+        Ok(())
+    }
+
+    /// Returns the value for display, masking secrets.
+    pub fn display_field(&self, field: ConfigField) -> String {
+        match self.get_field(field) {
+            Some(_) if field.is_secret() => "********".into(),
+            Some(x) => x,
+            None => "(not set)".into(),
+        }
+    }
+
+    /// Validates every field that is currently set.
+    pub fn validate(&self) -> Result<(), NeptisError> {
+        for field in ConfigField::ALL {
+            if let Some(v) = self.get_field(field) {
+                field.validate(&v)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the `(field, old, new)` display values for every field that differs from `old`.
+    pub fn diff(&self, old: &DynamicConfigDto) -> Vec<(ConfigField, String, String)> {
+        ConfigField::ALL
+            .into_iter()
+            .filter(|f| self.get_field(*f) != old.get_field(*f))
+            .map(|f| {
+                let (o, n) = (old.display_field(f), self.display_field(f));
+                if f.is_secret() && old.get_field(f).is_some() && self.get_field(f).is_some() {
+                    (f, o, "******** (changed)".into())
+                } else {
+                    (f, o, n)
+                }
+            })
+            .collect()
+    }
+}
+
+impl GlobalConfigPutDto {
+    /// Builds a request that only touches the fields changed between `old` and `new`.
+    ///
+    /// Unchanged fields are omitted. Cleared fields are sent as `null` with
+    /// `overwrite_nulls` set, as the server otherwise treats `null` as "keep". Since
+    /// `overwrite_nulls` applies to the whole request, every current value is sent with it so
+    /// nothing else is cleared by omission.
+    pub fn from_changes(old: &DynamicConfigDto, new: &DynamicConfigDto) -> Self {
+        let clears = ConfigField::ALL
+            .into_iter()
+            .any(|f| old.get_field(f).is_some() && new.get_field(f).is_none());
+        let pick_field = |f: ConfigField| {
+            let n = new.get_field(f);
+            (clears || old.get_field(f) != n).then_some(n)
+        };
+        let repo_base_directory = pick_field(ConfigField::RepoBaseDirectory);
+        let data_base_directory = pick_field(ConfigField::DataBaseDirectory);
+        let log_base_directory = pick_field(ConfigField::LogBaseDirectory);
+        let mount_base_directory = pick_field(ConfigField::MountBaseDirectory);
+        let server_smtp_url = pick_field(ConfigField::ServerSmtpUrl);
+        let server_email_address = pick_field(ConfigField::ServerEmailAddress);
+        let server_email_password = pick_field(ConfigField::ServerEmailPassword);
+        let auth_mins = pick_field(ConfigField::AuthMins);
+        let max_bytes_per_user = pick_field(ConfigField::MaxBytesPerUser);
+        let max_requests_per_user = pick_field(ConfigField::MaxRequestsPerUser);
+        let rate_limit_reset_mins = pick_field(ConfigField::RateLimitResetMins);
+        let num = |x: Option<Option<String>>| x.map(|v| v.and_then(|s| s.parse::<i64>().ok()));
+        GlobalConfigPutDto {
+            repo_base_directory,
+            data_base_directory,
+            log_base_directory,
+            mount_base_directory,
+            server_smtp_url,
+            server_email_address,
+            server_email_password,
+            auth_mins: auth_mins.map(|v| v.and_then(|s| s.parse::<i32>().ok())),
+            max_bytes_per_user: num(max_bytes_per_user),
+            max_requests_per_user: num(max_requests_per_user),
+            rate_limit_reset_mins: num(rate_limit_reset_mins),
+            overwrite_nulls: Some(clears),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> DynamicConfigDto {
+        let mut ret = DynamicConfigDto::default();
+        ret.set_field(ConfigField::RepoBaseDirectory, Some("/srv/repos"))
+            .unwrap();
+        ret.set_field(
+            ConfigField::ServerSmtpUrl,
+            Some("smtps://smtp.example.com:465"),
+        )
+        .unwrap();
+        ret.set_field(ConfigField::AuthMins, Some("30")).unwrap();
+        ret
+    }
+
+    #[test]
+    fn config_changes_only_send_changed_fields() {
+        let old = config();
+        let mut new = old.clone();
+        new.set_field(ConfigField::AuthMins, Some("45")).unwrap();
+        let json = serde_json::to_value(GlobalConfigPutDto::from_changes(&old, &new)).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({ "authMins": 45, "overwriteNulls": false })
+        );
+    }
+
+    #[test]
+    fn config_clears_send_every_current_value() {
+        let old = config();
+        let mut new = old.clone();
+        new.set_field(ConfigField::ServerSmtpUrl, None).unwrap();
+        let json = serde_json::to_value(GlobalConfigPutDto::from_changes(&old, &new)).unwrap();
+        assert_eq!(json["overwriteNulls"], true);
+        assert_eq!(json["serverSmtpUrl"], serde_json::Value::Null);
+        assert_eq!(json["repoBaseDirectory"], "/srv/repos");
+        assert_eq!(json["authMins"], 30);
+        for field in [
+            "dataBaseDirectory",
+            "logBaseDirectory",
+            "mountBaseDirectory",
+            "serverEmailAddress",
+            "serverEmailPassword",
+            "maxBytesPerUser",
+            "maxRequestsPerUser",
+            "rateLimitResetMins",
+        ] {
+            assert_eq!(json.get(field), Some(&serde_json::Value::Null), "{field}");
+        }
+    }
+}
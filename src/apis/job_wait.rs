@@ -0,0 +1,169 @@
+use std::error;
+use std::fmt::{self, Display, Formatter};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use super::NeptisError;
+use super::dtos::{JobStatus, RepoJobDto};
+
+/// The longest single sleep while waiting, so cancellation is noticed quickly.
+pub(crate) const WAIT_SLICE: Duration = Duration::from_millis(250);
+
+/// A snapshot of a job's progress, passed to the callback after every poll.
+#[derive(Clone, Debug)]
+pub struct JobProgress {
+    pub status: JobStatus,
+    pub used_bytes: u64,
+    pub total_bytes: Option<u64>,
+    /// The errors reported by the job since the previous poll.
+    pub new_errors: Vec<String>,
+    pub elapsed: Duration,
+}
+
+impl JobProgress {
+    /// Returns how much of the job is done, from 0 to 1, if the total size is known.
+    pub fn fraction(&self) -> Option<f64> {
+        self.total_bytes
+            .filter(|x| *x > 0)
+            .map(|total| (self.used_bytes as f64 / total as f64).clamp(0.0, 1.0))
+    }
+}
+
+/// Controls how [`WebApi::wait_for_job`](super::api::WebApi::wait_for_job) polls a job.
+pub struct JobWaitOptions<'a> {
+    /// The delay between polls.
+    pub poll_interval: Duration,
+    /// The delay before the first poll, giving the server time to set the job up.
+    pub start_delay: Duration,
+    /// Gives up once this much time has passed. `None` waits forever.
+    pub timeout: Option<Duration>,
+    /// How many polls in a row may fail before giving up.
+    pub max_poll_errors: usize,
+    /// How many errors the job itself may report before it is treated as failed. `None`
+    /// only fails once the server marks the job as failed.
+    pub max_job_errors: Option<usize>,
+    /// Stops waiting as soon as this is set. The job keeps running on the server.
+    pub cancel: Option<Arc<AtomicBool>>,
+    pub on_progress: Option<Box<dyn FnMut(&JobProgress) + 'a>>,
+}
+
+impl Default for JobWaitOptions<'_> {
+    fn default() -> Self {
+        JobWaitOptions {
+            poll_interval: Duration::from_secs(2),
+            start_delay: Duration::ZERO,
+            timeout: None,
+            max_poll_errors: 5,
+            max_job_errors: None,
+            cancel: None,
+            on_progress: None,
+        }
+    }
+}
+
+impl<'a> JobWaitOptions<'a> {
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    pub fn with_start_delay(mut self, start_delay: Duration) -> Self {
+        self.start_delay = start_delay;
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn with_max_poll_errors(mut self, max_poll_errors: usize) -> Self {
+        self.max_poll_errors = max_poll_errors;
+        self
+    }
+
+    pub fn with_max_job_errors(mut self, max_job_errors: usize) -> Self {
+        self.max_job_errors = Some(max_job_errors);
+        self
+    }
+
+    pub fn with_cancel(mut self, cancel: Arc<AtomicBool>) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+
+    pub fn with_progress(mut self, on_progress: impl FnMut(&JobProgress) + 'a) -> Self {
+        self.on_progress = Some(Box::new(on_progress));
+        self
+    }
+
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.cancel
+            .as_ref()
+            .is_some_and(|x| x.load(Ordering::Relaxed))
+    }
+}
+
+/// Why waiting for a job did not end with a successful job.
+#[derive(Debug)]
+pub enum JobWaitError {
+    /// The server marked the job as failed.
+    Failed(Box<RepoJobDto>),
+    /// The job reported more errors than allowed by `max_job_errors`.
+    TooManyErrors(Box<RepoJobDto>),
+    /// The timeout passed first. Contains the last known state of the job, if any.
+    TimedOut(Option<Box<RepoJobDto>>),
+    /// Waiting was cancelled. Contains the last known state of the job, if any.
+    Cancelled(Option<Box<RepoJobDto>>),
+    /// The job could not be polled `max_poll_errors` times in a row.
+    Unreachable(NeptisError),
+}
+
+impl JobWaitError {
+    /// Returns the last known state of the job, if any.
+    pub fn job(&self) -> Option<&RepoJobDto> {
+        match self {
+            JobWaitError::Failed(x) | JobWaitError::TooManyErrors(x) => Some(x),
+            JobWaitError::TimedOut(x) | JobWaitError::Cancelled(x) => x.as_deref(),
+            JobWaitError::Unreachable(_) => None,
+        }
+    }
+}
+
+impl Display for JobWaitError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            JobWaitError::Failed(x) if x.errors.is_empty() => write!(f, "The job failed"),
+            JobWaitError::Failed(x) => write!(f, "The job failed: {}", x.errors.join(", ")),
+            JobWaitError::TooManyErrors(x) => {
+                write!(
+                    f,
+                    "The job reported too many errors: {}",
+                    x.errors.join(", ")
+                )
+            }
+            JobWaitError::TimedOut(_) => write!(f, "Timed out waiting for the job"),
+            JobWaitError::Cancelled(_) => write!(f, "Stopped waiting for the job"),
+            JobWaitError::Unreachable(e) => write!(f, "Failed to poll the job: {}", e),
+        }
+    }
+}
+
+impl error::Error for JobWaitError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            JobWaitError::Unreachable(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<JobWaitError> for NeptisError {
+    fn from(e: JobWaitError) -> Self {
+        match e {
+            JobWaitError::Unreachable(e) => e,
+            e => NeptisError::Str(e.to_string()),
+        }
+    }
+}
@@ -1,5 +1,8 @@
 use std::error;
 use std::fmt;
+use std::time::Duration;
+
+use reqwest::StatusCode;
 
 #[derive(Debug)]
 pub enum NeptisError {
@@ -9,6 +12,46 @@ pub enum NeptisError {
     Sql(sqlx::Error),
     Str(String),
     Zip(zip::result::ZipError),
+    /// The server rejected the credentials (401).
+    Unauthorized(String),
+    /// The user is not allowed to perform the action (403).
+    Forbidden(String),
+    /// The requested resource does not exist (404).
+    NotFound(String),
+    /// The request conflicts with the current state, such as a running job (409).
+    Conflict(String),
+    /// Too many requests were sent (429). Contains the `Retry-After` delay, if any.
+    RateLimited(String, Option<Duration>),
+    /// Any other non-success status returned by the server.
+    ServerError(StatusCode, String),
+}
+
+impl NeptisError {
+    /// Builds the matching error for a non-success status, carrying the server's message.
+    pub fn from_status(status: StatusCode, message: String, retry_after: Option<Duration>) -> Self {
+        match status {
+            StatusCode::UNAUTHORIZED => NeptisError::Unauthorized(message),
+            StatusCode::FORBIDDEN => NeptisError::Forbidden(message),
+            StatusCode::NOT_FOUND => NeptisError::NotFound(message),
+            StatusCode::CONFLICT => NeptisError::Conflict(message),
+            StatusCode::TOO_MANY_REQUESTS => NeptisError::RateLimited(message, retry_after),
+            _ => NeptisError::ServerError(status, message),
+        }
+    }
+
+    /// Returns the HTTP status that caused this error, if it came from the server.
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            NeptisError::Api(e) => e.status(),
+            NeptisError::Unauthorized(_) => Some(StatusCode::UNAUTHORIZED),
+            NeptisError::Forbidden(_) => Some(StatusCode::FORBIDDEN),
+            NeptisError::NotFound(_) => Some(StatusCode::NOT_FOUND),
+            NeptisError::Conflict(_) => Some(StatusCode::CONFLICT),
+            NeptisError::RateLimited(_, _) => Some(StatusCode::TOO_MANY_REQUESTS),
+            NeptisError::ServerError(s, _) => Some(*s),
+            _ => None,
+        }
+    }
 }
 
 impl fmt::Display for NeptisError {
@@ -20,6 +63,16 @@ impl fmt::Display for NeptisError {
             NeptisError::Str(e) => ("custom", e.to_string()),
             NeptisError::Sql(e) => ("SQL", e.to_string()),
             NeptisError::Zip(e) => ("Zip", e.to_string()),
+            NeptisError::Unauthorized(e) => ("server", format!("Unauthorized: {e}")),
+            NeptisError::Forbidden(e) => ("server", format!("Forbidden: {e}")),
+            NeptisError::NotFound(e) => ("server", format!("Not Found: {e}")),
+            NeptisError::Conflict(e) => ("server", format!("Conflict: {e}")),
+            NeptisError::RateLimited(e, Some(d)) => (
+                "server",
+                format!("Rate Limited (retry in {}s): {e}", d.as_secs()),
+            ),
+            NeptisError::RateLimited(e, None) => ("server", format!("Rate Limited: {e}")),
+            NeptisError::ServerError(s, e) => ("server", format!("{s}: {e}")),
         };
         write!(f, "error in {}: {}", module, e)
     }
@@ -33,7 +86,13 @@ impl error::Error for NeptisError {
             NeptisError::Io(e) => e,
             NeptisError::Sql(e) => e,
             NeptisError::Zip(e) => e,
-            NeptisError::Str(_) => return None,
+            NeptisError::Str(_)
+            | NeptisError::Unauthorized(_)
+            | NeptisError::Forbidden(_)
+            | NeptisError::NotFound(_)
+            | NeptisError::Conflict(_)
+            | NeptisError::RateLimited(_, _)
+            | NeptisError::ServerError(_, _) => return None,
         })
     }
 }
@@ -104,5 +163,11 @@ pub fn parse_deep_object(prefix: &str, value: &serde_json::Value) -> Vec<(String
 }
 
 pub mod api;
+pub mod capabilities;
 pub mod dtos;
+pub mod job_wait;
+pub mod notifications;
+pub mod pairing;
+pub mod retention;
+pub mod snapshot_diff;
 pub mod prelude;
\ No newline at end of file
@@ -0,0 +1,129 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::runtime::Handle;
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel};
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+use super::NeptisError;
+use super::api::WebApi;
+use crate::models::WsNotificationDto;
+
+#[derive(Clone, Debug)]
+pub struct NotificationOptions {
+    /// How often to poll while the server answers.
+    pub poll_interval: Duration,
+    /// The delay before polling again after a failure, doubled after every further failure.
+    pub min_backoff: Duration,
+    /// The upper bound for the delay after failures.
+    pub max_backoff: Duration,
+}
+
+impl Default for NotificationOptions {
+    fn default() -> Self {
+        NotificationOptions {
+            poll_interval: Duration::from_secs(15),
+            min_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(120),
+        }
+    }
+}
+
+/// A feed of unread notifications for one server, polled from `/notifications`.
+///
+/// The server API has no push channel, so the feed polls every
+/// [`NotificationOptions::poll_interval`], and backs off exponentially while the server cannot
+/// be reached. Each unread notification is delivered once per subscription; once it has been
+/// shown, [`WebApi::get_one_notification`] marks it as read so it is not shown again by the
+/// next subscription. The background task stops when the subscription is dropped.
+pub struct NotificationSubscription {
+    rx: UnboundedReceiver<WsNotificationDto>,
+    task: JoinHandle<()>,
+}
+
+impl NotificationSubscription {
+    pub fn spawn(api: Arc<WebApi>, opts: NotificationOptions, handle: &Handle) -> Self {
+        let (tx, rx) = unbounded_channel();
+        let task = handle.spawn(Self::run(api, opts, tx));
+        NotificationSubscription { rx, task }
+    }
+
+    /// Returns the next notification if one has arrived, without waiting.
+    pub fn try_recv(&mut self) -> Option<WsNotificationDto> {
+        self.rx.try_recv().ok()
+    }
+
+    /// Waits for the next notification.
+    pub async fn recv(&mut self) -> Option<WsNotificationDto> {
+        self.rx.recv().await
+    }
+
+    async fn run(
+        api: Arc<WebApi>,
+        opts: NotificationOptions,
+        tx: UnboundedSender<WsNotificationDto>,
+    ) {
+        let mut seen = HashSet::new();
+        let mut backoff = opts.min_backoff;
+        loop {
+            let delay = match Self::poll(&api, &tx, &mut seen).await {
+                Ok(()) => {
+                    backoff = opts.min_backoff;
+                    opts.poll_interval
+                }
+                Err(_) => {
+                    let delay = backoff;
+                    backoff = (backoff * 2).min(opts.max_backoff);
+                    delay
+                }
+            };
+            if tx.is_closed() {
+                return;
+            }
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    /// Forwards the unread notifications which were not delivered yet. Only the IDs which are
+    /// still unread are remembered, so `seen` stays as small as the unread list.
+    async fn poll(
+        api: &WebApi,
+        tx: &UnboundedSender<WsNotificationDto>,
+        seen: &mut HashSet<Uuid>,
+    ) -> Result<(), NeptisError> {
+        let items = api.get_all_notifications(true).await?;
+        let mut unread = HashSet::new();
+        for dto in items {
+            if let Some(id) = dto.id {
+                unread.insert(id);
+            }
+            Self::forward(dto, tx, seen);
+        }
+        seen.retain(|x| unread.contains(x));
+        Ok(())
+    }
+
+    fn forward(
+        dto: WsNotificationDto,
+        tx: &UnboundedSender<WsNotificationDto>,
+        seen: &mut HashSet<Uuid>,
+    ) {
+        if dto.is_read == Some(true) {
+            return;
+        }
+        if let Some(id) = dto.id
+            && !seen.insert(id)
+        {
+            return;
+        }
+        let _ = tx.send(dto);
+    }
+}
+
+impl Drop for NotificationSubscription {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
@@ -0,0 +1,139 @@
+use chrono::{DateTime, TimeDelta, Utc};
+
+use super::NeptisError;
+use super::api::{RetryPolicy, SystemSnapshotDto, WebApi};
+use crate::rolling_secret::RollingSecret;
+
+/// How long a rotated secret keeps accepting the previous one by default. This covers
+/// scheduled jobs which started with the old secret, and clients which are not updated yet.
+pub const DEFAULT_ROTATION_GRACE: TimeDelta = TimeDelta::hours(24);
+
+/// Pairs a client with a server by generating a new secret, or rotates an existing one.
+///
+/// The server secret is shown to the user as a QR code or string to install on the server,
+/// along with a short code both sides can compare. [`SecretRotation::confirm`] then checks
+/// that the server really accepts it before the client switches over. When rotating, the
+/// server secret also carries the old secret and the end of the grace period, so the server
+/// keeps accepting the old one until then. The client only ever saves the new secret.
+#[derive(Debug, Clone)]
+pub struct SecretRotation {
+    secret: RollingSecret,
+    previous: Option<RollingSecret>,
+    grace_until: Option<DateTime<Utc>>,
+}
+
+impl SecretRotation {
+    /// Starts pairing a server which has no secret yet.
+    pub fn pair() -> Result<Self, NeptisError> {
+        Ok(SecretRotation {
+            secret: RollingSecret::generate()
+                .ok_or(NeptisError::Str("Failed to generate a secret!".into()))?,
+            previous: None,
+            grace_until: None,
+        })
+    }
+
+    /// Starts replacing `current`, which stays accepted for `grace` after the rotation.
+    pub fn rotate(current: &RollingSecret, grace: TimeDelta) -> Result<Self, NeptisError> {
+        if grace < TimeDelta::zero() {
+            return Err(NeptisError::Str(
+                "The grace period cannot be negative!".into(),
+            ));
+        }
+        Ok(SecretRotation {
+            secret: RollingSecret::generate()
+                .ok_or(NeptisError::Str("Failed to generate a secret!".into()))?,
+            previous: Some(current.without_previous()),
+            grace_until: Some(Utc::now() + grace),
+        })
+    }
+
+    /// Returns the new secret on its own. This is the form to save for the client once
+    /// confirmed.
+    pub fn secret(&self) -> &RollingSecret {
+        &self.secret
+    }
+
+    /// Returns the secret to install on the server. When rotating, it keeps the previous
+    /// secret until the grace period ends.
+    pub fn server_secret(&self) -> RollingSecret {
+        match (&self.previous, self.grace_until) {
+            (Some(previous), Some(until)) => {
+                self.secret.clone().with_previous(previous.clone(), until)
+            }
+            _ => self.secret.clone(),
+        }
+    }
+
+    /// Returns the secret being replaced, if any.
+    pub fn previous(&self) -> Option<&RollingSecret> {
+        self.previous.as_ref()
+    }
+
+    /// Returns until when the server should keep accepting the previous secret.
+    pub fn grace_until(&self) -> Option<DateTime<Utc>> {
+        self.grace_until
+    }
+
+    /// Returns the short code of the new secret, to compare with the one the server shows.
+    pub fn short_code(&self) -> String {
+        self.secret.fingerprint()
+    }
+
+    /// Returns the server secret as a QR code, see [`SecretRotation::server_secret`].
+    pub fn qr_code(&self) -> Option<String> {
+        self.server_secret().to_qr_code()
+    }
+
+    /// Completes the handshake by logging in and pulling the server information with only
+    /// the new secret. This succeeds only when the server can read requests sealed with it
+    /// and answers with the same secret, so both sides are known to agree.
+    pub async fn confirm(
+        &self,
+        endpoint: &str,
+        user_name: &str,
+        password: &str,
+    ) -> Result<SystemSnapshotDto, NeptisError> {
+        let api = WebApi::new(endpoint, user_name, password, Some(self.secret.clone()))
+            .with_retry_policy(RetryPolicy::none());
+        api.get_info().await.map_err(|e| {
+            NeptisError::Str(format!(
+                "The server did not accept the new secret ({}): {e}",
+                self.short_code()
+            ))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rotation_saves_only_the_new_secret() {
+        let current = RollingSecret::generate().unwrap();
+        let rotation = SecretRotation::rotate(&current, DEFAULT_ROTATION_GRACE).unwrap();
+        assert!(rotation.secret().previous().is_none());
+        assert_eq!(rotation.secret().to_string().split('§').count(), 3);
+        assert_ne!(rotation.short_code(), current.fingerprint());
+        let previous = rotation.previous().map(|x| x.fingerprint());
+        assert_eq!(previous, Some(current.fingerprint()));
+        assert!(rotation.grace_until().is_some_and(|x| x > Utc::now()));
+    }
+
+    #[test]
+    fn server_secret_keeps_the_previous_one() {
+        let current = RollingSecret::generate().unwrap();
+        let rotation = SecretRotation::rotate(&current, DEFAULT_ROTATION_GRACE).unwrap();
+        let installed = rotation.server_secret().to_string();
+        assert_eq!(installed.split('§').count(), 7);
+        let parsed = RollingSecret::from_string(&installed).unwrap();
+        assert_eq!(parsed.fingerprint(), rotation.short_code());
+        let previous = parsed.previous().map(|x| x.fingerprint());
+        assert_eq!(previous, Some(current.fingerprint()));
+
+        let pairing = SecretRotation::pair().unwrap();
+        let installed = pairing.server_secret().to_string();
+        assert_eq!(installed, pairing.secret().to_string());
+    }
+}
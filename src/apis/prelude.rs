@@ -1,2 +1,8 @@
 pub use super::api::*;
-pub use super::dtos::*;
\ No newline at end of file
+pub use super::capabilities::*;
+pub use super::dtos::*;
+pub use super::job_wait::*;
+pub use super::notifications::*;
+pub use super::pairing::*;
+pub use super::retention::*;
+pub use super::snapshot_diff::*;
\ No newline at end of file
@@ -0,0 +1,322 @@
+use std::cmp::Reverse;
+use std::fmt::{self, Display, Formatter};
+
+use chrono::{DateTime, Datelike, Local, TimeDelta};
+use serde::{Deserialize, Serialize};
+
+use super::NeptisError;
+use super::dtos::SnapshotFileDto;
+
+/// Decides which snapshots of a point to keep when pruning. A snapshot is kept if any rule
+/// matches it, and locked snapshots are always kept. An empty policy keeps everything.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    /// Keep the newest N snapshots.
+    pub keep_last: Option<u32>,
+    /// Keep the newest snapshot of each of the last N days which have one.
+    pub keep_daily: Option<u32>,
+    /// Keep the newest snapshot of each of the last N weeks which have one.
+    pub keep_weekly: Option<u32>,
+    /// Keep the newest snapshot of each of the last N months which have one.
+    pub keep_monthly: Option<u32>,
+    /// Keep the newest snapshot of each of the last N years which have one.
+    pub keep_yearly: Option<u32>,
+    /// Keep every snapshot within this duration of the newest one, such as `30d` or `1y6m`.
+    pub keep_within: Option<String>,
+}
+
+/// Why a snapshot is kept by a [`RetentionPolicy`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeepReason {
+    Locked,
+    Last,
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+    Within,
+}
+
+impl Display for KeepReason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            KeepReason::Locked => "locked",
+            KeepReason::Last => "last",
+            KeepReason::Daily => "daily",
+            KeepReason::Weekly => "weekly",
+            KeepReason::Monthly => "monthly",
+            KeepReason::Yearly => "yearly",
+            KeepReason::Within => "within",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// The result of applying a [`RetentionPolicy`], newest snapshots first.
+#[derive(Debug, Clone, Default)]
+pub struct PrunePlan {
+    pub keep: Vec<(SnapshotFileDto, Vec<KeepReason>)>,
+    pub remove: Vec<SnapshotFileDto>,
+}
+
+impl PrunePlan {
+    /// Returns the IDs of the snapshots which would be removed, sorted.
+    pub fn remove_ids(&self) -> Vec<&str> {
+        let mut ret = self
+            .remove
+            .iter()
+            .map(|x| x.id.as_str())
+            .collect::<Vec<_>>();
+        ret.sort_unstable();
+        ret
+    }
+}
+
+struct Bucket {
+    reason: KeepReason,
+    remaining: u32,
+    last: Option<i64>,
+    key: fn(&DateTime<Local>) -> i64,
+}
+
+fn day_key(x: &DateTime<Local>) -> i64 {
+    x.year() as i64 * 1000 + x.ordinal() as i64
+}
+
+fn week_key(x: &DateTime<Local>) -> i64 {
+    let w = x.iso_week();
+    w.year() as i64 * 100 + w.week() as i64
+}
+
+fn month_key(x: &DateTime<Local>) -> i64 {
+    x.year() as i64 * 100 + x.month() as i64
+}
+
+fn year_key(x: &DateTime<Local>) -> i64 {
+    x.year() as i64
+}
+
+impl RetentionPolicy {
+    pub fn is_empty(&self) -> bool {
+        *self == RetentionPolicy::default()
+    }
+
+    /// Parses a duration made of `<number><unit>` parts, where the unit is one of
+    /// `h`, `d`, `w`, `m` (30 days) or `y` (365 days).
+    pub fn parse_within(s: &str) -> Option<TimeDelta> {
+        let mut total = TimeDelta::zero();
+        let mut num = String::new();
+        for c in s.trim().chars() {
+            if c.is_ascii_digit() {
+                num.push(c);
+                continue;
+            }
+            let n = num.parse::<i64>().ok()?;
+            num.clear();
+            total += match c.to_ascii_lowercase() {
+                'h' => TimeDelta::try_hours(n)?,
+                'd' => TimeDelta::try_days(n)?,
+                'w' => TimeDelta::try_weeks(n)?,
+                'm' => TimeDelta::try_days(n.checked_mul(30)?)?,
+                'y' => TimeDelta::try_days(n.checked_mul(365)?)?,
+                _ => return None,
+            };
+        }
+        (num.is_empty() && total > TimeDelta::zero()).then_some(total)
+    }
+
+    pub fn validate(&self) -> Result<(), NeptisError> {
+        if let Some(ref within) = self.keep_within
+            && Self::parse_within(within).is_none()
+        {
+            return Err(NeptisError::Str(format!(
+                "'{}' is not a valid duration (ex. 30d or 1y6m)",
+                within
+            )));
+        }
+        Ok(())
+    }
+
+    /// Works out which snapshots the policy keeps and which it removes.
+    pub fn apply(&self, snapshots: &[SnapshotFileDto]) -> PrunePlan {
+        let mut sorted = snapshots.to_vec();
+        sorted.sort_by_key(|x| Reverse(x.time));
+        if self.is_empty() {
+            return PrunePlan {
+                keep: sorted.into_iter().map(|x| (x, vec![])).collect(),
+                remove: vec![],
+            };
+        }
+
+        let mut buckets = [
+            (KeepReason::Daily, self.keep_daily, day_key as fn(&DateTime<Local>) -> i64),
+            (KeepReason::Weekly, self.keep_weekly, week_key),
+            (KeepReason::Monthly, self.keep_monthly, month_key),
+            (KeepReason::Yearly, self.keep_yearly, year_key),
+        ]
+        .into_iter()
+        .filter_map(|(reason, n, key)| {
+            n.map(|remaining| Bucket {
+                reason,
+                remaining,
+                last: None,
+                key,
+            })
+        })
+        .collect::<Vec<_>>();
+
+        let within = self.keep_within.as_deref().and_then(Self::parse_within);
+        let cutoff = sorted
+            .first()
+            .zip(within)
+            .and_then(|(x, d)| x.time.checked_sub_signed(d));
+
+        let mut ret = PrunePlan::default();
+        for (i, snap) in sorted.into_iter().enumerate() {
+            let mut reasons = vec![];
+            if snap.locked {
+                reasons.push(KeepReason::Locked);
+            }
+            if self.keep_last.is_some_and(|n| (i as u64) < n as u64) {
+                reasons.push(KeepReason::Last);
+            }
+            if cutoff.is_some_and(|x| snap.time >= x) {
+                reasons.push(KeepReason::Within);
+            }
+            let local = snap.time.and_utc().with_timezone(&Local);
+            for bucket in buckets.iter_mut() {
+                let key = (bucket.key)(&local);
+                if bucket.remaining > 0 && bucket.last != Some(key) {
+                    bucket.remaining -= 1;
+                    bucket.last = Some(key);
+                    reasons.push(bucket.reason);
+                }
+            }
+            if reasons.is_empty() {
+                ret.remove.push(snap);
+            } else {
+                ret.keep.push((snap, reasons));
+            }
+        }
+        ret
+    }
+}
+
+impl Display for RetentionPolicy {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "Keep everything");
+        }
+        let parts = [
+            ("last", self.keep_last),
+            ("daily", self.keep_daily),
+            ("weekly", self.keep_weekly),
+            ("monthly", self.keep_monthly),
+            ("yearly", self.keep_yearly),
+        ]
+        .into_iter()
+        .filter_map(|(name, n)| n.map(|n| format!("{} {}", name, n)))
+        .chain(self.keep_within.iter().map(|x| format!("within {}", x)))
+        .collect::<Vec<_>>();
+        write!(f, "Keep {}", parts.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDateTime;
+
+    /// Snapshots are taken around midday UTC. Far east of UTC they fall on the next local day,
+    /// but every snapshot moves alike, so the days and weeks they are grouped into stay apart.
+    fn snapshot(id: &str, time: &str) -> SnapshotFileDto {
+        SnapshotFileDto {
+            id: id.into(),
+            time: NaiveDateTime::parse_from_str(time, "%Y-%m-%d %H:%M").unwrap(),
+            ..Default::default()
+        }
+    }
+
+    fn ids(snapshots: &[(SnapshotFileDto, Vec<KeepReason>)]) -> Vec<&str> {
+        snapshots.iter().map(|(x, _)| x.id.as_str()).collect()
+    }
+
+    #[test]
+    fn keep_last_keeps_the_newest() {
+        let snapshots = [
+            snapshot("a", "2024-03-01 12:00"),
+            snapshot("c", "2024-03-03 12:00"),
+            snapshot("b", "2024-03-02 12:00"),
+            snapshot("d", "2024-03-04 12:00"),
+        ];
+        let policy = RetentionPolicy {
+            keep_last: Some(2),
+            ..Default::default()
+        };
+        let plan = policy.apply(&snapshots);
+        assert_eq!(ids(&plan.keep), ["d", "c"]);
+        assert_eq!(plan.remove_ids(), ["a", "b"]);
+        assert!(plan.keep.iter().all(|(_, r)| r == &[KeepReason::Last]));
+    }
+
+    #[test]
+    fn keep_daily_keeps_the_newest_of_each_day() {
+        let snapshots = [
+            snapshot("day3-late", "2024-03-06 12:30"),
+            snapshot("day3-early", "2024-03-06 12:00"),
+            snapshot("day2", "2024-03-05 12:00"),
+            snapshot("day1", "2024-03-04 12:00"),
+        ];
+        let policy = RetentionPolicy {
+            keep_daily: Some(2),
+            ..Default::default()
+        };
+        let plan = policy.apply(&snapshots);
+        assert_eq!(ids(&plan.keep), ["day3-late", "day2"]);
+        assert_eq!(plan.remove_ids(), ["day1", "day3-early"]);
+    }
+
+    #[test]
+    fn keep_weekly_keeps_the_newest_of_each_iso_week() {
+        let snapshots = [
+            snapshot("w11-wed", "2024-03-13 12:00"),
+            snapshot("w11-tue", "2024-03-12 12:00"),
+            snapshot("w10-wed", "2024-03-06 12:00"),
+            snapshot("w09-wed", "2024-02-28 12:00"),
+        ];
+        let policy = RetentionPolicy {
+            keep_weekly: Some(2),
+            ..Default::default()
+        };
+        let plan = policy.apply(&snapshots);
+        assert_eq!(ids(&plan.keep), ["w11-wed", "w10-wed"]);
+        assert_eq!(plan.remove_ids(), ["w09-wed", "w11-tue"]);
+    }
+
+    #[test]
+    fn locked_snapshots_are_always_kept() {
+        let mut locked = snapshot("locked", "2024-03-01 12:00");
+        locked.locked = true;
+        let snapshots = [
+            locked,
+            snapshot("old", "2024-03-02 12:00"),
+            snapshot("new", "2024-03-03 12:00"),
+        ];
+        let policy = RetentionPolicy {
+            keep_last: Some(1),
+            ..Default::default()
+        };
+        let plan = policy.apply(&snapshots);
+        assert_eq!(ids(&plan.keep), ["new", "locked"]);
+        assert_eq!(plan.keep[1].1, [KeepReason::Locked]);
+        assert_eq!(plan.remove_ids(), ["old"]);
+    }
+
+    #[test]
+    fn empty_policy_keeps_everything() {
+        let snapshots = [snapshot("a", "2024-03-01 12:00")];
+        let plan = RetentionPolicy::default().apply(&snapshots);
+        assert_eq!(ids(&plan.keep), ["a"]);
+        assert!(plan.remove.is_empty());
+    }
+}
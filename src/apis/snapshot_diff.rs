@@ -0,0 +1,323 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{self, Display, Formatter};
+
+use serde::Serialize;
+
+use super::NeptisError;
+use super::dtos::NodeDto;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffKind {
+    Added,
+    Removed,
+    Modified,
+}
+
+impl Display for DiffKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            DiffKind::Added => "+",
+            DiffKind::Removed => "-",
+            DiffKind::Modified => "~",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// One path which differs between two snapshots, relative to the snapshot root.
+#[derive(Serialize, Debug, Clone)]
+pub struct DiffEntry {
+    pub path: String,
+    pub kind: DiffKind,
+    pub is_dir: bool,
+    /// The size in the older snapshot, or `None` if it did not exist (or is a directory).
+    pub old_bytes: Option<u64>,
+    /// The size in the newer snapshot, or `None` if it does not exist (or is a directory).
+    pub new_bytes: Option<u64>,
+}
+
+impl DiffEntry {
+    pub fn delta(&self) -> i64 {
+        self.new_bytes.unwrap_or(0) as i64 - self.old_bytes.unwrap_or(0) as i64
+    }
+}
+
+/// The changes below one child of a directory, used to drill down into a [`SnapshotDiff`].
+#[derive(Debug, Clone, Default)]
+pub struct DiffNode {
+    pub name: String,
+    pub path: String,
+    pub is_dir: bool,
+    /// How the child itself changed, or `None` if only its contents did.
+    pub kind: Option<DiffKind>,
+    pub added: usize,
+    pub removed: usize,
+    pub modified: usize,
+    pub delta: i64,
+}
+
+/// The files which were added, removed or modified between two snapshots of a point.
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct SnapshotDiff {
+    pub point: String,
+    pub from: String,
+    pub to: String,
+    pub entries: Vec<DiffEntry>,
+}
+
+impl SnapshotDiff {
+    /// Compares two complete snapshot trees. A file is modified when its size or modification
+    /// time differs, and a path which changed between a file and a directory is reported as
+    /// removed and added.
+    pub fn compare(point: &str, from: &str, to: &str, old: &[NodeDto], new: &[NodeDto]) -> Self {
+        fn bytes(x: &NodeDto) -> Option<u64> {
+            (!x.is_dir).then_some(x.bytes)
+        }
+
+        let old_map = old
+            .iter()
+            .map(|x| (x.path.as_str(), x))
+            .collect::<HashMap<_, _>>();
+        let new_map = new
+            .iter()
+            .map(|x| (x.path.as_str(), x))
+            .collect::<HashMap<_, _>>();
+
+        let mut entries = vec![];
+        for (path, o) in old_map.iter() {
+            match new_map.get(path) {
+                Some(n) if n.is_dir == o.is_dir => {
+                    if !o.is_dir && (o.bytes != n.bytes || o.mtime != n.mtime) {
+                        entries.push(DiffEntry {
+                            path: path.to_string(),
+                            kind: DiffKind::Modified,
+                            is_dir: false,
+                            old_bytes: bytes(o),
+                            new_bytes: bytes(n),
+                        });
+                    }
+                }
+                _ => entries.push(DiffEntry {
+                    path: path.to_string(),
+                    kind: DiffKind::Removed,
+                    is_dir: o.is_dir,
+                    old_bytes: bytes(o),
+                    new_bytes: None,
+                }),
+            }
+        }
+        for (path, n) in new_map.iter() {
+            if old_map.get(path).is_none_or(|o| o.is_dir != n.is_dir) {
+                entries.push(DiffEntry {
+                    path: path.to_string(),
+                    kind: DiffKind::Added,
+                    is_dir: n.is_dir,
+                    old_bytes: None,
+                    new_bytes: bytes(n),
+                });
+            }
+        }
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+        SnapshotDiff {
+            point: point.to_string(),
+            from: from.to_string(),
+            to: to.to_string(),
+            entries,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the number of added, removed and modified files, ignoring directories.
+    pub fn counts(&self) -> (usize, usize, usize) {
+        self.entries
+            .iter()
+            .filter(|x| !x.is_dir)
+            .fold((0, 0, 0), |(a, r, m), x| match x.kind {
+                DiffKind::Added => (a + 1, r, m),
+                DiffKind::Removed => (a, r + 1, m),
+                DiffKind::Modified => (a, r, m + 1),
+            })
+    }
+
+    /// Returns the total change in size, in bytes.
+    pub fn total_delta(&self) -> i64 {
+        self.entries.iter().map(|x| x.delta()).sum()
+    }
+
+    /// Groups the changes below `dir` by its direct children, directories first.
+    pub fn children(&self, dir: &str) -> Vec<DiffNode> {
+        let dir = dir.trim_end_matches('/');
+        let mut ret = BTreeMap::<(bool, String), DiffNode>::new();
+        for entry in self.entries.iter() {
+            let Some(rest) = entry
+                .path
+                .strip_prefix(dir)
+                .and_then(|x| x.strip_prefix('/'))
+            else {
+                continue;
+            };
+            let (name, is_self) = match rest.split_once('/') {
+                Some((name, _)) => (name, false),
+                None => (rest, true),
+            };
+            if name.is_empty() {
+                continue;
+            }
+            let is_dir = !is_self || entry.is_dir;
+            let node = ret
+                .entry((!is_dir, name.to_string()))
+                .or_insert_with(|| DiffNode {
+                    name: name.to_string(),
+                    path: format!("{}/{}", dir, name),
+                    is_dir,
+                    ..Default::default()
+                });
+            if is_self {
+                node.kind = Some(entry.kind);
+            }
+            if !entry.is_dir {
+                match entry.kind {
+                    DiffKind::Added => node.added += 1,
+                    DiffKind::Removed => node.removed += 1,
+                    DiffKind::Modified => node.modified += 1,
+                }
+                node.delta += entry.delta();
+            }
+        }
+        ret.into_values().collect()
+    }
+
+    pub fn to_json(&self) -> Result<String, NeptisError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::DateTime;
+
+    fn file(path: &str, bytes: u64, mtime: i64) -> NodeDto {
+        let time = DateTime::from_timestamp(mtime, 0).unwrap().naive_utc();
+        NodeDto {
+            path: path.to_string(),
+            atime: time,
+            ctime: time,
+            mtime: time,
+            is_dir: false,
+            bytes,
+        }
+    }
+
+    fn dir(path: &str) -> NodeDto {
+        NodeDto {
+            is_dir: true,
+            ..file(path, 0, 0)
+        }
+    }
+
+    fn diff() -> SnapshotDiff {
+        let old = [
+            dir("/docs"),
+            file("/docs/a.txt", 10, 0),
+            file("/docs/b.txt", 20, 0),
+            dir("/docs/sub"),
+            file("/docs/sub/c.txt", 5, 0),
+            file("/top.txt", 1, 0),
+        ];
+        let new = [
+            dir("/docs"),
+            file("/docs/a.txt", 15, 60),
+            file("/docs/new.txt", 7, 60),
+            dir("/docs/sub"),
+            file("/docs/sub/c.txt", 5, 60),
+            dir("/photos"),
+            file("/photos/p.jpg", 100, 60),
+            file("/top.txt", 1, 0),
+        ];
+        SnapshotDiff::compare("point", "old", "new", &old, &new)
+    }
+
+    #[test]
+    fn changes_are_found_and_sorted() {
+        let diff = diff();
+        let entries = diff
+            .entries
+            .iter()
+            .map(|x| (x.path.as_str(), x.kind))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            entries,
+            [
+                ("/docs/a.txt", DiffKind::Modified),
+                ("/docs/b.txt", DiffKind::Removed),
+                ("/docs/new.txt", DiffKind::Added),
+                ("/docs/sub/c.txt", DiffKind::Modified),
+                ("/photos", DiffKind::Added),
+                ("/photos/p.jpg", DiffKind::Added),
+            ]
+        );
+        assert_eq!(diff.counts(), (2, 1, 2));
+        assert!(!diff.is_empty());
+    }
+
+    #[test]
+    fn size_deltas_add_up() {
+        let diff = diff();
+        let deltas = diff.entries.iter().map(|x| x.delta()).collect::<Vec<_>>();
+        assert_eq!(deltas, [5, -20, 7, 0, 0, 100]);
+        assert_eq!(diff.total_delta(), 92);
+    }
+
+    #[test]
+    fn type_changes_are_removed_and_added() {
+        let old = [file("/x", 3, 0)];
+        let new = [dir("/x")];
+        let diff = SnapshotDiff::compare("point", "old", "new", &old, &new);
+        let kinds = diff.entries.iter().map(|x| x.kind).collect::<Vec<_>>();
+        assert_eq!(kinds, [DiffKind::Removed, DiffKind::Added]);
+
+        let same = SnapshotDiff::compare("point", "old", "new", &new, &new);
+        assert!(same.is_empty());
+    }
+
+    #[test]
+    fn children_group_changes_by_directory() {
+        let diff = diff();
+        let root = diff.children("");
+        assert_eq!(root.len(), 2);
+        assert_eq!((root[0].name.as_str(), root[0].kind), ("docs", None));
+        assert_eq!(
+            (root[0].added, root[0].removed, root[0].modified),
+            (1, 1, 2)
+        );
+        assert_eq!(root[0].delta, -8);
+        assert_eq!(root[1].path, "/photos");
+        assert_eq!(root[1].kind, Some(DiffKind::Added));
+        assert_eq!((root[1].added, root[1].delta), (1, 100));
+
+        let docs = diff.children("/docs/");
+        let names = docs.iter().map(|x| x.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, ["sub", "a.txt", "b.txt", "new.txt"]);
+        assert!(docs[0].is_dir);
+        assert_eq!((docs[0].modified, docs[0].delta), (1, 0));
+        assert_eq!(docs[2].kind, Some(DiffKind::Removed));
+        assert_eq!(docs[2].delta, -20);
+    }
+
+    #[test]
+    fn json_uses_lowercase_kinds() {
+        let json = diff().to_json().unwrap();
+        let value = serde_json::from_str::<serde_json::Value>(&json).unwrap();
+        assert_eq!(value["point"], "point");
+        assert_eq!(value["from"], "old");
+        assert_eq!(value["entries"].as_array().unwrap().len(), 6);
+        assert_eq!(value["entries"][0]["kind"], "modified");
+        assert_eq!(value["entries"][0]["old_bytes"], 10);
+        assert_eq!(value["entries"][4]["new_bytes"], serde_json::Value::Null);
+    }
+}
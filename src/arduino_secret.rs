@@ -1,51 +1,403 @@
+use std::collections::HashMap;
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
 use base64::{Engine as _, engine::general_purpose::STANDARD};
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use rand::{RngCore, rng};
+use sha2::Sha256;
 use totp_lite::{Sha1, totp_custom};
 
-/// A basic encoding layer which ONLY guards against replay
-/// attacks when used correctly! Not intended for sensitive data.
+use crate::apis::NeptisError;
+
+/// How far the timestamp of a v2 wake request may be from the firmware clock, in seconds.
+pub const WAKE_WINDOW_SECS: i64 = 30;
+
+/// The scheme of the `Authorization` header which carries a v2 wake request.
+pub const WAKE_AUTH_SCHEME: &str = "Neptis-v2";
+
+/// The first line of the message signed by a v2 wake request.
+pub const WAKE_DOMAIN: &str = "NEPTIS-WAKE-V2";
+
+const WAKE_KEY_LEN: usize = 32;
+const WAKE_NONCE_LEN: usize = 16;
+
+/// The secret shared with the Arduino which wakes up the server.
+///
+/// `V1` is the original format, which sends the sum of two 15-second TOTPs as a bearer token.
+/// Anyone who sees the token can replay it until the codes roll over, so it is only kept for
+/// firmware which has not been updated. `V2` signs every request, see [`WakeRequest`].
 #[derive(Debug, Clone)]
-pub struct ArduinoSecret {
-    key_a: Vec<u8>,
-    key_b: Vec<u8>,
+pub enum ArduinoSecret {
+    V1 { key_a: Vec<u8>, key_b: Vec<u8> },
+    V2 { key: Vec<u8> },
 }
 
 impl ToString for ArduinoSecret {
     fn to_string(&self) -> String {
-        let otp_a_key = STANDARD.encode(self.key_a.as_slice());
-        let otp_b_key = STANDARD.encode(self.key_b.as_slice());
-        format!("{}§{}", otp_a_key, otp_b_key)
+        match self {
+            ArduinoSecret::V1 { key_a, key_b } => {
+                format!("{}§{}", STANDARD.encode(key_a), STANDARD.encode(key_b))
+            }
+            ArduinoSecret::V2 { key } => format!("v2§{}", STANDARD.encode(key)),
+        }
     }
 }
 
 impl ArduinoSecret {
-    fn from_key(key_a: &[u8], key_b: &[u8]) -> Self {
-        ArduinoSecret {
-            key_a: key_a.to_vec(),
-            key_b: key_b.to_vec(),
-        }
+    /// Generates a new v2 secret.
+    pub fn generate() -> Self {
+        let mut key = vec![0u8; WAKE_KEY_LEN];
+        rng().fill_bytes(&mut key);
+        ArduinoSecret::V2 { key }
     }
 
+    /// Parses a secret in the `key_a§key_b` (v1) or `v2§key` (v2) form.
     pub fn from_string(encoded: &str) -> Option<Self> {
         let parts: Vec<&str> = encoded.split('§').collect();
-        if parts.len() != 2 {
-            return None;
+        match parts.as_slice() {
+            ["v2", key] => {
+                let key = STANDARD.decode(key).ok()?;
+                (!key.is_empty()).then_some(ArduinoSecret::V2 { key })
+            }
+            [key_a, key_b] => Some(ArduinoSecret::V1 {
+                key_a: STANDARD.decode(key_a).ok()?,
+                key_b: STANDARD.decode(key_b).ok()?,
+            }),
+            _ => None,
         }
+    }
 
-        let otp1_key = STANDARD.decode(parts[0]).ok()?;
-        let otp2_key = STANDARD.decode(parts[1]).ok()?;
+    pub fn version(&self) -> u8 {
+        match self {
+            ArduinoSecret::V1 { .. } => 1,
+            ArduinoSecret::V2 { .. } => 2,
+        }
+    }
 
-        Some(Self::from_key(otp1_key.as_slice(), otp2_key.as_slice()))
+    /// Returns the key to flash onto v2 firmware, as lowercase hex.
+    pub fn firmware_key(&self) -> Option<String> {
+        match self {
+            ArduinoSecret::V1 { .. } => None,
+            ArduinoSecret::V2 { key } => Some(to_hex(key)),
+        }
     }
 
+    /// Returns the v1 bearer token for the current time.
     pub fn rolling_key(&self) -> Option<usize> {
-        use chrono::Utc;
+        let ArduinoSecret::V1 { key_a, key_b } = self else {
+            return None;
+        };
         let now = Utc::now().timestamp() as u64;
-        let o1 = totp_custom::<Sha1>(15, 6, self.key_a.as_slice(), now)
+        let o1 = totp_custom::<Sha1>(15, 6, key_a.as_slice(), now)
             .parse::<usize>()
             .ok()?;
-        let o2 = totp_custom::<Sha1>(15, 6, self.key_b.as_slice(), now)
+        let o2 = totp_custom::<Sha1>(15, 6, key_b.as_slice(), now)
             .parse::<usize>()
             .ok()?;
         Some(o1 + o2)
     }
+
+    /// Signs a v2 wake request for the current time, with a random nonce.
+    pub fn sign(&self, action: WakeAction) -> Option<WakeRequest> {
+        let mut nonce = [0u8; WAKE_NONCE_LEN];
+        rng().fill_bytes(&mut nonce);
+        self.sign_at(action, Utc::now().timestamp(), nonce)
+    }
+
+    /// Signs a v2 wake request with a fixed timestamp and nonce. Only v2 secrets can sign.
+    pub fn sign_at(
+        &self,
+        action: WakeAction,
+        timestamp: i64,
+        nonce: [u8; WAKE_NONCE_LEN],
+    ) -> Option<WakeRequest> {
+        let ArduinoSecret::V2 { key } = self else {
+            return None;
+        };
+        let nonce = to_hex(&nonce);
+        let mut mac = Hmac::<Sha256>::new_from_slice(key).ok()?;
+        mac.update(WakeRequest::message(action, timestamp, &nonce).as_bytes());
+        Some(WakeRequest {
+            action,
+            timestamp,
+            nonce,
+            mac: to_hex(&mac.finalize().into_bytes()),
+        })
+    }
+
+    /// Returns the `Authorization` header value which asks the Arduino to perform `action`.
+    pub fn authorization(&self, action: WakeAction) -> Option<String> {
+        match self {
+            ArduinoSecret::V1 { .. } => Some(format!("Bearer {}", self.rolling_key()?)),
+            ArduinoSecret::V2 { .. } => Some(self.sign(action)?.to_header()),
+        }
+    }
+}
+
+/// What a wake request asks the Arduino to do. The action is also the request path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WakeAction {
+    /// Powers on the server.
+    Start,
+}
+
+impl WakeAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WakeAction::Start => "start",
+        }
+    }
+}
+
+impl Display for WakeAction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for WakeAction {
+    type Err = NeptisError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "start" => Ok(WakeAction::Start),
+            _ => Err(NeptisError::Str(format!("Unknown wake action '{s}'"))),
+        }
+    }
+}
+
+/// A signed v2 wake request, in the form the Arduino firmware reads.
+///
+/// The request is `POST {endpoint}/{action}` with the header
+/// `Authorization: Neptis-v2 {timestamp}.{nonce}.{mac}`, where:
+///
+/// - `timestamp` is the Unix time in seconds, in decimal.
+/// - `nonce` is 16 random bytes, as 32 lowercase hex characters.
+/// - `mac` is the HMAC-SHA256 of the message below under the 32 byte key, as 64 lowercase
+///   hex characters.
+///
+/// The message is the ASCII text `NEPTIS-WAKE-V2\n{action}\n{timestamp}\n{nonce}`, without a
+/// trailing newline. The firmware must reject the request when the MAC does not match
+/// (compared in constant time), when the timestamp is more than [`WAKE_WINDOW_SECS`] away from
+/// its own clock, or when it already accepted the nonce within that window. [`WakeVerifier`]
+/// performs the same checks.
+///
+/// # Test vectors
+///
+/// The key below is the bytes 0 to 31 (`000102…1e1f` as a firmware key).
+///
+/// ```
+/// use neptis_rs::arduino_secret::{ArduinoSecret, WakeAction};
+///
+/// let secret = ArduinoSecret::from_string("v2§AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8=")
+///     .unwrap();
+///
+/// let nonce = [
+///     0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e,
+///     0x0f,
+/// ];
+/// let req = secret.sign_at(WakeAction::Start, 1700000000, nonce).unwrap();
+/// assert_eq!(
+///     req.to_header(),
+///     "Neptis-v2 1700000000.000102030405060708090a0b0c0d0e0f.\
+///      5cbf5de349fe98e18b90fec82ead571052ebe560293f416d82b992cd6e6b65ba"
+/// );
+///
+/// let nonce = [
+///     0xff, 0xee, 0xdd, 0xcc, 0xbb, 0xaa, 0x99, 0x88, 0x77, 0x66, 0x55, 0x44, 0x33, 0x22, 0x11,
+///     0x00,
+/// ];
+/// let req = secret.sign_at(WakeAction::Start, 1735689600, nonce).unwrap();
+/// assert_eq!(
+///     req.to_header(),
+///     "Neptis-v2 1735689600.ffeeddccbbaa99887766554433221100.\
+///      1d2723bde4d9b5e5a7edd9e013bef27b02ec0be4221e69fe9c3e2161461e6861"
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WakeRequest {
+    pub action: WakeAction,
+    pub timestamp: i64,
+    /// The nonce, as lowercase hex.
+    pub nonce: String,
+    /// The HMAC-SHA256 of [`WakeRequest::message`], as lowercase hex.
+    pub mac: String,
+}
+
+impl WakeRequest {
+    /// Builds the message which is signed.
+    pub fn message(action: WakeAction, timestamp: i64, nonce: &str) -> String {
+        format!("{WAKE_DOMAIN}\n{action}\n{timestamp}\n{nonce}")
+    }
+
+    pub fn token(&self) -> String {
+        format!("{}.{}.{}", self.timestamp, self.nonce, self.mac)
+    }
+
+    pub fn to_header(&self) -> String {
+        format!("{WAKE_AUTH_SCHEME} {}", self.token())
+    }
+
+    /// Parses the `Authorization` header of a request for `action`.
+    pub fn from_header(action: WakeAction, header: &str) -> Option<Self> {
+        let token = header.strip_prefix(WAKE_AUTH_SCHEME)?.strip_prefix(' ')?;
+        let mut parts = token.split('.');
+        let ret = WakeRequest {
+            action,
+            timestamp: parts.next()?.parse().ok()?,
+            nonce: parts.next()?.to_string(),
+            mac: parts.next()?.to_string(),
+        };
+        parts.next().is_none().then_some(ret)
+    }
+}
+
+/// Checks v2 wake requests the way the firmware must, remembering the nonces it accepted so a
+/// request cannot be replayed while its timestamp is still valid.
+///
+/// This is a reference for the firmware and its tests. The client only signs requests, so
+/// rejecting a replayed request is left to the firmware.
+pub struct WakeVerifier {
+    key: Vec<u8>,
+    seen: HashMap<String, i64>,
+}
+
+impl WakeVerifier {
+    /// Creates a verifier for a v2 secret.
+    pub fn new(secret: &ArduinoSecret) -> Option<Self> {
+        let ArduinoSecret::V2 { key } = secret else {
+            return None;
+        };
+        Some(WakeVerifier {
+            key: key.clone(),
+            seen: HashMap::new(),
+        })
+    }
+
+    pub fn verify(&mut self, req: &WakeRequest) -> Result<(), NeptisError> {
+        self.verify_at(req, Utc::now().timestamp())
+    }
+
+    /// Checks a request against the clock time `now`, and remembers its nonce if it is valid.
+    pub fn verify_at(&mut self, req: &WakeRequest, now: i64) -> Result<(), NeptisError> {
+        // Nonces older than the window are rejected by their timestamp anyway.
+        self.seen.retain(|_, x| *x >= now - WAKE_WINDOW_SECS);
+        if (now - req.timestamp).abs() > WAKE_WINDOW_SECS {
+            return Err(NeptisError::Str("The wake request has expired!".into()));
+        }
+        let valid = from_hex(&req.mac).is_some_and(|mac| {
+            Hmac::<Sha256>::new_from_slice(&self.key).is_ok_and(|mut x| {
+                x.update(WakeRequest::message(req.action, req.timestamp, &req.nonce).as_bytes());
+                x.verify_slice(&mac).is_ok()
+            })
+        });
+        if !valid || from_hex(&req.nonce).is_none_or(|x| x.len() != WAKE_NONCE_LEN) {
+            return Err(NeptisError::Str(
+                "The wake request signature is invalid!".into(),
+            ));
+        }
+        if self.seen.contains_key(&req.nonce) {
+            return Err(NeptisError::Str(
+                "The wake request was already used!".into(),
+            ));
+        }
+        self.seen.insert(req.nonce.clone(), req.timestamp);
+        Ok(())
+    }
+}
+
+fn to_hex(data: &[u8]) -> String {
+    data.iter().map(|x| format!("{x:02x}")).collect()
+}
+
+fn from_hex(s: &str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(2) || !s.bytes().all(|x| matches!(x, b'0'..=b'9' | b'a'..=b'f')) {
+        return None;
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 1_700_000_000;
+
+    fn setup() -> (ArduinoSecret, WakeVerifier) {
+        let secret = ArduinoSecret::generate();
+        let verifier = WakeVerifier::new(&secret).unwrap();
+        (secret, verifier)
+    }
+
+    fn sign(secret: &ArduinoSecret, timestamp: i64, nonce: u8) -> WakeRequest {
+        secret
+            .sign_at(WakeAction::Start, timestamp, [nonce; WAKE_NONCE_LEN])
+            .unwrap()
+    }
+
+    #[test]
+    fn requests_expire_outside_the_window() {
+        let (secret, mut verifier) = setup();
+        let edge = sign(&secret, NOW - WAKE_WINDOW_SECS, 1);
+        assert!(verifier.verify_at(&edge, NOW).is_ok());
+        let edge = sign(&secret, NOW + WAKE_WINDOW_SECS, 2);
+        assert!(verifier.verify_at(&edge, NOW).is_ok());
+        let old = sign(&secret, NOW - WAKE_WINDOW_SECS - 1, 3);
+        assert!(verifier.verify_at(&old, NOW).is_err());
+        let new = sign(&secret, NOW + WAKE_WINDOW_SECS + 1, 4);
+        assert!(verifier.verify_at(&new, NOW).is_err());
+    }
+
+    #[test]
+    fn replayed_requests_are_rejected() {
+        let (secret, mut verifier) = setup();
+        let req = sign(&secret, NOW, 1);
+        assert!(verifier.verify_at(&req, NOW).is_ok());
+        assert!(verifier.verify_at(&req, NOW + 1).is_err());
+        assert!(verifier.verify_at(&sign(&secret, NOW, 2), NOW + 1).is_ok());
+
+        // Once the nonce is forgotten, the old request has expired by its timestamp.
+        let later = NOW + 2 * WAKE_WINDOW_SECS;
+        assert!(verifier.verify_at(&req, later).is_err());
+        assert!(verifier.verify_at(&sign(&secret, later, 1), later).is_ok());
+    }
+
+    #[test]
+    fn tampered_requests_are_rejected() {
+        let (secret, mut verifier) = setup();
+        let req = sign(&secret, NOW, 1);
+
+        let mut mac = req.mac.clone().into_bytes();
+        mac[0] = if mac[0] == b'0' { b'1' } else { b'0' };
+        let tampered = WakeRequest {
+            mac: String::from_utf8(mac).unwrap(),
+            ..req.clone()
+        };
+        assert!(verifier.verify_at(&tampered, NOW).is_err());
+
+        let tampered = WakeRequest {
+            timestamp: NOW + 1,
+            ..req.clone()
+        };
+        assert!(verifier.verify_at(&tampered, NOW).is_err());
+
+        let (other, _) = setup();
+        assert!(verifier.verify_at(&sign(&other, NOW, 2), NOW).is_err());
+
+        // None of the rejected requests used up the nonce.
+        assert!(verifier.verify_at(&req, NOW).is_ok());
+    }
+
+    #[test]
+    fn header_round_trips() {
+        let (secret, _) = setup();
+        let req = sign(&secret, NOW, 7);
+        let parsed = WakeRequest::from_header(WakeAction::Start, &req.to_header());
+        assert_eq!(parsed, Some(req));
+    }
 }
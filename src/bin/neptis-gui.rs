@@ -11,7 +11,7 @@ extern crate url;
 use axoupdater::{
     AxoUpdater, AxoupdateError, ReleaseSource, ReleaseSourceType, UpdateRequest, Version,
 };
-use chrono::{Local, Utc};
+use chrono::{Local, TimeDelta, Utc};
 use cron::{Schedule, TimeUnitSpec};
 use inquire::list_option::ListOption;
 use inquire::{Editor, MultiSelect};
@@ -52,6 +52,70 @@ impl ToShortIdString for InternalMountDto {
     }
 }
 
+const SHARE_ACCESS: [&str; 4] = ["Read", "Write", "Share", "Manage"];
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+struct InternalShareDto {
+    user_name: String,
+    data_access: Vec<String>,
+    repo_access: Vec<String>,
+}
+
+impl ToShortIdString for InternalShareDto {
+    fn to_short_id_string(&self) -> String {
+        self.user_name.clone()
+    }
+}
+
+impl InternalShareDto {
+    fn to_access(flags: [Option<bool>; 4]) -> Vec<String> {
+        SHARE_ACCESS
+            .iter()
+            .zip(flags)
+            .filter(|(_, x)| x.unwrap_or(false))
+            .map(|(n, _)| n.to_string())
+            .collect()
+    }
+
+    fn to_flags(access: &[String]) -> [Option<bool>; 4] {
+        SHARE_ACCESS.map(|x| Some(access.iter().any(|y| y == x)))
+    }
+
+    fn prompt_access(title: &str, access: &mut Vec<String>) -> PromptResult {
+        let defaults = SHARE_ACCESS
+            .iter()
+            .enumerate()
+            .filter(|(_, x)| access.iter().any(|y| y == *x))
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>();
+        match MultiSelect::new(title, SHARE_ACCESS.to_vec())
+            .with_default(&defaults)
+            .prompt_skippable()
+            .expect("Failed to show prompt!")
+        {
+            Some(x) => {
+                *access = x.into_iter().map(|x| x.to_string()).collect();
+                PromptResult::Ok
+            }
+            None => PromptResult::Cancel,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+struct InternalPermissionDto {
+    permission: String,
+    /// The permission as it was pulled from the server, so an edit can revoke it first.
+    #[serde(skip)]
+    original: Option<String>,
+}
+
+impl ToShortIdString for InternalPermissionDto {
+    fn to_short_id_string(&self) -> String {
+        self.permission.clone()
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Default)]
 struct InternalTransferAutoSchedule {
     schedule_name: String,
@@ -245,7 +309,9 @@ impl UiApp {
                 println!("\n(No snapshot summary available)");
             }
 
-            println!("\n←/→ to browse | l to toggle lock | q or Enter to go back");
+            println!(
+                "\n←/→ to browse | l to toggle lock | e to edit | t to tag all | c to compare | q or Enter to go back"
+            );
 
             enable_raw_mode().expect("Failed to enable raw mode");
             let result = event::read();
@@ -290,6 +356,29 @@ impl UiApp {
                             }
                         }
                     }
+                    KeyCode::Char('e') => {
+                        if key.is_press()
+                            && let Some(updated) = self.on_edit_snapshot(mount, snapshot)
+                        {
+                            *snapshot = updated;
+                        }
+                    }
+                    KeyCode::Char('t') => {
+                        if key.is_press() {
+                            self.on_tag_snapshots(mount, &mut snapshots);
+                        }
+                    }
+                    KeyCode::Char('c') => {
+                        if key.is_press() {
+                            let current = snapshot.clone();
+                            let others = snapshots
+                                .iter()
+                                .filter(|x| x.id != current.id)
+                                .cloned()
+                                .collect::<Vec<_>>();
+                            self.on_compare_snapshots(mount, &current, &others);
+                        }
+                    }
                     KeyCode::Char('q') | KeyCode::Enter => {
                         if key.is_press() {
                             break;
@@ -304,6 +393,265 @@ impl UiApp {
         self.on_manage_snapshot(mount);
     }
 
+    /// Edits the label, description and tags of a snapshot. Returns the updated snapshot, or
+    /// `None` if nothing was saved.
+    fn on_edit_snapshot(&self, mount: &str, snapshot: &SnapshotFileDto) -> Option<SnapshotFileDto> {
+        clearscreen::clear().expect("Failed to clear screen!");
+        println!("**** Editing Snapshot: {}", snapshot.id);
+        let label = Text::new("Label:")
+            .with_initial_value(&snapshot.label)
+            .prompt_skippable()
+            .expect("Failed to show prompt!")?;
+        let description = Text::new("Description:")
+            .with_initial_value(snapshot.description.as_deref().unwrap_or_default())
+            .with_help_message("Leave empty to clear the description")
+            .prompt_skippable()
+            .expect("Failed to show prompt!")?;
+        let tags = Text::new("Tags:")
+            .with_initial_value(&snapshot.tags.join(", "))
+            .with_help_message("Separate tags with commas")
+            .prompt_skippable()
+            .expect("Failed to show prompt!")?;
+
+        let updated = SnapshotFileDto {
+            label: label.trim().to_string(),
+            description: Some(description.trim().to_string()).filter(|x| !x.is_empty()),
+            tags: PutForSnapshotApi::clean_tags(tags.split(',')),
+            ..snapshot.clone()
+        };
+        let dto = PutForSnapshotApi::from_changes(snapshot, &updated);
+        if dto.is_empty() {
+            println!("**** No changes were made!");
+            thread::sleep(Duration::from_secs(2));
+            return None;
+        }
+        let ret = {
+            let m_api = &*self.api.read().unwrap();
+            if let Some(api) = m_api {
+                self.rt
+                    .block_on(async { api.put_one_snapshot(mount, &snapshot.id, dto).await })
+            } else {
+                Err(NeptisError::Str("API is invalid!".into()))
+            }
+        };
+        match ret {
+            Ok(_) => Some(updated),
+            Err(e) => {
+                println!("**** Failed to update the snapshot. ****\n{}", e);
+                thread::sleep(Duration::from_secs(2));
+                None
+            }
+        }
+    }
+
+    /// Adds or removes tags on every snapshot being viewed.
+    fn on_tag_snapshots(&self, mount: &str, snapshots: &mut [SnapshotFileDto]) {
+        const STR_ADD: &str = "Add Tags";
+        const STR_REMOVE: &str = "Remove Tags";
+        clearscreen::clear().expect("Failed to clear screen!");
+        println!("**** Tagging {} snapshot(s)", snapshots.len());
+        let Some(mode) = Select::new("What do you want to do?", vec![STR_ADD, STR_REMOVE])
+            .prompt_skippable()
+            .expect("Failed to show prompt!")
+        else {
+            return;
+        };
+        let Some(tags) = Text::new("Tags:")
+            .with_help_message("Separate tags with commas")
+            .with_validator(required!())
+            .prompt_skippable()
+            .expect("Failed to show prompt!")
+        else {
+            return;
+        };
+        let tags = PutForSnapshotApi::clean_tags(tags.split(','));
+        let (add, remove) = if mode == STR_ADD {
+            (tags, vec![])
+        } else {
+            (vec![], tags)
+        };
+        let ids = snapshots.iter().map(|x| x.id.clone()).collect::<Vec<_>>();
+        println!("Please wait...");
+        let ret = {
+            let m_api = &*self.api.read().unwrap();
+            if let Some(api) = m_api {
+                self.rt.block_on(async {
+                    let n = api.tag_snapshots(mount, &ids, &add, &remove).await?;
+                    // Pull the snapshots again so the viewer shows the saved tags.
+                    for snapshot in snapshots.iter_mut() {
+                        *snapshot = api.get_one_snapshot(mount, &snapshot.id).await?;
+                    }
+                    Ok::<_, NeptisError>(n)
+                })
+            } else {
+                Err(NeptisError::Str("API is invalid!".into()))
+            }
+        };
+        match ret {
+            Ok(n) => println!("**** Updated the tags of {} snapshot(s).", n),
+            Err(e) => println!("**** Failed to update the tags. ****\n{}", e),
+        }
+        thread::sleep(Duration::from_secs(2));
+    }
+
+    /// Compares `current` with another snapshot of the point. When `others` is empty, the user
+    /// can pick any other snapshot of the point.
+    fn on_compare_snapshots(
+        &self,
+        mount: &str,
+        current: &SnapshotFileDto,
+        others: &[SnapshotFileDto],
+    ) {
+        let ret = {
+            let m_api = &*self.api.read().unwrap();
+            if let Some(api) = m_api {
+                let others = if others.is_empty() {
+                    self.rt
+                        .block_on(async { api.get_all_snapshots(mount).await })
+                        .unwrap_or_default()
+                        .into_iter()
+                        .filter(|x| x.id != current.id)
+                        .sorted_by_key(|x| std::cmp::Reverse(x.time))
+                        .collect()
+                } else {
+                    others.to_vec()
+                };
+                let other = match others.len() {
+                    0 => {
+                        println!("**** There are no other snapshots to compare with. ****");
+                        thread::sleep(Duration::from_secs(2));
+                        return;
+                    }
+                    1 => others[0].clone(),
+                    _ => {
+                        // Select has no skippable raw prompt; treat a cancelled prompt as "go back".
+                        let Ok(choice) = Select::new(
+                            "Please select a Snapshot to compare with",
+                            others.iter().map(|x| x.to_short_id_string()).collect(),
+                        )
+                        .raw_prompt() else {
+                            return;
+                        };
+                        others[choice.index].clone()
+                    }
+                };
+                let (from, to) = if other.time <= current.time {
+                    (other, current.clone())
+                } else {
+                    (current.clone(), other)
+                };
+                println!("Comparing snapshots, please wait...");
+                self.rt
+                    .block_on(async { api.diff_snapshots(mount, &from.id, &to.id).await })
+            } else {
+                Err(NeptisError::Str("API is invalid!".into()))
+            }
+        };
+        match ret {
+            Ok(diff) => Self::show_snapshot_diff(&diff),
+            Err(e) => {
+                println!("**** Failed to compare the snapshots. ****\n{}", e);
+                thread::sleep(Duration::from_secs(2));
+            }
+        }
+    }
+
+    /// Shows the changes of a snapshot comparison one directory at a time.
+    fn show_snapshot_diff(diff: &SnapshotDiff) {
+        const STR_UP: &str = ".. (Up)";
+        const STR_EXPORT: &str = "Export as JSON";
+        const STR_BACK: &str = "Go Back";
+        fn fmt_delta(delta: i64) -> String {
+            format!(
+                "{}{}",
+                if delta < 0 { "-" } else { "+" },
+                FileSize::prettify(delta.unsigned_abs())
+            )
+        }
+
+        let mut dir = String::from("/");
+        loop {
+            clearscreen::clear().expect("Failed to clear screen!");
+            let (added, removed, modified) = diff.counts();
+            println!("================= Snapshot Comparison =================");
+            println!("Point:        {}", diff.point);
+            println!("From:         {}", diff.from);
+            println!("To:           {}", diff.to);
+            println!(
+                "Files:        +{} added, -{} removed, ~{} modified",
+                added, removed, modified
+            );
+            println!("Size Change:  {}", fmt_delta(diff.total_delta()));
+            println!("Directory:    {}", dir);
+            println!("=======================================================\n");
+            if diff.is_empty() {
+                println!("The snapshots are identical.\n");
+            }
+
+            let nodes = diff.children(&dir);
+            let options = nodes
+                .iter()
+                .map(|x| {
+                    let marker = x.kind.map(|k| k.to_string()).unwrap_or(" ".into());
+                    if x.is_dir {
+                        format!(
+                            "{} {}/ [+{} -{} ~{}] ({})",
+                            marker,
+                            x.name,
+                            x.added,
+                            x.removed,
+                            x.modified,
+                            fmt_delta(x.delta)
+                        )
+                    } else {
+                        format!("{} {} ({})", marker, x.name, fmt_delta(x.delta))
+                    }
+                })
+                .chain((dir != "/").then(|| STR_UP.to_string()))
+                .chain([STR_EXPORT.to_string(), STR_BACK.to_string()])
+                .collect::<Vec<_>>();
+
+            // Select has no skippable raw prompt; treat a cancelled prompt as "go back".
+            let Ok(choice) = Select::new("Please select a directory to open", options).raw_prompt()
+            else {
+                return;
+            };
+            if let Some(node) = nodes.get(choice.index) {
+                if node.is_dir {
+                    dir = node.path.clone();
+                }
+                continue;
+            }
+            match choice.value.as_str() {
+                STR_UP => {
+                    dir = match dir.rsplit_once('/') {
+                        Some((parent, _)) if !parent.is_empty() => parent.to_string(),
+                        _ => "/".into(),
+                    };
+                }
+                STR_EXPORT => {
+                    let Some(path) = Text::new("Please enter the file to export to")
+                        .with_initial_value(&format!(
+                            "{}-{}-{}.json",
+                            diff.point, diff.from, diff.to
+                        ))
+                        .with_validator(required!())
+                        .prompt_skippable()
+                        .expect("Failed to show prompt!")
+                    else {
+                        continue;
+                    };
+                    match diff.to_json().and_then(|x| Ok(fs::write(&path, x)?)) {
+                        Ok(_) => println!("**** Exported the comparison to '{}'. ****", path),
+                        Err(e) => println!("**** Failed to export the comparison. ****\n{}", e),
+                    }
+                    thread::sleep(Duration::from_secs(2));
+                }
+                _ => return,
+            }
+        }
+    }
+
     fn on_select_rclone_job(&self, id: Uuid, schedule_name: &str, server_name: &str) {
         let mut last_refresh = Instant::now();
         let mut first_time = true;
@@ -1272,7 +1620,7 @@ impl UiApp {
                             "Job Type",
                             false,
                             |_, dto: &mut AutoJobDto| match CustomType::<AutoJobType>::new(
-                                "Please enter Job Type (Backup/Check)",
+                                "Please enter Job Type (Backup/Check/Prune)",
                             )
                             .with_starting_input(
                                 dto.job_type.to_string().replace("Unknown", "").as_str(),
@@ -1288,6 +1636,31 @@ impl UiApp {
                             },
                             |x| x.job_type.to_string(),
                         ),
+                        ModelProperty::new(
+                            "Retention",
+                            false,
+                            |_, dto: &mut AutoJobDto| {
+                                if dto.job_type != AutoJobType::Prune {
+                                    dto.retention = None;
+                                    return PromptResult::Ok;
+                                }
+                                match UiApp::prompt_retention_policy(
+                                    &dto.retention.clone().unwrap_or_default(),
+                                ) {
+                                    Some(x) => {
+                                        dto.retention = Some(x);
+                                        PromptResult::Ok
+                                    }
+                                    None => PromptResult::Cancel,
+                                }
+                            },
+                            |x| {
+                                x.retention
+                                    .as_ref()
+                                    .map(|x| x.to_string())
+                                    .unwrap_or("-".into())
+                            },
+                        ),
                     ],
                     Box::new({
                         let mount_owned = mount_owned.clone();
@@ -1333,6 +1706,7 @@ impl UiApp {
                                     cron_schedule: dto.cron_schedule.clone(),
                                     job_type: dto.job_type.clone(),
                                     enabled: dto.enabled,
+                                    retention: dto.retention.clone(),
                                 },
                             )
                             .await
@@ -1365,6 +1739,7 @@ impl UiApp {
     fn get_snapshot_mm<'a>(
         api: &'a WebApi,
         mount: &str,
+        tag: Option<String>,
     ) -> ModelManager<'a, SnapshotFileDto, WebApi> {
         let mount_owned = mount.to_string(); // make it owned
         ModelManager::new(
@@ -1383,7 +1758,11 @@ impl UiApp {
                         .as_deref()
                         .ok_or(NeptisError::Str("API is not valid!".into()))?;
                     let mount_inner = mount_owned.clone(); // clone again for async block
+                    let tag_inner = tag.clone();
                     ctx.rt.block_on(async move {
+                        if let Some(tag) = tag_inner {
+                            return api.get_snapshots_by_tag(&mount_inner, &tag).await;
+                        }
                         let mut ret = api.get_all_snapshots(&mount_inner).await?;
                         ret.sort_by_key(|x| std::cmp::Reverse(x.time));
                         Ok(ret)
@@ -1411,14 +1790,38 @@ impl UiApp {
         api: &WebApi,
         mount: &str,
     ) -> Result<Option<SnapshotFileDto>, NeptisError> {
-        Self::get_snapshot_mm(api, mount).do_display()
+        Self::get_snapshot_mm(api, mount, None).do_display()
     }
 
     fn do_raw_multi_snapshot_select(
         api: &WebApi,
         mount: &str,
+        tag: Option<String>,
     ) -> Result<Vec<SnapshotFileDto>, NeptisError> {
-        Self::get_snapshot_mm(api, mount).do_multi_display()
+        Self::get_snapshot_mm(api, mount, tag).do_multi_display()
+    }
+
+    /// Asks which tag to filter the snapshots by. Returns `None` if the user cancelled.
+    fn prompt_snapshot_tag(api: &WebApi, rt: &Runtime, mount: &str) -> Option<Option<String>> {
+        const STR_ALL: &str = "(All Snapshots)";
+        let tags = rt
+            .block_on(async { api.get_all_snapshots(mount).await })
+            .unwrap_or_default()
+            .into_iter()
+            .flat_map(|x| x.tags)
+            .unique()
+            .sorted()
+            .collect::<Vec<_>>();
+        if tags.is_empty() {
+            return Some(None);
+        }
+        let choice = Select::new(
+            "Please select a Tag to filter by",
+            once(STR_ALL.to_string()).chain(tags).collect(),
+        )
+        .prompt_skippable()
+        .expect("Failed to show prompt!")?;
+        Some((choice != STR_ALL).then_some(choice))
     }
 
     // inspected
@@ -1426,7 +1829,10 @@ impl UiApp {
         let ret = {
             let m_api = &*self.api.read().unwrap();
             if let Some(api) = m_api {
-                Self::do_raw_multi_snapshot_select(api, mount)
+                match Self::prompt_snapshot_tag(api, &self.rt, mount) {
+                    Some(tag) => Self::do_raw_multi_snapshot_select(api, mount, tag),
+                    None => Ok(vec![]),
+                }
             } else {
                 Err(NeptisError::Str("API is invalid!".into()))
             }
@@ -1496,6 +1902,22 @@ impl UiApp {
                 _ => Some(false),
             };
 
+            let backup = match (mode, opt) {
+                (JobType::Backup, Some(_)) => self.prompt_backup_options(mount),
+                _ => None,
+            };
+            let restore = match (mode, s_ret.as_ref()) {
+                (JobType::Restore, Some(snapshot)) => self.prompt_restore_options(mount, snapshot),
+                _ => None,
+            };
+            let opt = if (mode == JobType::Backup && backup.is_none())
+                || (mode == JobType::Restore && restore.is_none())
+            {
+                None
+            } else {
+                opt
+            };
+
             if let Some(opt) = opt {
                 if Confirm::new("Do you want to proceed?")
                     .with_default(true)
@@ -1505,16 +1927,19 @@ impl UiApp {
                 {
                     // Initialize the job and attempt to show it off.
                     println!("Creating job...");
+                    let is_dry_run = backup.as_ref().is_some_and(|x| x.dry_run);
                     let ret = {
                         let m_api = &*self.api.read().unwrap();
                         if let Some(api) = m_api {
                             self.rt.block_on(async {
                                 match mode {
-                                    JobType::Backup => api.post_one_backup(mount, opt).await,
+                                    JobType::Backup => {
+                                        api.post_one_backup_with(mount, opt, backup.unwrap())
+                                            .await
+                                    }
                                     JobType::Check => api.post_one_check(mount).await,
                                     JobType::Restore => {
-                                        api.post_one_restore(mount, s_ret.unwrap().id.as_str())
-                                            .await
+                                        api.post_one_restore_with(mount, restore.unwrap()).await
                                     }
                                     _ => Err(NeptisError::Str("Invalid option selected".into())),
                                 }
@@ -1526,14 +1951,16 @@ impl UiApp {
                     match ret {
                         Ok(x) => {
                             println!("**** Job created successfully! ID: {}", x.id);
+                            if is_dry_run {
+                                println!(
+                                    "**** This is a dry-run: the job messages will list what would be backed up."
+                                );
+                            }
                             thread::sleep(Duration::from_secs(2));
                             self.on_select_job(mount, x.id.clone(), Some(mount.to_string()));
                         }
                         Err(e) => {
-                            println!(
-                                "**** An unexpected error has occurred while changing the password. ****\n{}",
-                                e.to_string()
-                            );
+                            println!("**** Failed to create the job. ****\n{}", e);
                             thread::sleep(Duration::from_secs(2));
                             self.on_select_mount(mount);
                         }
@@ -1551,56 +1978,348 @@ impl UiApp {
         }
     }
 
-    // inspected
-    fn on_select_mount(&self, mount: &str) {
-        clearscreen::clear().expect("Failed to clear screen!");
-        println!("**** Currently selected Mount: {}", mount);
-        // Attempt to pull the real up-to-date information on the mount.
-        match {
-            let mut stats = None;
-            let mut smb = None;
+    /// Asks for the snapshot tags, label, description and whether to only do a dry-run.
+    fn prompt_backup_options(&self, mount: &str) -> Option<PostForBackupApi> {
+        let user_name = {
             let m_api = &*self.api.read().unwrap();
-            if let Some(api) = m_api {
-                stats = self.rt.block_on(async {
-                    api.get_all_mounts()
-                        .await
-                        .ok()
-                        .map(|x| x.into_iter().find(|y| y.name == mount))
-                        .flatten()
-                });
-                smb = self.rt.block_on(async {
-                    api.get_one_user(&api.get_username())
-                        .await
-                        .ok()
-                        .map(|x| x.is_smb)
-                })
+            m_api.as_ref().map(|x| x.get_username())?
+        };
+        let mut ret = PostForBackupApi::new(user_name, mount);
+        let tags = Text::new("Please enter any Tags for the snapshot")
+            .with_help_message("Separate multiple tags with a comma, or leave empty for none")
+            .prompt_skippable()
+            .expect("Failed to show prompt!")?;
+        let tags = tags
+            .split(',')
+            .map(|x| x.trim().to_string())
+            .filter(|x| !x.is_empty())
+            .unique()
+            .collect::<Vec<_>>();
+        ret.tags = (!tags.is_empty()).then_some(tags);
+        ret.label = Some(
+            Text::new("Please enter a Label for the snapshot")
+                .with_help_message("Leave empty for none")
+                .prompt_skippable()
+                .expect("Failed to show prompt!")?,
+        )
+        .filter(|x| !x.trim().is_empty());
+        ret.description = Some(
+            Text::new("Please enter a Description for the snapshot")
+                .with_help_message("Leave empty for none")
+                .prompt_skippable()
+                .expect("Failed to show prompt!")?,
+        )
+        .filter(|x| !x.trim().is_empty());
+        ret.dry_run = Confirm::new("Do you want to do a dry-run only?")
+            .with_help_message("A dry-run reports what would be backed up without creating a snapshot")
+            .with_default(false)
+            .prompt_skippable()
+            .expect("Failed to show prompt!")?;
+        Some(ret)
+    }
+
+    /// Asks which paths to restore and where to, then offers a dry-run preview of the changes.
+    fn prompt_restore_options(
+        &self,
+        mount: &str,
+        snapshot: &SnapshotFileDto,
+    ) -> Option<PostForRestoreApi> {
+        const STR_ORIGINAL: &str = "Original Point (overwrite)";
+        let (user_name, mounts) = {
+            let m_api = &*self.api.read().unwrap();
+            let api = m_api.as_ref()?;
+            (
+                api.get_username(),
+                self.rt
+                    .block_on(async { api.get_all_mounts().await })
+                    .unwrap_or_default(),
+            )
+        };
+        let mut ret = PostForRestoreApi::new(user_name.as_str(), mount, snapshot.id.as_str());
+
+        if !snapshot.paths.is_empty() {
+            let all = (0..snapshot.paths.len()).collect::<Vec<_>>();
+            let paths = MultiSelect::new("Please select the Paths to restore", snapshot.paths.clone())
+                .with_default(&all)
+                .prompt_skippable()
+                .expect("Failed to show prompt!")?;
+            if paths.is_empty() {
+                println!("**** No paths were selected!");
+                thread::sleep(Duration::from_secs(2));
+                return None;
             }
-            (stats, smb.unwrap_or(false))
-        } {
-            (Some(stats), smb) => {
-                let d_total = stats.usage.b_data_total;
-                let d_used = stats.usage.b_data_used;
-                let d_free = d_total - d_used;
+            if paths.len() != snapshot.paths.len() {
+                ret.paths = Some(paths);
+            }
+        }
 
-                let r_total = stats.usage.b_repo_total;
-                let r_used = stats.usage.b_repo_used;
-                let r_free = r_total - r_used;
+        let targets = mounts
+            .into_iter()
+            .filter(|x| !(x.name == mount && x.owned_by == user_name))
+            .map(|x| (x.owned_by, x.name))
+            .collect::<Vec<_>>();
+        if !targets.is_empty() {
+            // Select has no skippable raw prompt; treat a cancelled prompt as "go back".
+            let sel = Select::new(
+                "Please select where to restore to",
+                once(STR_ORIGINAL.to_string())
+                    .chain(targets.iter().map(|(u, n)| format!("{} (owned by {})", n, u)))
+                    .collect(),
+            )
+            .raw_prompt()
+            .ok()?;
+            if sel.index > 0 {
+                let (u, n) = targets[sel.index - 1].clone();
+                ret.target_user = Some(u);
+                ret.target_name = Some(n);
+            }
+        }
 
-                fn prettify_bytes(
-                    b_total: i64,
-                    b_used: Option<i64>,
-                    b_free: Option<i64>,
-                ) -> String {
-                    format!(
-                        "{} / {} ({} remaining)",
-                        b_used
-                            .map(|x| FileSize::prettify(x as u64))
-                            .unwrap_or("N/A".into()),
-                        FileSize::prettify(b_total as u64),
-                        b_free
-                            .map(|x| FileSize::prettify(x as u64))
-                            .unwrap_or("unknown space".into())
-                    )
+        println!(
+            "Restore Paths: {}",
+            ret.paths
+                .as_ref()
+                .map(|x| x.join(", "))
+                .unwrap_or("ALL".into())
+        );
+        println!(
+            "Restore To: {}\n",
+            ret.target_name.as_deref().unwrap_or(mount)
+        );
+        if Confirm::new("Do you want to preview the changes first (dry-run)?")
+            .with_default(true)
+            .prompt_skippable()
+            .expect("Failed to show prompt!")?
+            && !self.on_preview_restore(mount, &ret)
+        {
+            return None;
+        }
+        Some(ret)
+    }
+
+    /// Shows what the restore would change. Returns `true` if the user wants to continue.
+    fn on_preview_restore(&self, mount: &str, dto: &PostForRestoreApi) -> bool {
+        const MAX_SHOWN: usize = 20;
+        println!("Running a dry-run, please wait...");
+        let ret = {
+            let m_api = &*self.api.read().unwrap();
+            if let Some(api) = m_api {
+                self.rt
+                    .block_on(async { api.preview_restore(mount, dto.clone()).await })
+            } else {
+                Err(NeptisError::Str("API is not valid!".into()))
+            }
+        };
+        clearscreen::clear().expect("Failed to clear screen!");
+        match ret {
+            Ok(preview) => {
+                println!("================= Restore Preview =================");
+                println!("These are the messages of a dry run, not an exact list of changes.\n");
+                println!("Messages ({}):", preview.messages.len());
+                for msg in preview.messages.iter().take(MAX_SHOWN) {
+                    println!("  {}", msg);
+                }
+                if preview.messages.len() > MAX_SHOWN {
+                    println!("  ... and {} more", preview.messages.len() - MAX_SHOWN);
+                }
+                if !preview.errors.is_empty() {
+                    println!("Errors ({}):\n{}", preview.errors.len(), preview.errors.join("\n"));
+                }
+                if preview.is_empty() {
+                    println!("\nThe dry run did not report anything.");
+                }
+                println!("===================================================\n");
+            }
+            Err(e) => {
+                println!("**** Failed to preview the restore. ****\n{}\n", e);
+            }
+        }
+        Confirm::new("Do you want to continue with the restore?")
+            .with_default(false)
+            .prompt_skippable()
+            .expect("Failed to show prompt!")
+            .unwrap_or(false)
+    }
+
+    /// Asks for each retention rule, starting from `current`. Returns `None` if cancelled.
+    fn prompt_retention_policy(current: &RetentionPolicy) -> Option<RetentionPolicy> {
+        fn prompt_count(msg: &str, current: Option<u32>) -> Option<Option<u32>> {
+            let ret = Text::new(msg)
+                .with_help_message("Leave empty to disable this rule")
+                .with_initial_value(&current.map(|x| x.to_string()).unwrap_or_default())
+                .with_validator(|x: &str| {
+                    if x.trim().is_empty() || x.trim().parse::<u32>().is_ok() {
+                        Ok(Validation::Valid)
+                    } else {
+                        Ok(Validation::Invalid("Please enter a whole number!".into()))
+                    }
+                })
+                .prompt_skippable()
+                .expect("Failed to show prompt!")?;
+            Some(ret.trim().parse::<u32>().ok())
+        }
+
+        println!("**** Locked snapshots are always kept.");
+        let mut ret = RetentionPolicy {
+            keep_last: prompt_count("Keep the last N snapshots", current.keep_last)?,
+            keep_daily: prompt_count("Keep N daily snapshots", current.keep_daily)?,
+            keep_weekly: prompt_count("Keep N weekly snapshots", current.keep_weekly)?,
+            keep_monthly: prompt_count("Keep N monthly snapshots", current.keep_monthly)?,
+            keep_yearly: prompt_count("Keep N yearly snapshots", current.keep_yearly)?,
+            keep_within: None,
+        };
+        let within = Text::new("Keep all snapshots within")
+            .with_help_message("ex. 30d or 1y6m (h/d/w/m/y); leave empty to disable this rule")
+            .with_initial_value(current.keep_within.as_deref().unwrap_or_default())
+            .with_validator(|x: &str| {
+                if x.trim().is_empty() || RetentionPolicy::parse_within(x).is_some() {
+                    Ok(Validation::Valid)
+                } else {
+                    Ok(Validation::Invalid("The duration is not valid!".into()))
+                }
+            })
+            .prompt_skippable()
+            .expect("Failed to show prompt!")?;
+        ret.keep_within = Some(within.trim().to_string()).filter(|x| !x.is_empty());
+        if ret.is_empty() {
+            println!("**** At least one rule is required!");
+            thread::sleep(Duration::from_secs(2));
+            return None;
+        }
+        Some(ret)
+    }
+
+    fn on_start_prune(&self, mount: &str) {
+        clearscreen::clear().expect("Failed to clear screen!");
+        let Some(policy) = Self::prompt_retention_policy(&RetentionPolicy::default()) else {
+            self.on_select_mount(mount);
+            return;
+        };
+        let ret = {
+            let m_api = &*self.api.read().unwrap();
+            if let Some(api) = m_api {
+                self.rt
+                    .block_on(async { api.preview_prune(mount, &policy).await })
+            } else {
+                Err(NeptisError::Str("API is not valid!".into()))
+            }
+        };
+        let plan = match ret {
+            Ok(x) => x,
+            Err(e) => {
+                println!("**** Failed to preview the prune. ****\n{}", e);
+                thread::sleep(Duration::from_secs(2));
+                self.on_select_mount(mount);
+                return;
+            }
+        };
+
+        clearscreen::clear().expect("Failed to clear screen!");
+        println!("================= Prune Preview =================");
+        println!("Policy: {}\n", policy);
+        println!("Kept ({}):", plan.keep.len());
+        for (snap, reasons) in plan.keep.iter() {
+            println!(
+                "  {} ({})",
+                snap.to_short_id_string(),
+                reasons.iter().map(|x| x.to_string()).join(", ")
+            );
+        }
+        println!("\nRemoved ({}):", plan.remove.len());
+        for snap in plan.remove.iter() {
+            println!("  {}", snap.to_short_id_string());
+        }
+        println!("=================================================\n");
+        if plan.remove.is_empty() {
+            println!("**** Nothing would be removed!");
+            thread::sleep(Duration::from_secs(2));
+            self.on_select_mount(mount);
+            return;
+        }
+        if !Confirm::new("Do you want to remove these snapshots?")
+            .with_default(false)
+            .prompt_skippable()
+            .expect("Failed to show prompt!")
+            .unwrap_or(false)
+        {
+            self.on_select_mount(mount);
+            return;
+        }
+
+        println!("Creating job...");
+        let ret = {
+            let m_api = &*self.api.read().unwrap();
+            if let Some(api) = m_api {
+                self.rt
+                    .block_on(async { api.post_one_prune(mount, &policy, &plan).await })
+            } else {
+                Err(NeptisError::Str("API is not valid!".into()))
+            }
+        };
+        match ret {
+            Ok(x) => {
+                println!("**** Job created successfully! ID: {}", x.id);
+                thread::sleep(Duration::from_secs(2));
+                self.on_select_job(mount, x.id, Some(mount.to_string()));
+            }
+            Err(e) => {
+                println!("**** Failed to create the job. ****\n{}", e);
+                thread::sleep(Duration::from_secs(2));
+                self.on_select_mount(mount);
+            }
+        }
+    }
+
+    // inspected
+    fn on_select_mount(&self, mount: &str) {
+        clearscreen::clear().expect("Failed to clear screen!");
+        println!("**** Currently selected Mount: {}", mount);
+        // Attempt to pull the real up-to-date information on the mount.
+        match {
+            let mut stats = None;
+            let mut smb = None;
+            let m_api = &*self.api.read().unwrap();
+            if let Some(api) = m_api {
+                stats = self.rt.block_on(async {
+                    api.get_all_mounts()
+                        .await
+                        .ok()
+                        .map(|x| x.into_iter().find(|y| y.name == mount))
+                        .flatten()
+                });
+                smb = self.rt.block_on(async {
+                    api.get_one_user(&api.get_username())
+                        .await
+                        .ok()
+                        .map(|x| x.is_smb)
+                })
+            }
+            (stats, smb.unwrap_or(false))
+        } {
+            (Some(stats), smb) => {
+                let d_total = stats.usage.b_data_total;
+                let d_used = stats.usage.b_data_used;
+                let d_free = d_total - d_used;
+
+                let r_total = stats.usage.b_repo_total;
+                let r_used = stats.usage.b_repo_used;
+                let r_free = r_total - r_used;
+
+                fn prettify_bytes(
+                    b_total: i64,
+                    b_used: Option<i64>,
+                    b_free: Option<i64>,
+                ) -> String {
+                    format!(
+                        "{} / {} ({} remaining)",
+                        b_used
+                            .map(|x| FileSize::prettify(x as u64))
+                            .unwrap_or("N/A".into()),
+                        FileSize::prettify(b_total as u64),
+                        b_free
+                            .map(|x| FileSize::prettify(x as u64))
+                            .unwrap_or("unknown space".into())
+                    )
                 }
                 if smb {
                     println!(
@@ -1656,23 +2375,31 @@ impl UiApp {
         const STR_START_BACKUP: &'static str = "Start Backup";
         const STR_START_CHECK: &'static str = "Start Check";
         const STR_START_RESTORE: &'static str = "Start Restore";
+        const STR_START_PRUNE: &'static str = "Prune Snapshots";
+        const STR_MANAGE_SHARING: &'static str = "Manage Sharing";
         const STR_GO_BACK: &'static str = "Go Back";
 
-        match Select::new(
-            "Please select your desired action",
-            vec![
-                STR_GO_BACK,
-                STR_MANAGE_SNAPSHOT,
-                STR_MANAGE_JOB,
-                STR_MANAGE_AUTO_JOB,
-                STR_START_BACKUP,
-                STR_START_CHECK,
-                STR_START_RESTORE,
-            ],
-        )
-        .prompt_skippable()
-        .expect("Failed to show prompt!")
-        .unwrap_or(STR_GO_BACK)
+        let mut menu_items = vec![
+            STR_GO_BACK,
+            STR_MANAGE_SNAPSHOT,
+            STR_MANAGE_JOB,
+            STR_MANAGE_AUTO_JOB,
+            STR_START_BACKUP,
+            STR_START_CHECK,
+            STR_START_RESTORE,
+            STR_START_PRUNE,
+            STR_MANAGE_SHARING,
+        ];
+        menu_items.retain(|x| match *x {
+            STR_START_PRUNE => self.supports(Feature::Prune),
+            STR_MANAGE_SHARING => self.supports(Feature::Shares),
+            _ => true,
+        });
+
+        match Select::new("Please select your desired action", menu_items)
+            .prompt_skippable()
+            .expect("Failed to show prompt!")
+            .unwrap_or(STR_GO_BACK)
         {
             STR_MANAGE_SNAPSHOT => self.on_manage_snapshot(mount),
             STR_MANAGE_JOB => self.on_view_jobs(mount, None),
@@ -1680,22 +2407,232 @@ impl UiApp {
             STR_START_BACKUP => self.on_start_job(mount, JobType::Backup),
             STR_START_CHECK => self.on_start_job(mount, JobType::Check),
             STR_START_RESTORE => self.on_start_job(mount, JobType::Restore),
+            STR_START_PRUNE => self.on_start_prune(mount),
+            STR_MANAGE_SHARING => self.on_manage_sharing(mount),
             _ => self.show_points(), // go back
         }
     }
 
+    fn on_manage_sharing(&self, mount: &str) {
+        let ret = {
+            let mount_owned = mount.to_string();
+            let m_api = &*self.api.read().unwrap();
+            if let Some(api) = m_api {
+                ModelManager::new(
+                    Some(api),
+                    vec![
+                        ModelProperty::new(
+                            "User Name",
+                            true,
+                            |_, dto: &mut InternalShareDto| match Text::new(
+                                "Please enter the User to share with",
+                            )
+                            .with_validator(required!())
+                            .with_initial_value(dto.user_name.as_str())
+                            .prompt_skippable()
+                            .expect("Failed to show prompt!")
+                            {
+                                Some(x) => {
+                                    dto.user_name = x.trim().to_string();
+                                    PromptResult::Ok
+                                }
+                                None => PromptResult::Cancel,
+                            },
+                            |x| x.user_name.clone(),
+                        ),
+                        ModelProperty::new(
+                            "Data Access",
+                            false,
+                            |_, dto: &mut InternalShareDto| {
+                                InternalShareDto::prompt_access(
+                                    "Please select Data access (none to unshare)",
+                                    &mut dto.data_access,
+                                )
+                            },
+                            |x| x.data_access.join(", "),
+                        ),
+                        ModelProperty::new(
+                            "Repo Access",
+                            false,
+                            |_, dto: &mut InternalShareDto| {
+                                InternalShareDto::prompt_access(
+                                    "Please select Repo access (none to unshare)",
+                                    &mut dto.repo_access,
+                                )
+                            },
+                            |x| x.repo_access.join(", "),
+                        ),
+                    ],
+                    Box::new({
+                        let mount_owned = mount_owned.clone();
+                        move |ctx| {
+                            let api = ctx
+                                .api
+                                .as_deref()
+                                .ok_or(NeptisError::Str("API is not valid!".into()))?;
+                            let mount_inner = mount_owned.clone();
+                            ctx.rt.block_on(async move {
+                                let owner = api.get_username();
+                                let mut ret: Vec<InternalShareDto> = vec![];
+                                for share in api.get_data_shares(&owner, &mount_inner, None).await? {
+                                    ret.push(InternalShareDto {
+                                        user_name: share.user_name.flatten().unwrap_or_default(),
+                                        data_access: InternalShareDto::to_access([
+                                            share.can_read,
+                                            share.can_write,
+                                            share.can_share,
+                                            share.can_manage,
+                                        ]),
+                                        repo_access: vec![],
+                                    });
+                                }
+                                for share in api.get_repo_shares(&owner, &mount_inner, None).await? {
+                                    let user_name = share.user_name.flatten().unwrap_or_default();
+                                    let access = InternalShareDto::to_access([
+                                        share.can_read,
+                                        share.can_write,
+                                        share.can_share,
+                                        share.can_manage,
+                                    ]);
+                                    match ret.iter_mut().find(|x| x.user_name == user_name) {
+                                        Some(x) => x.repo_access = access,
+                                        None => ret.push(InternalShareDto {
+                                            user_name,
+                                            data_access: vec![],
+                                            repo_access: access,
+                                        }),
+                                    }
+                                }
+                                Ok(ret)
+                            })
+                        }
+                    }),
+                )
+                .with_back()
+                .with_delete(Box::new({
+                    let mount_owned = mount_owned.clone();
+                    move |ctx, dto| {
+                        let api = ctx
+                            .api
+                            .as_deref()
+                            .ok_or(NeptisError::Str("API is not valid!".into()))?;
+                        let mount_inner = mount_owned.clone();
+                        ctx.rt.block_on(async move {
+                            let owner = api.get_username();
+                            for ret in [
+                                api.delete_data_share(&owner, &mount_inner, &dto.user_name)
+                                    .await,
+                                api.delete_repo_share(&owner, &mount_inner, &dto.user_name)
+                                    .await,
+                            ] {
+                                match ret {
+                                    Ok(_) | Err(NeptisError::NotFound(_)) => {}
+                                    Err(e) => return Err(e),
+                                }
+                            }
+                            Ok(())
+                        })
+                    }
+                }))
+                .with_modify(Box::new(move |ctx, _, dto| {
+                    let api = ctx
+                        .api
+                        .as_deref()
+                        .ok_or(NeptisError::Str("API is not valid!".into()))?;
+                    let mount_inner = mount_owned.clone();
+                    ctx.rt.block_on(async move {
+                        let owner = api.get_username();
+                        let user = dto.user_name.as_str();
+                        let [can_read, can_write, can_share, can_manage] =
+                            InternalShareDto::to_flags(&dto.data_access);
+                        match if dto.data_access.is_empty() {
+                            api.delete_data_share(&owner, &mount_inner, user).await
+                        } else {
+                            api.put_data_share(
+                                &owner,
+                                &mount_inner,
+                                DataPointShareDto {
+                                    user_name: Some(Some(user.to_string())),
+                                    can_read,
+                                    can_write,
+                                    can_share,
+                                    can_manage,
+                                },
+                            )
+                            .await
+                            .map(|_| ())
+                        } {
+                            Ok(_) | Err(NeptisError::NotFound(_)) => {}
+                            Err(e) => return Err(e),
+                        }
+                        let [can_read, can_write, can_share, can_manage] =
+                            InternalShareDto::to_flags(&dto.repo_access);
+                        match if dto.repo_access.is_empty() {
+                            api.delete_repo_share(&owner, &mount_inner, user).await
+                        } else {
+                            api.put_repo_share(
+                                &owner,
+                                &mount_inner,
+                                RepoPointShareDto {
+                                    user_name: Some(Some(user.to_string())),
+                                    can_read,
+                                    can_write,
+                                    can_share,
+                                    can_manage,
+                                },
+                            )
+                            .await
+                            .map(|_| ())
+                        } {
+                            Ok(_) | Err(NeptisError::NotFound(_)) => Ok(()),
+                            Err(e) => Err(e),
+                        }
+                    })
+                }))
+                .do_display()
+            } else {
+                Err(NeptisError::Str("API is not valid!".into()))
+            }
+        };
+        match ret {
+            Ok(Some(_)) => self.on_manage_sharing(mount),
+            Ok(None) => self.on_select_mount(mount),
+            Err(e) => {
+                println!("**** Failed to manage sharing. ****\n{}", e);
+                thread::sleep(Duration::from_secs(2));
+                self.on_select_mount(mount);
+            }
+        }
+    }
+
     // inspected
     fn on_select_user(&self, user: &UserDto, ack: bool) {
         clearscreen::clear().expect("Failed to clear screen!");
 
-        if ack
-            || Confirm::new(
-                "The only available option is to change the password. Do you want to do this?",
+        const STR_CHANGE_PASSWORD: &'static str = "Change Password";
+        const STR_MANAGE_PERMS: &'static str = "Manage Permissions";
+        const STR_GO_BACK: &'static str = "Go Back";
+        let choice = if ack {
+            STR_CHANGE_PASSWORD
+        } else {
+            let mut menu_items = vec![STR_CHANGE_PASSWORD, STR_MANAGE_PERMS, STR_GO_BACK];
+            if !self.supports(Feature::Permissions) {
+                menu_items.retain(|x| *x != STR_MANAGE_PERMS);
+            }
+            Select::new(
+                &format!("Please select an action for {}", user.user_name.as_str()),
+                menu_items,
             )
             .prompt_skippable()
             .expect("Failed to show prompt!")
-            .unwrap_or(false)
-        {
+            .unwrap_or(STR_GO_BACK)
+        };
+        if choice == STR_MANAGE_PERMS {
+            self.on_manage_user_perms(user);
+            return;
+        }
+
+        if choice == STR_CHANGE_PASSWORD {
             let a_str = format!("Please enter password for {}", user.user_name.as_str());
             if let Some(p) = Password::new(a_str.as_str())
                 .with_validator(required!())
@@ -1741,10 +2678,128 @@ impl UiApp {
                         thread::sleep(Duration::from_secs(2));
                         self.show_users();
                     }
-                }
+                }
+            }
+        }
+        self.show_users();
+    }
+
+    fn on_manage_user_perms(&self, user: &UserDto) {
+        let ret = {
+            let user_owned = user.user_name.clone();
+            let m_api = &*self.api.read().unwrap();
+            if let Some(api) = m_api {
+                ModelManager::new(
+                    Some(api),
+                    vec![ModelProperty::new(
+                        "Permission",
+                        false,
+                        |ctx, dto: &mut InternalPermissionDto| {
+                            let valid = match ctx.api {
+                                Some(api) => ctx
+                                    .rt
+                                    .block_on(async { api.get_valid_perms().await })
+                                    .unwrap_or_default(),
+                                None => vec![],
+                            };
+                            if valid.is_empty() {
+                                println!("**** Failed to pull the valid permissions!");
+                                thread::sleep(Duration::from_secs(2));
+                                return PromptResult::Cancel;
+                            }
+                            match Select::new("Please select the Permission to grant", valid)
+                                .prompt_skippable()
+                                .expect("Failed to show prompt!")
+                            {
+                                Some(x) => {
+                                    dto.permission = x;
+                                    PromptResult::Ok
+                                }
+                                None => PromptResult::Cancel,
+                            }
+                        },
+                        |x| x.permission.clone(),
+                    )],
+                    Box::new({
+                        let user_owned = user_owned.clone();
+                        move |ctx| {
+                            let api = ctx
+                                .api
+                                .ok_or(NeptisError::Str("API is not valid!".into()))?;
+                            let user_inner = user_owned.clone();
+                            ctx.rt.block_on(async move {
+                                Ok(api
+                                    .get_user_perms(&user_inner)
+                                    .await?
+                                    .into_iter()
+                                    .filter_map(|x| x.permission.flatten())
+                                    .map(|permission| InternalPermissionDto {
+                                        original: Some(permission.clone()),
+                                        permission,
+                                    })
+                                    .collect())
+                            })
+                        }
+                    }),
+                )
+                .with_select_title(format!(
+                    "Permissions for {} (select several to revoke them at once)",
+                    user_owned
+                ))
+                .with_back()
+                .with_delete(Box::new({
+                    let user_owned = user_owned.clone();
+                    move |ctx, dto| {
+                        let api = ctx
+                            .api
+                            .ok_or(NeptisError::Str("API is not valid!".into()))?;
+                        let user_inner = user_owned.clone();
+                        ctx.rt.block_on(async move {
+                            api.revoke_user_perm(&user_inner, &dto.permission).await
+                        })
+                    }
+                }))
+                .with_modify(Box::new(move |ctx, _, dto| {
+                    let api = ctx
+                        .api
+                        .ok_or(NeptisError::Str("API is not valid!".into()))?;
+                    let user_inner = user_owned.clone();
+                    ctx.rt.block_on(async move {
+                        // Changing a permission replaces it, rather than granting both.
+                        if let Some(old) = dto.original.as_ref().filter(|x| **x != dto.permission) {
+                            api.revoke_user_perm(&user_inner, old).await?;
+                        }
+                        api.grant_user_perm(&user_inner, &dto.permission).await
+                    })
+                }))
+                .do_multi_display()
+            } else {
+                Err(NeptisError::Str("API is not valid!".into()))
+            }
+        };
+        match ret {
+            Ok(x) if !x.is_empty() => self.on_manage_user_perms(user),
+            Ok(_) => self.on_select_user(user, false),
+            Err(e) => {
+                println!("**** Failed to manage permissions. ****\n{}", e);
+                thread::sleep(Duration::from_secs(2));
+                self.show_users();
             }
         }
-        self.show_users();
+    }
+
+    /// Returns whether the connected server supports `feature`. Everything is assumed to be
+    /// supported when the server could not be asked.
+    fn supports(&self, feature: Feature) -> bool {
+        let m_api = &*self.api.read().unwrap();
+        match m_api {
+            Some(api) => self
+                .rt
+                .block_on(async { api.capabilities().await })
+                .map(|x| x.supports(feature))
+                .unwrap_or(true),
+            None => true,
+        }
     }
 
     fn get_luser_stats(&self, api: &WebApi, is_breakdown: bool) -> (String, bool) {
@@ -1836,25 +2891,43 @@ impl UiApp {
             "**** Sent request. Server responded with Job #{:.6}...",
             &id.to_string()
         );
-        thread::sleep(Duration::from_secs(2));
-        for i in 0..20 {
-            let job = api.get_one_job(id).await?;
-            if job.job_status == JobStatus::Successful {
+        let opts = JobWaitOptions::default()
+            .with_start_delay(Duration::from_secs(2))
+            .with_timeout(Duration::from_secs(42))
+            .with_progress(|progress| {
+                if matches!(progress.status, JobStatus::Successful | JobStatus::Failed) {
+                    return;
+                }
+                match progress.fraction() {
+                    Some(x) => println!("> Waiting for job to finish... ({:.0}%)", x * 100.0),
+                    None => println!(
+                        "> Waiting for job to finish... ({}s)",
+                        progress.elapsed.as_secs()
+                    ),
+                }
+            });
+        match api.wait_for_job(id, opts).await {
+            Ok(_) => {
                 println!("> Operation successful!");
                 thread::sleep(Duration::from_secs(1));
-                return Ok(());
-            } else if job.job_status == JobStatus::Failed {
+                Ok(())
+            }
+            Err(JobWaitError::Failed(job)) => {
                 println!("> Operation failed. Error(s):\n{}", job.errors.join("\n"));
                 thread::sleep(Duration::from_secs(2));
-                return Err(NeptisError::Str("Operation failed".into()));
-            } else {
-                println!("> Waiting for job to finish... ({i}/20) tries");
+                Err(NeptisError::Str("Operation failed".into()))
+            }
+            Err(JobWaitError::TimedOut(_)) => {
+                println!("> Operation timed out without response.");
+                thread::sleep(Duration::from_secs(1));
+                Err(NeptisError::Str("Operation failed".into()))
+            }
+            Err(e) => {
+                println!("> {}", e);
+                thread::sleep(Duration::from_secs(2));
+                Err(NeptisError::Str("Operation failed".into()))
             }
-            thread::sleep(Duration::from_secs(2));
         }
-        println!("> Operation timed out without response.");
-        thread::sleep(Duration::from_secs(1));
-        Err(NeptisError::Str("Operation failed".into()))
     }
 
     fn show_points(&self) {
@@ -2518,6 +3591,8 @@ impl UiApp {
         const STR_REFRESH: &str = "Cancel";
         const STR_SHUTDOWN: &str = "Shutdown";
         const STR_RESTART: &str = "Restart";
+        const STR_LOGS: &str = "View Logs";
+        const STR_CONFIG: &str = "Edit Configuration";
         const STR_BACK: &str = "Go Back";
         let mut last_refresh = Instant::now();
         let mut first_time: bool = true;
@@ -2531,7 +3606,10 @@ impl UiApp {
                     let m_api = &*self.api.read().unwrap();
                     if let Some(api) = m_api {
                         if let Ok(info) = self.rt.block_on(async { api.get_info().await }) {
+                            let caps = Capabilities::from_info(&info);
                             info.print_info();
+                            println!();
+                            caps.print_info();
                             Ok(())
                         } else {
                             Err(NeptisError::Str("Failed to pull info!".into()))
@@ -2583,20 +3661,31 @@ impl UiApp {
                     thread::sleep(Duration::from_secs(3));
                     callback();
                 }
-                Err(_) => {
-                    println!("Operation failed!");
+                Err(e) => {
+                    println!("Operation failed! {e}");
+                    thread::sleep(Duration::from_secs(3));
                     callback();
                 }
             }
         }
 
-        let choice = Select::new(
-            "Please select an option",
-            vec![STR_REFRESH, STR_BACK, STR_SHUTDOWN, STR_RESTART],
-        )
-        .prompt_skippable()
-        .expect("Failed to show prompt!")
-        .unwrap_or(STR_BACK);
+        let mut menu_items = vec![
+            STR_REFRESH,
+            STR_BACK,
+            STR_LOGS,
+            STR_CONFIG,
+            STR_SHUTDOWN,
+            STR_RESTART,
+        ];
+        menu_items.retain(|x| match *x {
+            STR_LOGS => self.supports(Feature::Logs),
+            STR_CONFIG => self.supports(Feature::GlobalConfig),
+            _ => true,
+        });
+        let choice = Select::new("Please select an option", menu_items)
+            .prompt_skippable()
+            .expect("Failed to show prompt!")
+            .unwrap_or(STR_BACK);
 
         fn handle_unsafe(is_safe: bool) -> bool {
             if is_safe {
@@ -2613,6 +3702,8 @@ impl UiApp {
 
         match choice {
             STR_REFRESH => self.show_system(),
+            STR_LOGS => self.show_logs(),
+            STR_CONFIG => self.on_edit_config(),
             STR_SHUTDOWN => {
                 if !handle_unsafe(is_safe) {
                     self.show_dashboard();
@@ -2629,24 +3720,469 @@ impl UiApp {
                 }
             }
 
-            STR_RESTART => {
-                if !handle_unsafe(is_safe) {
-                    self.show_dashboard();
-                } else {
-                    let result = {
-                        let m_api = &*self.api.read().unwrap();
-                        if let Some(api) = m_api {
-                            self.rt.block_on(async { api.restart().await })
-                        } else {
-                            Err(NeptisError::Str("API is invalid!".into()))
-                        }
+            STR_RESTART => {
+                if !handle_unsafe(is_safe) {
+                    self.show_dashboard();
+                } else {
+                    let result = {
+                        let m_api = &*self.api.read().unwrap();
+                        if let Some(api) = m_api {
+                            self.rt.block_on(async { api.restart().await })
+                        } else {
+                            Err(NeptisError::Str("API is invalid!".into()))
+                        }
+                    };
+                    handle_result(result, || self.show_dashboard());
+                }
+            }
+
+            _ => self.show_dashboard(),
+        }
+    }
+
+    fn on_edit_config(&self) {
+        const STR_SAVE: &str = "Save Changes";
+        const STR_BACK: &str = "Go Back";
+        let ret = {
+            let m_api = &*self.api.read().unwrap();
+            if let Some(api) = m_api {
+                self.rt.block_on(async { api.get_global_config().await })
+            } else {
+                Err(NeptisError::Str("API is invalid!".into()))
+            }
+        };
+        let original = match ret {
+            Ok(x) => x,
+            Err(e) => {
+                println!("**** Failed to pull the configuration. ****\n{}", e);
+                thread::sleep(Duration::from_secs(2));
+                self.show_system();
+                return;
+            }
+        };
+        let mut edited = original.clone();
+        loop {
+            clearscreen::clear().expect("Failed to clear screen!");
+            println!("**** Global Configuration (* = unsaved) ****\n");
+            let mut options = ConfigField::ALL
+                .iter()
+                .map(|f| {
+                    format!(
+                        "{}{}: {}",
+                        if edited.get_field(*f) != original.get_field(*f) {
+                            "* "
+                        } else {
+                            ""
+                        },
+                        f.name(),
+                        edited.display_field(*f)
+                    )
+                })
+                .collect::<Vec<_>>();
+            options.push(STR_SAVE.into());
+            options.push(STR_BACK.into());
+
+            // Select has no skippable raw prompt; treat a cancelled prompt as "go back".
+            let sel = Select::new("Please select a setting to change", options)
+                .raw_prompt()
+                .ok();
+            match sel {
+                Some(sel) if sel.index < ConfigField::ALL.len() => {
+                    let field = ConfigField::ALL[sel.index];
+                    let msg = format!("Please enter the {}", field.name());
+                    let input = if field.is_secret() {
+                        Password::new(msg.as_str())
+                            .with_help_message("Leave empty to clear")
+                            .prompt_skippable()
+                            .expect("Failed to show prompt!")
+                    } else {
+                        Text::new(msg.as_str())
+                            .with_initial_value(&edited.get_field(field).unwrap_or_default())
+                            .with_help_message("Leave empty to clear")
+                            .with_validator(move |x: &str| {
+                                Ok(match x.trim() {
+                                    "" => Validation::Valid,
+                                    v => match field.validate(v) {
+                                        Ok(_) => Validation::Valid,
+                                        Err(e) => Validation::Invalid(e.to_string().into()),
+                                    },
+                                })
+                            })
+                            .prompt_skippable()
+                            .expect("Failed to show prompt!")
+                    };
+                    if let Some(input) = input
+                        && let Err(e) = edited.set_field(field, Some(&input))
+                    {
+                        println!("**** {}", e);
+                        thread::sleep(Duration::from_secs(2));
+                    }
+                }
+                Some(sel) if sel.value == STR_SAVE => {
+                    if self.on_save_config(&original, &edited) {
+                        break;
+                    }
+                }
+                _ => {
+                    if edited.diff(&original).is_empty()
+                        || Confirm::new("Discard the unsaved changes?")
+                            .prompt_skippable()
+                            .expect("Failed to show prompt!")
+                            .unwrap_or(false)
+                    {
+                        break;
+                    }
+                }
+            }
+        }
+        self.show_system();
+    }
+
+    /// Shows the pending changes and saves them once confirmed. Returns `true` when saved.
+    fn on_save_config(&self, original: &DynamicConfigDto, edited: &DynamicConfigDto) -> bool {
+        if let Err(e) = edited.validate() {
+            println!("**** {}", e);
+            thread::sleep(Duration::from_secs(2));
+            return false;
+        }
+        let diff = edited.diff(original);
+        if diff.is_empty() {
+            println!("**** No changes were made!");
+            thread::sleep(Duration::from_secs(2));
+            return false;
+        }
+        clearscreen::clear().expect("Failed to clear screen!");
+        println!("**** The following changes will be saved:\n");
+        for (field, old, new) in diff.iter() {
+            println!("{}: {} -> {}", field.name(), old, new);
+        }
+        let dto = GlobalConfigPutDto::from_changes(original, edited);
+        if dto.overwrite_nulls == Some(true) {
+            println!("\nCleared settings will be removed from the server.");
+        }
+        println!();
+        if !Confirm::new("Do you want to save these changes?")
+            .prompt_skippable()
+            .expect("Failed to show prompt!")
+            .unwrap_or(false)
+        {
+            return false;
+        }
+        let ret = {
+            let m_api = &*self.api.read().unwrap();
+            if let Some(api) = m_api {
+                self.rt.block_on(async { api.put_global_config(dto).await })
+            } else {
+                Err(NeptisError::Str("API is invalid!".into()))
+            }
+        };
+        match ret {
+            Ok(_) => {
+                println!("Successfully saved the configuration!");
+                thread::sleep(Duration::from_secs(2));
+                true
+            }
+            Err(e) => {
+                println!("**** Failed to save the configuration. ****\n{}", e);
+                thread::sleep(Duration::from_secs(2));
+                false
+            }
+        }
+    }
+
+    fn show_logs(&self) {
+        use crossterm::{
+            event::{self, Event, KeyCode},
+            terminal::{disable_raw_mode, enable_raw_mode},
+        };
+        const PAGE_SIZE: usize = 15;
+        let mut filter = LogFilter::default();
+        let mut offset: usize = 0;
+        // The server cannot page logs, so they are pulled once and only again after changes.
+        let mut logs: Option<Vec<LogItemDto>> = None;
+        loop {
+            if logs.is_none() {
+                let ret = {
+                    let m_api = &*self.api.read().unwrap();
+                    if let Some(api) = m_api {
+                        self.rt.block_on(async { api.get_all_logs().await })
+                    } else {
+                        Err(NeptisError::Str("API is invalid!".into()))
+                    }
+                };
+                match ret {
+                    Ok(x) => logs = Some(x),
+                    Err(e) => {
+                        clearscreen::clear().expect("Failed to clear screen!");
+                        println!("**** Failed to pull the logs. ****\n{}", e);
+                        thread::sleep(Duration::from_secs(2));
+                        break;
+                    }
+                }
+            }
+            clearscreen::clear().expect("Failed to clear screen!");
+            let page = filter.page(logs.as_deref().unwrap_or_default(), PAGE_SIZE, offset);
+            println!(
+                "*** Showing logs {} to {} ***\nFilter: {}\n",
+                usize::min(offset + 1, offset + page.len()),
+                offset + page.len(),
+                filter
+            );
+            if page.is_empty() {
+                println!("No logs were found.");
+            }
+            for log in page.iter() {
+                println!("{}", log.to_short_id_string());
+            }
+            println!(
+                "\n←/→ to browse | r to read | d to delete | f to filter | l to follow live | q to exit"
+            );
+
+            enable_raw_mode().expect("Failed to enable raw mode");
+            let result = event::read();
+            disable_raw_mode().expect("Failed to disable raw mode");
+            if let Ok(Event::Key(key)) = result
+                && key.is_press()
+            {
+                match key.code {
+                    KeyCode::Left => offset = offset.saturating_sub(PAGE_SIZE),
+                    KeyCode::Right if page.len() == PAGE_SIZE => offset += PAGE_SIZE,
+                    KeyCode::Char('r') => self.on_read_log(&page),
+                    KeyCode::Char('d') => {
+                        self.on_delete_logs(&page);
+                        logs = None;
+                    }
+                    KeyCode::Char('f') => {
+                        if let Some(new_filter) = self.prompt_log_filter(&filter) {
+                            filter = new_filter;
+                            offset = 0;
+                        }
+                    }
+                    KeyCode::Char('l') => {
+                        self.on_follow_logs(&filter);
+                        logs = None;
+                    }
+                    KeyCode::Char('q') | KeyCode::Enter => break,
+                    _ => {}
+                }
+            }
+        }
+        self.show_system();
+    }
+
+    fn on_read_log(&self, page: &[LogItemDto]) {
+        use crossterm::{
+            event,
+            terminal::{disable_raw_mode, enable_raw_mode},
+        };
+        if page.is_empty() {
+            return;
+        }
+        // Select has no skippable raw prompt; treat a cancelled prompt as "go back".
+        let Ok(sel) = Select::new(
+            "Please select the log to read",
+            page.iter().map(|x| x.to_short_id_string()).collect(),
+        )
+        .raw_prompt() else {
+            return;
+        };
+        let log = &page[sel.index];
+        // Pull the newest copy in case the list was truncated or stale.
+        let log = {
+            let m_api = &*self.api.read().unwrap();
+            match (m_api, log.id) {
+                (Some(api), Some(id)) => self
+                    .rt
+                    .block_on(async { api.get_one_log(id).await })
+                    .unwrap_or(log.clone()),
+                _ => log.clone(),
+            }
+        };
+        clearscreen::clear().expect("Failed to clear screen!");
+        println!("ID:        {}", log.id.map(|x| x.to_string()).unwrap_or("N/A".into()));
+        println!(
+            "Date:      {}",
+            log.date()
+                .map(|x| x.with_timezone(&Local).format("%Y-%m-%d %I:%M:%S %p").to_string())
+                .unwrap_or("N/A".into())
+        );
+        println!("Category:  {}", log.category.flatten().unwrap_or("N/A".into()));
+        println!("Class:     {}", log.class_name.flatten().unwrap_or("N/A".into()));
+        println!("User:      {}", log.user_name.flatten().unwrap_or("SYSTEM".into()));
+        println!("\n{}", log.message.flatten().unwrap_or_default());
+        println!("\nPress any key to go back...");
+        enable_raw_mode().expect("Failed to enable raw mode");
+        let _ = event::read();
+        disable_raw_mode().expect("Failed to disable raw mode");
+    }
+
+    fn on_delete_logs(&self, page: &[LogItemDto]) {
+        if page.is_empty() {
+            return;
+        }
+        let selected = MultiSelect::new(
+            "Please select the logs to delete",
+            page.iter().map(|x| x.to_short_id_string()).collect(),
+        )
+        .raw_prompt_skippable()
+        .expect("Failed to show prompt!")
+        .unwrap_or_default();
+        if selected.is_empty()
+            || !Confirm::new(&format!("Delete {} log(s)?", selected.len()))
+                .prompt_skippable()
+                .expect("Failed to show prompt!")
+                .unwrap_or(false)
+        {
+            return;
+        }
+        let m_api = &*self.api.read().unwrap();
+        let Some(api) = m_api else {
+            return;
+        };
+        let mut failed = 0;
+        for id in selected.iter().filter_map(|x| page[x.index].id) {
+            if self
+                .rt
+                .block_on(async { api.delete_one_log(id).await })
+                .is_err()
+            {
+                failed += 1;
+            }
+        }
+        if failed > 0 {
+            println!("**** Failed to delete {} log(s)!", failed);
+        } else {
+            println!("Successfully deleted {} log(s)!", selected.len());
+        }
+        thread::sleep(Duration::from_secs(2));
+    }
+
+    /// Parses a local date, with an optional time. A date alone means the start of the day, or
+    /// its end when `end_of_day` is set.
+    fn parse_local_date(s: &str, end_of_day: bool) -> Option<chrono::DateTime<Utc>> {
+        let s = s.trim();
+        let naive = chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M")
+            .ok()
+            .or_else(|| {
+                chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d")
+                    .ok()
+                    .and_then(|d| {
+                        if end_of_day {
+                            d.and_hms_opt(23, 59, 59)
+                        } else {
+                            d.and_hms_opt(0, 0, 0)
+                        }
+                    })
+            })?;
+        naive
+            .and_local_timezone(Local)
+            .earliest()
+            .map(|x| x.with_timezone(&Utc))
+    }
+
+    /// Asks for an optional local date. Returns `None` if the user cancelled.
+    fn prompt_local_date(msg: &str, end_of_day: bool) -> Option<Option<chrono::DateTime<Utc>>> {
+        let ret = Text::new(msg)
+            .with_help_message("YYYY-MM-DD or YYYY-MM-DD HH:MM (local time); leave empty for any")
+            .with_validator(move |x: &str| {
+                if x.trim().is_empty() || Self::parse_local_date(x, end_of_day).is_some() {
+                    Ok(Validation::Valid)
+                } else {
+                    Ok(Validation::Invalid("Invalid date format!".into()))
+                }
+            })
+            .prompt_skippable()
+            .expect("Failed to show prompt!")?;
+        Some(Self::parse_local_date(&ret, end_of_day))
+    }
+
+    fn prompt_log_filter(&self, current: &LogFilter) -> Option<LogFilter> {
+        const STR_ANY: &str = "(Any)";
+        let mut categories = {
+            let m_api = &*self.api.read().unwrap();
+            match m_api {
+                Some(api) => self
+                    .rt
+                    .block_on(async { api.get_all_logs().await })
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter_map(|x| x.category.flatten())
+                    .unique()
+                    .sorted()
+                    .collect::<Vec<_>>(),
+                None => vec![],
+            }
+        };
+        categories.insert(0, STR_ANY.to_string());
+        let category = Select::new("Please select the Category", categories)
+            .prompt_skippable()
+            .expect("Failed to show prompt!")?;
+        let user_name = Text::new("Please enter the User Name")
+            .with_help_message("Leave empty for any user")
+            .with_initial_value(current.user_name.as_deref().unwrap_or_default())
+            .prompt_skippable()
+            .expect("Failed to show prompt!")?;
+        let start = Self::prompt_local_date("Please enter the Start Date", false)?;
+        let end = Self::prompt_local_date("Please enter the End Date", true)?;
+        Some(LogFilter {
+            category: (category != STR_ANY).then_some(category),
+            user_name: Some(user_name.trim().to_string()).filter(|x| !x.is_empty()),
+            start,
+            end,
+        })
+    }
+
+    fn on_follow_logs(&self, filter: &LogFilter) {
+        use crossterm::{
+            event::{self, Event},
+            terminal::{disable_raw_mode, enable_raw_mode},
+        };
+        const BACKLOG: usize = 10;
+        clearscreen::clear().expect("Failed to clear screen!");
+        println!("*** Following logs (Filter: {}) ***", filter);
+        println!("(Press any key to stop)\n");
+        let mut last_id: Option<i64> = None;
+        let mut first_time = true;
+        let mut last_refresh = Instant::now();
+        enable_raw_mode().expect("Failed to enable raw mode");
+        loop {
+            if first_time || last_refresh.elapsed().as_secs() >= 2 {
+                // Only hold the API between polls, so it can be replaced while following.
+                let ret = {
+                    let m_api = &*self.api.read().unwrap();
+                    match m_api {
+                        Some(api) => self
+                            .rt
+                            .block_on(async { api.get_logs_since(filter, last_id).await }),
+                        None => Err(NeptisError::Str("API is invalid!".into())),
+                    }
+                };
+                if let Ok(logs) = ret {
+                    let skip = if first_time {
+                        logs.len().saturating_sub(BACKLOG)
+                    } else {
+                        0
                     };
-                    handle_result(result, || self.show_dashboard());
+                    for log in logs.iter().skip(skip) {
+                        // Raw mode does not translate newlines into carriage returns.
+                        print!("{}\r\n", log.to_short_id_string());
+                    }
+                    let _ = std::io::Write::flush(&mut std::io::stdout());
+                    if let Some(id) = logs.iter().filter_map(|x| x.id).max() {
+                        last_id = Some(id);
+                    }
                 }
+                first_time = false;
+                last_refresh = Instant::now();
+            }
+            if event::poll(Duration::from_millis(100)).unwrap()
+                && let Event::Key(k) = event::read().unwrap()
+                && k.is_press()
+            {
+                break;
             }
-
-            _ => self.show_dashboard(),
         }
+        disable_raw_mode().expect("Failed to disable raw mode");
     }
 
     fn _on_change_password(
@@ -2684,7 +4220,7 @@ impl UiApp {
                         Ok(_) => {
                             println!("**** Successfully changed password!")
                         }
-                        Err(_) => println!("**** Failed to change password!"),
+                        Err(e) => println!("**** Failed to change password! {e}"),
                     }
                     thread::sleep(Duration::from_secs(2));
                 }
@@ -2693,6 +4229,193 @@ impl UiApp {
         self.show_dashboard();
     }
 
+    /// Lists every job, pulling more pages from the server as the user scrolls down.
+    fn show_jobs(&self) {
+        use crossterm::{
+            event::{self, Event, KeyCode},
+            terminal::{disable_raw_mode, enable_raw_mode},
+        };
+        use futures::StreamExt;
+        const PAGE_SIZE: usize = 15;
+        enum Action {
+            Open(Uuid, String),
+            Search,
+            Filter,
+            Exit,
+        }
+
+        let mut filter = JobFilter::default();
+        let opened = loop {
+            let action = {
+                let m_api = &*self.api.read().unwrap();
+                let Some(api) = m_api else {
+                    break None;
+                };
+                let mut stream = Box::pin(api.jobs_stream(filter.clone(), PAGE_SIZE));
+                let mut jobs: Vec<RepoJobDto> = vec![];
+                let mut error = None;
+                let mut done = false;
+                let mut cursor: usize = 0;
+                loop {
+                    // Keep a full page loaded below the cursor.
+                    while !done && jobs.len() < cursor + PAGE_SIZE {
+                        match self.rt.block_on(stream.next()) {
+                            Some(Ok(job)) => jobs.push(job),
+                            Some(Err(e)) => {
+                                error = Some(e);
+                                done = true;
+                            }
+                            None => done = true,
+                        }
+                    }
+
+                    clearscreen::clear().expect("Failed to clear screen!");
+                    println!(
+                        "*** Showing job {} of {}{} ***\nFilter: {}\n",
+                        usize::min(cursor + 1, jobs.len()),
+                        jobs.len(),
+                        if done { "" } else { "+" },
+                        filter
+                    );
+                    if let Some(ref e) = error {
+                        println!("**** Failed to pull more jobs. ****\n{}\n", e);
+                    }
+                    if jobs.is_empty() {
+                        println!("No jobs were found.");
+                    }
+                    let start = cursor.saturating_sub(PAGE_SIZE / 2);
+                    for (i, job) in jobs.iter().enumerate().skip(start).take(PAGE_SIZE) {
+                        println!(
+                            "{} {}",
+                            if i == cursor { ">" } else { " " },
+                            job.to_short_id_string()
+                        );
+                    }
+                    println!(
+                        "\n↑/↓ to scroll | Enter to open | / to search | f to filter | q to exit"
+                    );
+
+                    enable_raw_mode().expect("Failed to enable raw mode");
+                    let result = event::read();
+                    disable_raw_mode().expect("Failed to disable raw mode");
+                    if let Ok(Event::Key(key)) = result
+                        && key.is_press()
+                    {
+                        match key.code {
+                            KeyCode::Up => cursor = cursor.saturating_sub(1),
+                            KeyCode::Down if cursor + 1 < jobs.len() => cursor += 1,
+                            KeyCode::PageUp => cursor = cursor.saturating_sub(PAGE_SIZE),
+                            KeyCode::PageDown => {
+                                cursor = (cursor + PAGE_SIZE).min(jobs.len().saturating_sub(1))
+                            }
+                            KeyCode::Enter if !jobs.is_empty() => {
+                                break Action::Open(jobs[cursor].id, jobs[cursor].point_name.clone());
+                            }
+                            KeyCode::Char('/') => break Action::Search,
+                            KeyCode::Char('f') => break Action::Filter,
+                            KeyCode::Char('q') => break Action::Exit,
+                            _ => {}
+                        }
+                    }
+                }
+            };
+            match action {
+                Action::Open(id, point) => break Some((id, point)),
+                Action::Search => {
+                    if let Some(text) = Text::new("Please enter the text to search for")
+                        .with_help_message("Matches the ID, title, point or snapshot; leave empty for all")
+                        .with_initial_value(filter.search.as_deref().unwrap_or_default())
+                        .prompt_skippable()
+                        .expect("Failed to show prompt!")
+                    {
+                        filter.search = Some(text.trim().to_string()).filter(|x| !x.is_empty());
+                    }
+                }
+                Action::Filter => {
+                    if let Some(new_filter) = self.prompt_job_filter(&filter) {
+                        filter = new_filter;
+                    }
+                }
+                Action::Exit => break None,
+            }
+        };
+        match opened {
+            Some((id, point)) => self.on_select_job(&point, id, Some(point.clone())),
+            None => self.show_dashboard(),
+        }
+    }
+
+    /// Asks for the server-side job filters, keeping the current search text.
+    fn prompt_job_filter(&self, current: &JobFilter) -> Option<JobFilter> {
+        const STR_ANY: &str = "(Any)";
+        let points = {
+            let m_api = &*self.api.read().unwrap();
+            match m_api {
+                Some(api) => self
+                    .rt
+                    .block_on(async { api.get_all_mounts().await })
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|x| x.name)
+                    .sorted()
+                    .collect::<Vec<_>>(),
+                None => vec![],
+            }
+        };
+        let point = Select::new(
+            "Please select the Point",
+            once(STR_ANY.to_string()).chain(points).collect(),
+        )
+        .prompt_skippable()
+        .expect("Failed to show prompt!")?;
+        let status = Select::new(
+            "Please select the Status",
+            once(STR_ANY.to_string())
+                .chain(
+                    [
+                        JobStatus::NotStarted,
+                        JobStatus::Running,
+                        JobStatus::Successful,
+                        JobStatus::Failed,
+                    ]
+                    .iter()
+                    .map(|x| x.to_string()),
+                )
+                .collect(),
+        )
+        .prompt_skippable()
+        .expect("Failed to show prompt!")?;
+        let job_type = Select::new(
+            "Please select the Job Type",
+            once(STR_ANY.to_string())
+                .chain(
+                    [
+                        JobType::Backup,
+                        JobType::Restore,
+                        JobType::Check,
+                        JobType::Prune,
+                        JobType::PointAdjust,
+                        JobType::PointDelete,
+                    ]
+                    .iter()
+                    .map(|x| x.to_string()),
+                )
+                .collect(),
+        )
+        .prompt_skippable()
+        .expect("Failed to show prompt!")?;
+        let start = Self::prompt_local_date("Please enter the Start Date", false)?;
+        let end = Self::prompt_local_date("Please enter the End Date", true)?;
+        Some(JobFilter {
+            point: (point != STR_ANY).then_some(point),
+            status: JobStatus::from_str(&status).ok(),
+            job_type: JobType::from_str(&job_type).ok(),
+            start,
+            end,
+            search: current.search.clone(),
+        })
+    }
+
     fn show_point_breakdown(&self) {
         loop {
             clearscreen::clear().expect("Failed to clear screen!");
@@ -3179,6 +4902,7 @@ impl UiApp {
         const STR_SYNC: &str = "Manage Client-Side Sync";
         const STR_BREAKDOWN: &str = "Show Usage Breakdown";
         const STR_MESSAGE: &str = "View Messages";
+        const STR_JOBS: &str = "View Jobs";
         const STR_POINTS: &str = "Manage Points";
         const STR_USERS: &str = "Manage Users";
         const STR_NOTIFICATION: &str = "Manage Notifications";
@@ -3290,6 +5014,7 @@ impl UiApp {
         }
 
         menu_items.push(STR_MESSAGE);
+        menu_items.push(STR_JOBS);
         menu_items.push(STR_BREAKDOWN);
         menu_items.push(STR_POINTS);
 
@@ -3324,6 +5049,7 @@ impl UiApp {
             Some(STR_SYNC) => self.show_rclone_schedules(),
             Some(STR_NOTIFICATION) => self.show_notifications(),
             Some(STR_MESSAGE) => self.show_messages(),
+            Some(STR_JOBS) => self.show_jobs(),
             Some(STR_BACK) => {
                 clearscreen::clear().expect("Failed to clear screen!");
                 self.show_dashboard();
@@ -3446,9 +5172,10 @@ impl UiApp {
                 p_user.clone(),
                 p_password.clone(),
                 secret.clone(),
-            );
+            )
+            .with_envelope_policy(server.envelope_policy());
             self.rt
-                .block_on(async { t_api.get_info().await })
+                .block_on(async { t_api.negotiate().await })
                 .map_err(|e| format!("Failed to load server: {e}"))?;
             Ok(t_api)
         };
@@ -3477,29 +5204,9 @@ impl UiApp {
 
                         let ep = a_endpoint.as_str();
                         let a_func = || {
-                            let key = ArduinoSecret::from_string(a_pass.as_str())
-                                .ok_or("Failed to parse Arduino Key".to_string())?
-                                .rolling_key()
-                                .ok_or("Failed to calculate next Arduino key".to_string())?;
-                            ClientBuilder::new()
-                                .build()
-                                .map(|x| {
-                                    self.rt.block_on(async move {
-                                        x.post(format!("{}/{}", ep, "start"))
-                                            .bearer_auth(key.to_string())
-                                            .send()
-                                            .await
-                                            .ok()
-                                    })
-                                })
-                                .ok()
-                                .flatten()
-                                .ok_or("Failed to send packet to Arduino!".to_string())?
-                                .error_for_status()
-                                .map_err(|e| {
-                                    format!("Arduino returned error upon submission: {}", e)
-                                })
-                                .map(|_| ())
+                            self.rt
+                                .block_on(WebApi::wake_pc(ep, a_pass.as_str()))
+                                .map_err(|e| e.to_string())
                         };
 
                         let mut sig_good = false;
@@ -3582,6 +5289,21 @@ impl UiApp {
         }
         match ret {
             Ok(x) => {
+                if let Some(reason) = self
+                    .rt
+                    .block_on(async { x.capabilities().await })
+                    .ok()
+                    .and_then(|c| c.incompatible_reason())
+                {
+                    println!("**** Incompatible server! {reason}");
+                    thread::sleep(Duration::from_secs(3));
+                    if auto {
+                        // Prevent an infinite loop by terminating.
+                        process::exit(1);
+                    }
+                    self.begin();
+                    return;
+                }
                 {
                     let mut api = self.api.write().unwrap();
                     *api = Some(x);
@@ -3611,6 +5333,103 @@ impl UiApp {
         }
     }
 
+    /// Unlocks the credential vault once for this session, using the key file if there is
+    /// one and otherwise asking for the passphrase.
+    pub fn unlock_vault(&self) {
+        match self.db.unlock_vault_unattended_sync() {
+            Ok(VaultStatus::Locked) => {}
+            Ok(_) => return,
+            Err(e) => println!("**** Failed to unlock the credential vault! {e}"),
+        }
+        loop {
+            let Some(pass) = Password::new("Please enter the vault passphrase")
+                .without_confirmation()
+                .prompt_skippable()
+                .expect("Failed to show prompt!")
+            else {
+                // The saved servers cannot be read without the vault.
+                process::exit(0);
+            };
+            match self.db.unlock_vault_sync(&VaultKey::Passphrase(pass)) {
+                Ok(_) => return,
+                Err(e) => println!("**** {e}"),
+            }
+        }
+    }
+
+    /// Sets up the credential vault, or changes how an unlocked one is unlocked.
+    pub fn setup_vault(&self) {
+        const STR_PASSPHRASE: &str = "Change Passphrase";
+        const STR_KEY_FILE: &str = "Create Key File";
+        const STR_BACK: &str = "Go Back";
+
+        fn create_key_file(db: &DbController) {
+            let path = VaultKey::default_key_file();
+            match VaultKey::create_key_file(&path).and_then(|x| db.add_vault_key_sync(&x)) {
+                Ok(_) => println!("The key file was saved to {}", path.display()),
+                Err(e) => println!("**** Failed to create the key file! {e}"),
+            }
+        }
+
+        clearscreen::clear().unwrap();
+        println!("Credential Vault: {}", self.db.vault_status());
+        match self.db.vault_status() {
+            VaultStatus::NotConfigured => {
+                let Some(pass) = Password::new("Please enter a master passphrase")
+                    .with_help_message("This protects the passwords saved on this computer")
+                    .with_validator(required!())
+                    .prompt_skippable()
+                    .expect("Failed to show prompt!")
+                else {
+                    return;
+                };
+                if let Err(e) = self.db.setup_vault_sync(&VaultKey::Passphrase(pass)) {
+                    println!("**** Failed to set up the credential vault! {e}");
+                    thread::sleep(Duration::from_secs(3));
+                    return;
+                }
+                println!("The saved passwords are now encrypted.");
+                if Confirm::new(
+                    "Create a key file, so the background service can unlock the vault?",
+                )
+                .with_default(true)
+                .prompt_skippable()
+                .expect("Failed to show prompt!")
+                .unwrap_or(false)
+                {
+                    create_key_file(&self.db);
+                }
+            }
+            VaultStatus::Unlocked => {
+                match Select::new(
+                    "What do you want to do?",
+                    vec![STR_PASSPHRASE, STR_KEY_FILE, STR_BACK],
+                )
+                .prompt_skippable()
+                .expect("Failed to show prompt!")
+                {
+                    Some(STR_PASSPHRASE) => {
+                        let Some(pass) = Password::new("Please enter the new passphrase")
+                            .with_validator(required!())
+                            .prompt_skippable()
+                            .expect("Failed to show prompt!")
+                        else {
+                            return;
+                        };
+                        match self.db.add_vault_key_sync(&VaultKey::Passphrase(pass)) {
+                            Ok(_) => println!("The passphrase was changed."),
+                            Err(e) => println!("**** Failed to change the passphrase! {e}"),
+                        }
+                    }
+                    Some(STR_KEY_FILE) => create_key_file(&self.db),
+                    _ => return,
+                }
+            }
+            VaultStatus::Locked => println!("**** The credential vault is locked!"),
+        }
+        thread::sleep(Duration::from_secs(2));
+    }
+
     pub fn begin(&self) {
         use crossterm::{
             event::{self, Event},
@@ -3622,6 +5441,106 @@ impl UiApp {
         fn format_slash(s: &str) -> String {
             s.strip_suffix("/").unwrap_or(s).to_string()
         }
+        fn pair_secret(ctx: &mut ApiContext<'_, DbController>, serv: &mut ServerItem) -> PromptResult {
+            if serv.server_endpoint.trim().is_empty() {
+                println!("**** Please enter the Server Endpoint first!");
+                return PromptResult::Cancel;
+            }
+            let current = serv
+                .server_password
+                .as_deref()
+                .and_then(RollingSecret::from_string);
+            let rotation = match current {
+                Some(ref current) => {
+                    let Some(hours) = CustomType::<u32>::new(
+                        "How many hours should the old secret keep working?",
+                    )
+                    .with_default(DEFAULT_ROTATION_GRACE.num_hours() as u32)
+                    .prompt_skippable()
+                    .expect("Failed to show prompt!") else {
+                        return PromptResult::Cancel;
+                    };
+                    SecretRotation::rotate(current, TimeDelta::hours(hours as i64))
+                }
+                None => SecretRotation::pair(),
+            };
+            let rotation = match rotation {
+                Ok(x) => x,
+                Err(e) => {
+                    println!("**** Failed to generate a secret! {e}");
+                    return PromptResult::Cancel;
+                }
+            };
+
+            let user_name = match serv.user_name.clone() {
+                Some(x) => x,
+                None => match Text::new("Enter User Name")
+                    .with_validator(required!())
+                    .prompt_skippable()
+                    .expect("Failed to show prompt!")
+                {
+                    Some(x) => x,
+                    None => return PromptResult::Cancel,
+                },
+            };
+            let password = match serv.user_password.clone() {
+                Some(x) => x,
+                None => match Password::new("Enter User Password")
+                    .without_confirmation()
+                    .prompt_skippable()
+                    .expect("Failed to show prompt!")
+                {
+                    Some(x) => x,
+                    None => return PromptResult::Cancel,
+                },
+            };
+
+            loop {
+                clearscreen::clear().unwrap();
+                if let Some(qr) = rotation.qr_code() {
+                    println!("{qr}");
+                }
+                println!("Secret: {}", rotation.server_secret());
+                println!("Short Code: {}", rotation.short_code());
+                if let Some(until) = rotation.grace_until() {
+                    println!(
+                        "The server keeps accepting the old secret until {}.",
+                        until.with_timezone(&Local).format("%Y-%m-%d %H:%M")
+                    );
+                }
+                println!();
+                match Confirm::new("Is the new secret installed on the server?")
+                    .with_default(true)
+                    .prompt_skippable()
+                    .expect("Failed to show prompt!")
+                {
+                    Some(true) => {}
+                    _ => return PromptResult::Cancel,
+                }
+                match ctx.rt.block_on(rotation.confirm(
+                    &serv.server_endpoint,
+                    &user_name,
+                    &password,
+                )) {
+                    Ok(_) => {
+                        println!("The server accepted the new secret!");
+                        serv.server_password = Some(rotation.secret().to_string());
+                        return PromptResult::Ok;
+                    }
+                    Err(e) => {
+                        println!("**** {e}");
+                        if !Confirm::new("Do you want to try again?")
+                            .with_default(true)
+                            .prompt_skippable()
+                            .expect("Failed to show prompt!")
+                            .unwrap_or(false)
+                        {
+                            return PromptResult::Cancel;
+                        }
+                    }
+                }
+            }
+        }
 
         // 7-1-25: Check to see if the IPC service is running or not.
         if cfg!(not(debug_assertions))
@@ -3757,6 +5676,18 @@ impl UiApp {
                                 .unwrap_or("[EMPTY]".to_string())
                         },
                     ),
+                    ModelProperty::new(
+                        "Pair / Rotate Secret",
+                        false,
+                        pair_secret,
+                        |x| {
+                            x.server_password
+                                .as_deref()
+                                .and_then(RollingSecret::from_string)
+                                .map(|x| x.fingerprint())
+                                .unwrap_or("[NOT PAIRED]".to_string())
+                        },
+                    ),
                     ModelProperty::new(
                         "Default User",
                         false,
@@ -3851,6 +5782,35 @@ impl UiApp {
                                 .unwrap_or("[EMPTY]".to_string())
                         },
                     ),
+                    ModelProperty::new(
+                        "Generate Arduino Secret",
+                        false,
+                        |_, serv: &mut ServerItem| {
+                            let secret = ArduinoSecret::generate();
+                            println!("Arduino Secret: {}", secret.to_string());
+                            println!(
+                                "Firmware Key: {}",
+                                secret.firmware_key().unwrap_or_default()
+                            );
+                            match Confirm::new("Is the new key flashed onto the Arduino?")
+                                .with_default(false)
+                                .prompt_skippable()
+                                .expect("Failed to show prompt!") {
+                                Some(true) => {
+                                    serv.arduino_password = Some(secret.to_string());
+                                    PromptResult::Ok
+                                },
+                                _ => PromptResult::Cancel
+                            }
+                        },
+                        |x| {
+                            x.arduino_password
+                                .as_deref()
+                                .and_then(ArduinoSecret::from_string)
+                                .map(|x| format!("v{}", x.version()))
+                                .unwrap_or("[EMPTY]".to_string())
+                        },
+                    ),
                     ModelProperty::new_for_linux_only(
                         "Auto Fuse",
                         false,
@@ -3867,6 +5827,22 @@ impl UiApp {
                             }
                         },
                         |x| x.auto_fuse.to_string()),
+                    ModelProperty::new(
+                        "Allow Legacy Envelope",
+                        false,
+                        |_, serv: &mut ServerItem| {
+                            match Confirm::new("Do you want to allow the unauthenticated legacy envelope (only for old servers)")
+                                .with_default(serv.allow_legacy_envelope)
+                                .prompt_skippable()
+                                .expect("Failed to show prompt!") {
+                                Some(x) => {
+                                    serv.allow_legacy_envelope = x;
+                                    PromptResult::Ok
+                                },
+                                None => PromptResult::Cancel
+                            }
+                        },
+                        |x| x.allow_legacy_envelope.to_string()),
                     ModelProperty::new(
                         "Set As Default",
                         false,
@@ -3955,16 +5931,18 @@ use neptis_rs::db::sync_models::TransferJobDto;
 use neptis_rs::db::sync_models::TransferJobStatus;
 use neptis_rs::get_working_dir;
 use neptis_rs::prelude::{
-    AlertMode, AlertTrigger, ArduinoSecret, AutoJobDto, AutoJobType, DbController, FileSize,
-    JobStatus, JobType, NeptisError, NeptisFS, PostForAutoScheduleStartDto, PostForMessageApi,
-    PostForSubscriptionApi, PutForAutoJobWebApi, PutForMountApi, PutForSubscriptionApi, RepoJobDto,
-    ServerItem, SnapshotFileDto, SubscriptionDto, TransferAutoJob, TransferAutoSchedule, UserDto,
-    UserForCreateApi, UserForUpdateApi, WebApi,
+    AlertMode, AlertTrigger, ArduinoSecret, AutoJobDto, AutoJobType, Capabilities, ConfigField,
+    DataPointShareDto, DbController, DEFAULT_ROTATION_GRACE, DynamicConfigDto, Feature, FileSize, GlobalConfigPutDto,
+    JobFilter, JobStatus, JobType, JobWaitError, JobWaitOptions, LogFilter, LogItemDto, NeptisError, NeptisFS, PostForAutoScheduleStartDto, PostForMessageApi,
+    PostForBackupApi, PostForRestoreApi, PostForSubscriptionApi, RetentionPolicy, PutForAutoJobWebApi, PutForMountApi, PutForSubscriptionApi, RepoJobDto,
+    RepoPointShareDto,
+    PutForSnapshotApi, SecretRotation, ServerItem, SnapshotDiff, SnapshotFileDto, SubscriptionDto, TransferAutoJob, TransferAutoSchedule, UserDto,
+    UserForCreateApi, UserForUpdateApi, VaultKey, VaultStatus, WebApi,
 };
 use neptis_rs::rolling_secret::RollingSecret;
 use neptis_rs::traits::ToShortIdString;
 use neptis_rs::ui::browser::{FileBrowser, FileBrowserMode};
-use neptis_rs::ui::manager::{ModelManager, ModelProperty, PromptResult};
+use neptis_rs::ui::manager::{ApiContext, ModelManager, ModelProperty, PromptResult};
 use rocket::futures::SinkExt;
 
 #[derive(Parser, Debug)]
@@ -3983,6 +5961,10 @@ pub struct CliArgs {
     /// Use beta/pre-release updates instead of stable
     #[arg(long = "beta", conflicts_with = "no_update")]
     pub beta: Option<bool>,
+
+    /// Set up the credential vault, or change how it is unlocked
+    #[arg(long = "setup-vault")]
+    pub setup_vault: Option<bool>,
 }
 
 pub fn main() {
@@ -4022,5 +6004,9 @@ pub fn main() {
     #[cfg(not(unix))]
     let app = UiApp::new();
 
+    app.unlock_vault();
+    if args.setup_vault.unwrap_or(false) {
+        app.setup_vault();
+    }
     app.begin();
 }
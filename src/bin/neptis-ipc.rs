@@ -1,6 +1,6 @@
 use neptis_rs::ipc::handlers;
 use neptis_rs::ipc::rclone::{RCloneClient, RCloneSettings};
-use neptis_rs::prelude::{DbController, IPC_PORT, WebApi};
+use neptis_rs::prelude::{DbController, IPC_PORT, VAULT_PASSPHRASE_ENV, VaultStatus, WebApi};
 use rocket::{Config, catch, catchers, get, routes};
 use std::net::{IpAddr, Ipv4Addr};
 use std::sync::Arc;
@@ -26,7 +26,16 @@ fn rocket() -> _ {
     }
 
     let settings = RCloneSettings::new(neptis_rs::get_working_dir());
-    let db = Arc::new(DbController::new(rt.clone()));
+    let db = Arc::new(DbController::new(rt.clone()).with_unattended_unlock());
+    match db.unlock_vault_unattended_sync() {
+        Ok(VaultStatus::Locked) => eprintln!(
+            "The credential vault is locked. Create a key file from the GUI, or set {}, so \
+            scheduled jobs can read their passwords. It is unlocked as soon as a key is found.",
+            VAULT_PASSPHRASE_ENV
+        ),
+        Ok(_) => {}
+        Err(e) => eprintln!("Failed to unlock the credential vault: {e}"),
+    }
     let sync_client = Arc::new(RCloneClient::new(settings, db.clone(), rt.clone()));
     
     let mut msg_client = IPCMessageReceiver::new(db, rt);
@@ -1,10 +1,14 @@
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::SystemTime;
 
 use super::server::ServerItem;
+use super::vault::{CredentialVault, VAULT_COLUMNS, VaultKey, VaultStatus};
+use crate::apis::NeptisError;
 use crate::get_working_dir;
 use crate::prelude::{TransferAutoJob, TransferAutoSchedule, TransferJobInternalDto};
 use sqlx::{
-    Sqlite, SqlitePool,
+    Sqlite, SqliteConnection, SqlitePool,
     migrate::MigrateDatabase,
     sqlite::SqlitePoolOptions,
 };
@@ -14,10 +18,335 @@ use uuid::Uuid;
 pub struct DbController {
     rt: Arc<Runtime>,
     pool: SqlitePool,
+    vault: RwLock<Option<CredentialVault>>,
+    vault_configured: AtomicBool,
+    /// Whether a locked vault is unlocked with [`VaultKey::unattended`] whenever it is needed.
+    unattended_unlock: bool,
+    /// The unattended key which last failed to unlock the vault, and when its file was
+    /// changed. It is not tried again until either one changes.
+    failed_unattended: Mutex<Option<(VaultKey, Option<SystemTime>)>>,
 }
 
 impl DbController {
+    fn vault_locked() -> NeptisError {
+        NeptisError::Str("The credential vault is locked!".into())
+    }
+
+    /// Gets the vault ready before credentials are read or written. Another process may have
+    /// set the vault up since this one started, so that is checked again while it looks
+    /// unconfigured, and a locked vault is unlocked unattended when that is enabled.
+    ///
+    /// An unattended key which fails to unlock the vault is reported once, and then only
+    /// tried again after it changes, so a wrong key does not run Argon2 for every query.
+    async fn prepare_vault(&self) -> Result<(), sqlx::Error> {
+        if self.vault_status() == VaultStatus::NotConfigured {
+            let (count,) = sqlx::query_as::<_, (i64,)>("SELECT COUNT(*) FROM vault_keys")
+                .fetch_one(&self.pool)
+                .await?;
+            if count > 0 {
+                self.vault_configured.store(true, Ordering::SeqCst);
+            }
+        }
+        if self.unattended_unlock
+            && self.vault_status() == VaultStatus::Locked
+            && let Some(key) = VaultKey::unattended()
+        {
+            let attempt = Some((key.clone(), key.modified()));
+            if *self.failed_unattended.lock().unwrap() == attempt {
+                return Ok(());
+            }
+            if let Err(e) = self.unlock_vault(&key).await {
+                *self.failed_unattended.lock().unwrap() = attempt;
+                return Err(sqlx::Error::Decode(Box::new(e)));
+            }
+            *self.failed_unattended.lock().unwrap() = None;
+        }
+        Ok(())
+    }
+
+    /// Runs the Argon2 key derivation of the vault on a blocking thread, as it takes long
+    /// enough to hold up the other tasks of the runtime.
+    async fn derive_blocking<T: Send + 'static>(
+        f: impl FnOnce() -> Result<T, NeptisError> + Send + 'static,
+    ) -> Result<T, NeptisError> {
+        tokio::task::spawn_blocking(f)
+            .await
+            .map_err(|e| NeptisError::Str(format!("Failed to derive the vault key: {e}")))?
+    }
+
+    /// Encrypts a credential before it is written, when the vault is set up. Writers call
+    /// [`DbController::prepare_vault`] first, so plaintext is never written once another
+    /// process has set the vault up.
+    fn seal(&self, table: &str, column: &str, value: &str) -> Result<String, sqlx::Error> {
+        match self.vault.read().unwrap().as_ref() {
+            Some(vault) => vault
+                .encrypt(table, column, value)
+                .map_err(|e| sqlx::Error::Encode(Box::new(e))),
+            None if self.vault_configured.load(Ordering::SeqCst) => {
+                Err(sqlx::Error::Encode(Box::new(Self::vault_locked())))
+            }
+            None => Ok(value.to_string()),
+        }
+    }
+
+    /// Decrypts a credential after it is read. Plaintext values are returned as they are.
+    fn open(&self, table: &str, column: &str, value: String) -> Result<String, sqlx::Error> {
+        if !CredentialVault::is_encrypted(&value) {
+            return Ok(value);
+        }
+        match self.vault.read().unwrap().as_ref() {
+            Some(vault) => vault
+                .decrypt(table, column, &value)
+                .map_err(|e| sqlx::Error::Decode(Box::new(e))),
+            None => Err(sqlx::Error::Decode(Box::new(Self::vault_locked()))),
+        }
+    }
+
+    fn seal_opt(
+        &self,
+        table: &str,
+        column: &str,
+        value: &Option<String>,
+    ) -> Result<Option<String>, sqlx::Error> {
+        value
+            .as_deref()
+            .map(|x| self.seal(table, column, x))
+            .transpose()
+    }
+
+    fn open_opt(
+        &self,
+        table: &str,
+        column: &str,
+        value: Option<String>,
+    ) -> Result<Option<String>, sqlx::Error> {
+        value.map(|x| self.open(table, column, x)).transpose()
+    }
+
+    fn seal_server(&self, server: &ServerItem) -> Result<ServerItem, sqlx::Error> {
+        Ok(ServerItem {
+            server_password: self.seal_opt(
+                "server_items",
+                "server_password",
+                &server.server_password,
+            )?,
+            user_password: self.seal_opt("server_items", "user_password", &server.user_password)?,
+            arduino_password: self.seal_opt(
+                "server_items",
+                "arduino_password",
+                &server.arduino_password,
+            )?,
+            ..server.clone()
+        })
+    }
+
+    fn open_server(&self, server: ServerItem) -> Result<ServerItem, sqlx::Error> {
+        Ok(ServerItem {
+            server_password: self.open_opt(
+                "server_items",
+                "server_password",
+                server.server_password,
+            )?,
+            user_password: self.open_opt("server_items", "user_password", server.user_password)?,
+            arduino_password: self.open_opt(
+                "server_items",
+                "arduino_password",
+                server.arduino_password,
+            )?,
+            ..server
+        })
+    }
+
+    fn seal_schedule(
+        &self,
+        schedule: &TransferAutoSchedule,
+    ) -> Result<TransferAutoSchedule, sqlx::Error> {
+        Ok(TransferAutoSchedule {
+            smb_password: self.seal(
+                "transfer_auto_schedules",
+                "smb_password",
+                &schedule.smb_password,
+            )?,
+            user_password: self.seal_opt(
+                "transfer_auto_schedules",
+                "user_password",
+                &schedule.user_password,
+            )?,
+            ..schedule.clone()
+        })
+    }
+
+    fn open_schedule(
+        &self,
+        schedule: TransferAutoSchedule,
+    ) -> Result<TransferAutoSchedule, sqlx::Error> {
+        Ok(TransferAutoSchedule {
+            smb_password: self.open(
+                "transfer_auto_schedules",
+                "smb_password",
+                schedule.smb_password,
+            )?,
+            user_password: self.open_opt(
+                "transfer_auto_schedules",
+                "user_password",
+                schedule.user_password,
+            )?,
+            ..schedule
+        })
+    }
+
+    pub fn vault_status(&self) -> VaultStatus {
+        if self.vault.read().unwrap().is_some() {
+            VaultStatus::Unlocked
+        } else if self.vault_configured.load(Ordering::SeqCst) {
+            VaultStatus::Locked
+        } else {
+            VaultStatus::NotConfigured
+        }
+    }
+
+    /// Sets up the credential vault with its first key, and encrypts every stored credential.
+    /// The vault stays unlocked for this session.
+    pub async fn setup_vault(&self, key: &VaultKey) -> Result<(), NeptisError> {
+        if self.vault_status() != VaultStatus::NotConfigured {
+            return Err(NeptisError::Str(
+                "The credential vault is already set up!".into(),
+            ));
+        }
+        let vault = CredentialVault::generate();
+        let mut tx = self.pool.begin().await?;
+        Self::store_vault_key(&vault, key, &mut tx).await?;
+        Self::encrypt_vault_columns(&vault, &mut tx).await?;
+        tx.commit().await?;
+        *self.vault.write().unwrap() = Some(vault);
+        self.vault_configured.store(true, Ordering::SeqCst);
+        Ok(())
+    }
+
+    pub fn setup_vault_sync(&self, key: &VaultKey) -> Result<(), NeptisError> {
+        self.rt.block_on(async move { self.setup_vault(key).await })
+    }
+
+    /// Unlocks the credential vault for this session. Any credentials still stored in
+    /// plaintext, such as ones written by an older version, are encrypted as well.
+    pub async fn unlock_vault(&self, key: &VaultKey) -> Result<(), NeptisError> {
+        let (salt, wrapped) = sqlx::query_as::<_, (String, String)>(
+            "SELECT salt, wrapped_key FROM vault_keys WHERE source = ?",
+        )
+        .bind(key.source())
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or(NeptisError::Str(format!(
+            "The credential vault has no {} set up!",
+            key.source().replace('_', " ")
+        )))?;
+        let vault = {
+            let key = key.clone();
+            Self::derive_blocking(move || CredentialVault::unwrap(&key, &salt, &wrapped)).await?
+        };
+        let mut tx = self.pool.begin().await?;
+        Self::encrypt_vault_columns(&vault, &mut tx).await?;
+        tx.commit().await?;
+        *self.vault.write().unwrap() = Some(vault);
+        Ok(())
+    }
+
+    pub fn unlock_vault_sync(&self, key: &VaultKey) -> Result<(), NeptisError> {
+        self.rt
+            .block_on(async move { self.unlock_vault(key).await })
+    }
+
+    /// Unlocks the vault with [`VaultKey::unattended`], if it is locked and such a key exists.
+    /// Returns the status afterwards.
+    pub async fn unlock_vault_unattended(&self) -> Result<VaultStatus, NeptisError> {
+        if self.vault_status() == VaultStatus::Locked
+            && let Some(key) = VaultKey::unattended()
+        {
+            self.unlock_vault(&key).await?;
+        }
+        Ok(self.vault_status())
+    }
+
+    pub fn unlock_vault_unattended_sync(&self) -> Result<VaultStatus, NeptisError> {
+        self.rt
+            .block_on(async move { self.unlock_vault_unattended().await })
+    }
+
+    /// Adds another way to unlock the vault, or replaces the existing key of the same source,
+    /// such as to change the passphrase. The vault must be unlocked.
+    pub async fn add_vault_key(&self, key: &VaultKey) -> Result<(), NeptisError> {
+        let vault = self
+            .vault
+            .read()
+            .unwrap()
+            .clone()
+            .ok_or_else(Self::vault_locked)?;
+        let mut tx = self.pool.begin().await?;
+        Self::store_vault_key(&vault, key, &mut tx).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    pub fn add_vault_key_sync(&self, key: &VaultKey) -> Result<(), NeptisError> {
+        self.rt
+            .block_on(async move { self.add_vault_key(key).await })
+    }
+
+    /// Forgets the vault key, so credentials cannot be read until it is unlocked again.
+    pub fn lock_vault(&self) {
+        *self.vault.write().unwrap() = None;
+    }
+
+    async fn store_vault_key(
+        vault: &CredentialVault,
+        key: &VaultKey,
+        conn: &mut SqliteConnection,
+    ) -> Result<(), NeptisError> {
+        let (salt, wrapped) = {
+            let (vault, key) = (vault.clone(), key.clone());
+            Self::derive_blocking(move || vault.wrap(&key)).await?
+        };
+        sqlx::query(
+            "INSERT OR REPLACE INTO vault_keys (source, salt, wrapped_key) VALUES (?, ?, ?)",
+        )
+        .bind(key.source())
+        .bind(salt)
+        .bind(wrapped)
+        .execute(conn)
+        .await?;
+        Ok(())
+    }
+
+    /// Encrypts any plaintext values left in [`VAULT_COLUMNS`], returning how many were changed.
+    async fn encrypt_vault_columns(
+        vault: &CredentialVault,
+        conn: &mut SqliteConnection,
+    ) -> Result<u64, NeptisError> {
+        let mut count = 0;
+        for (table, column) in VAULT_COLUMNS {
+            let select = format!("SELECT rowid, {column} FROM {table} WHERE {column} IS NOT NULL");
+            let update = format!("UPDATE {table} SET {column} = ? WHERE rowid = ?");
+            let rows = sqlx::query_as::<_, (i64, String)>(&select)
+                .fetch_all(&mut *conn)
+                .await?;
+            for (row_id, value) in rows {
+                if CredentialVault::is_encrypted(&value) {
+                    continue;
+                }
+                sqlx::query(&update)
+                    .bind(vault.encrypt(table, column, &value)?)
+                    .bind(row_id)
+                    .execute(&mut *conn)
+                    .await?;
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+
     pub async fn save_server(&self, server: &ServerItem) -> Result<(), sqlx::Error> {
+        self.prepare_vault().await?;
+        let server = &self.seal_server(server)?;
         // Run an update if we need to first
         if sqlx::query!(
             r#"
@@ -45,7 +374,7 @@ impl DbController {
         .execute(&self.pool)
         .await
         .map(|x| x.rows_affected())?
-            <= 0
+            == 0
         {
             sqlx::query!(
                 r#"
@@ -83,6 +412,11 @@ impl DbController {
             .execute(&self.pool)
             .await?;
         }
+        sqlx::query("UPDATE server_items SET allow_legacy_envelope = ? WHERE server_name = ?")
+            .bind(server.allow_legacy_envelope)
+            .bind(&server.server_name)
+            .execute(&self.pool)
+            .await?;
         Ok(())
     }
 
@@ -114,6 +448,7 @@ impl DbController {
     }
 
     pub async fn get_all_servers(&self) -> Result<Vec<ServerItem>, sqlx::Error> {
+        self.prepare_vault().await?;
         let servers = sqlx::query_as::<_, ServerItem>(
             r#"
             SELECT *
@@ -123,7 +458,7 @@ impl DbController {
         .fetch_all(&self.pool)
         .await?;
 
-        Ok(servers)
+        servers.into_iter().map(|x| self.open_server(x)).collect()
     }
 
     pub fn get_all_servers_sync(&self) -> Result<Vec<ServerItem>, sqlx::Error> {
@@ -145,6 +480,8 @@ impl DbController {
         &self,
         schedule: &TransferAutoSchedule,
     ) -> Result<(), sqlx::Error> {
+        self.prepare_vault().await?;
+        let schedule = &self.seal_schedule(schedule)?;
         if sqlx::query!(
             r#"
             UPDATE transfer_auto_schedules
@@ -172,7 +509,7 @@ impl DbController {
         .execute(&self.pool)
         .await
         .map(|x| x.rows_affected())?
-            <= 0
+            == 0
         {
             sqlx::query!(
                 r#"
@@ -213,6 +550,7 @@ impl DbController {
     pub async fn get_all_transfer_auto_schedules(
         &self,
     ) -> Result<Vec<TransferAutoSchedule>, sqlx::Error> {
+        self.prepare_vault().await?;
         let results = sqlx::query_as::<_, TransferAutoSchedule>(
             r#"
             SELECT * FROM transfer_auto_schedules
@@ -220,7 +558,7 @@ impl DbController {
         )
         .fetch_all(&self.pool)
         .await?;
-        Ok(results)
+        results.into_iter().map(|x| self.open_schedule(x)).collect()
     }
 
     pub fn get_all_transfer_auto_schedules_sync(
@@ -281,7 +619,7 @@ impl DbController {
         .execute(&self.pool)
         .await
         .map(|x| x.rows_affected())?
-            <= 0
+            == 0
         {
             sqlx::query!(
                 r#"
@@ -371,6 +709,11 @@ impl DbController {
             .map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
         let warnings_json =
             serde_json::to_string(&job.warnings).map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
+        self.prepare_vault().await?;
+        let job = &TransferJobInternalDto {
+            smb_password: self.seal("transfer_jobs_internal", "smb_password", &job.smb_password)?,
+            ..job.clone()
+        };
 
         if sqlx::query!(
             r#"
@@ -468,6 +811,7 @@ impl DbController {
     pub async fn get_all_transfer_jobs_internal(
         &self,
     ) -> Result<Vec<TransferJobInternalDto>, sqlx::Error> {
+        self.prepare_vault().await?;
         let results = sqlx::query_as::<_, TransferJobInternalDto>(
             r#"
             SELECT * FROM transfer_jobs_internal
@@ -475,7 +819,19 @@ impl DbController {
         )
         .fetch_all(&self.pool)
         .await?;
-        Ok(results)
+        results
+            .into_iter()
+            .map(|x| {
+                Ok(TransferJobInternalDto {
+                    smb_password: self.open(
+                        "transfer_jobs_internal",
+                        "smb_password",
+                        x.smb_password.clone(),
+                    )?,
+                    ..x
+                })
+            })
+            .collect()
     }
 
     pub fn get_all_transfer_jobs_internal_sync(
@@ -505,27 +861,43 @@ impl DbController {
             "sqlite://{}",
             get_working_dir().join("neptis.db").to_str().unwrap()
         );
+        let pool = rt.block_on(async move {
+            if !Sqlite::database_exists(&url).await.unwrap_or(false) {
+                Sqlite::create_database(&url)
+                    .await
+                    .expect("Failed to create Database!");
+            }
+            let pool = SqlitePoolOptions::new()
+                .max_connections(4)
+                .connect(&url)
+                .await
+                .expect("Expected pool to open!");
+            sqlx::migrate!()
+                .run(&pool)
+                .await
+                .expect("Failed to run migrations!");
+            pool
+        });
+        let vault_configured =
+            rt.block_on(
+                sqlx::query_as::<_, (i64,)>("SELECT COUNT(*) FROM vault_keys").fetch_one(&pool),
+            )
+            .expect("Failed to read the credential vault!")
+            .0 > 0;
         Self {
             rt: rt.clone(),
-            pool: {
-                rt.block_on(async move {
-                    if !Sqlite::database_exists(&url).await.unwrap_or(false) {
-                        Sqlite::create_database(&url)
-                            .await
-                            .expect("Failed to create Database!");
-                    }
-                    let pool = SqlitePoolOptions::new()
-                        .max_connections(4)
-                        .connect(&url)
-                        .await
-                        .expect("Expected pool to open!");
-                    sqlx::migrate!()
-                        .run(&pool)
-                        .await
-                        .expect("Failed to run migrations!");
-                    pool
-                })
-            },
+            pool,
+            vault: RwLock::new(None),
+            vault_configured: AtomicBool::new(vault_configured),
+            unattended_unlock: false,
+            failed_unattended: Mutex::new(None),
         }
     }
+
+    /// Unlocks the vault with [`VaultKey::unattended`] whenever credentials are needed while it
+    /// is locked, such as after the GUI set it up or created a key file. Meant for the daemon.
+    pub fn with_unattended_unlock(mut self) -> Self {
+        self.unattended_unlock = true;
+        self
+    }
 }
@@ -2,4 +2,5 @@ pub mod controller;
 pub mod server;
 pub mod transfer;
 pub mod prelude;
-pub mod sync_models;
\ No newline at end of file
+pub mod sync_models;
+pub mod vault;
\ No newline at end of file
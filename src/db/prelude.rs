@@ -1,3 +1,4 @@
 pub use super::controller::*;
 pub use super::server::*;
-pub use super::transfer::*;
\ No newline at end of file
+pub use super::transfer::*;
+pub use super::vault::*;
\ No newline at end of file
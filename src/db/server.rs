@@ -1,3 +1,4 @@
+use crate::apis::api::EnvelopePolicy;
 use crate::traits::ToShortIdString;
 use serde::{Deserialize, Serialize};
 use sqlx::prelude::FromRow;
@@ -13,6 +14,20 @@ pub struct ServerItem {
     pub arduino_password: Option<String>,
     pub auto_fuse: bool,
     pub is_default: bool,
+    /// Allows the legacy CBC envelope for servers which predate versioned envelopes.
+    #[serde(default)]
+    pub allow_legacy_envelope: bool,
+}
+
+impl ServerItem {
+    /// Returns the envelope policy to use for the secure API of this server.
+    pub fn envelope_policy(&self) -> EnvelopePolicy {
+        if self.allow_legacy_envelope {
+            EnvelopePolicy::legacy()
+        } else {
+            EnvelopePolicy::default()
+        }
+    }
 }
 
 impl ToShortIdString for ServerItem {
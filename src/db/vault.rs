@@ -0,0 +1,321 @@
+use std::env;
+use std::fmt::{self, Display, Formatter};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use aes_gcm::Aes256Gcm;
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use argon2::Argon2;
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use rand::{RngCore, rng};
+
+use crate::apis::NeptisError;
+use crate::get_working_dir;
+
+/// Marks a column value as encrypted by the vault. Anything else is read as plaintext, so
+/// rows written before the vault was set up keep working until they are encrypted.
+pub const VAULT_PREFIX: &str = "$neptis-vault$v1$";
+
+/// Sets the passphrase used to unlock the vault without a prompt, such as for the IPC daemon.
+pub const VAULT_PASSPHRASE_ENV: &str = "NEPTIS_VAULT_PASSPHRASE";
+
+/// Overrides the key file used to unlock the vault without a prompt.
+pub const VAULT_KEY_FILE_ENV: &str = "NEPTIS_VAULT_KEY_FILE";
+
+/// The `(table, column)` pairs which are stored encrypted once the vault is set up.
+pub const VAULT_COLUMNS: [(&str, &str); 6] = [
+    ("server_items", "server_password"),
+    ("server_items", "user_password"),
+    ("server_items", "arduino_password"),
+    ("transfer_auto_schedules", "smb_password"),
+    ("transfer_auto_schedules", "user_password"),
+    ("transfer_jobs_internal", "smb_password"),
+];
+
+const KEY_LEN: usize = 32;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+/// Something which can unlock the credential vault.
+///
+/// Each source wraps the same data key, so a vault set up with a passphrase for the GUI can
+/// also get a key file for the IPC daemon, and either one unlocks it.
+#[derive(Clone, PartialEq, Eq)]
+pub enum VaultKey {
+    Passphrase(String),
+    KeyFile(PathBuf),
+}
+
+impl VaultKey {
+    /// The key file used when [`VAULT_KEY_FILE_ENV`] is not set.
+    pub fn default_key_file() -> PathBuf {
+        env::var_os(VAULT_KEY_FILE_ENV)
+            .map(PathBuf::from)
+            .unwrap_or_else(|| get_working_dir().join("vault.key"))
+    }
+
+    /// Writes a new random key file, readable only by the current user on Unix.
+    pub fn create_key_file<P: AsRef<Path>>(path: P) -> Result<Self, NeptisError> {
+        let mut key = [0u8; KEY_LEN];
+        rng().fill_bytes(&mut key);
+        fs::write(&path, STANDARD.encode(key))?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o600))?;
+        }
+        Ok(VaultKey::KeyFile(path.as_ref().to_path_buf()))
+    }
+
+    /// Returns the key which can be used without asking the user: the passphrase from
+    /// [`VAULT_PASSPHRASE_ENV`], or else the key file if it exists.
+    pub fn unattended() -> Option<Self> {
+        if let Ok(passphrase) = env::var(VAULT_PASSPHRASE_ENV)
+            && !passphrase.is_empty()
+        {
+            return Some(VaultKey::Passphrase(passphrase));
+        }
+        Some(VaultKey::default_key_file())
+            .filter(|x| x.is_file())
+            .map(VaultKey::KeyFile)
+    }
+
+    /// Returns when the key file was last changed, so a replaced file can be told apart from
+    /// the one it replaced. Passphrases have no such time.
+    pub fn modified(&self) -> Option<SystemTime> {
+        match self {
+            VaultKey::Passphrase(_) => None,
+            VaultKey::KeyFile(path) => fs::metadata(path).and_then(|x| x.modified()).ok(),
+        }
+    }
+
+    /// The name this source is stored under in the `vault_keys` table.
+    pub fn source(&self) -> &'static str {
+        match self {
+            VaultKey::Passphrase(_) => "passphrase",
+            VaultKey::KeyFile(_) => "key_file",
+        }
+    }
+
+    fn material(&self) -> Result<Vec<u8>, NeptisError> {
+        match self {
+            VaultKey::Passphrase(x) => Ok(x.as_bytes().to_vec()),
+            VaultKey::KeyFile(path) => {
+                let data = fs::read_to_string(path)?;
+                STANDARD
+                    .decode(data.trim())
+                    .map_err(|_| NeptisError::Str("The vault key file is not valid!".into()))
+            }
+        }
+    }
+
+    /// Derives the key which wraps the data key, using Argon2id with its default parameters.
+    fn derive(&self, salt: &[u8]) -> Result<[u8; KEY_LEN], NeptisError> {
+        let mut ret = [0u8; KEY_LEN];
+        Argon2::default()
+            .hash_password_into(&self.material()?, salt, &mut ret)
+            .map_err(|e| NeptisError::Str(format!("Failed to derive the vault key: {e}")))?;
+        Ok(ret)
+    }
+}
+
+/// Whether the credential vault is set up, and unlocked for this session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VaultStatus {
+    /// Credentials are stored in plaintext.
+    NotConfigured,
+    Locked,
+    Unlocked,
+}
+
+impl Display for VaultStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            VaultStatus::NotConfigured => "Not Configured",
+            VaultStatus::Locked => "Locked",
+            VaultStatus::Unlocked => "Unlocked",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// An unlocked credential vault, which encrypts the columns in [`VAULT_COLUMNS`].
+///
+/// Values are encrypted with AES-256-GCM under a random data key, and bound to their column
+/// so an encrypted value cannot be moved into another one. The data key is stored once for
+/// every [`VaultKey`] source, encrypted with a key derived from that source.
+#[derive(Clone)]
+pub struct CredentialVault {
+    key: [u8; KEY_LEN],
+}
+
+impl CredentialVault {
+    /// Creates a vault with a new random data key.
+    pub(crate) fn generate() -> Self {
+        let mut key = [0u8; KEY_LEN];
+        rng().fill_bytes(&mut key);
+        CredentialVault { key }
+    }
+
+    /// Encrypts the data key with `key`, returning the `(salt, wrapped_key)` to store.
+    pub(crate) fn wrap(&self, key: &VaultKey) -> Result<(String, String), NeptisError> {
+        let mut salt = [0u8; SALT_LEN];
+        rng().fill_bytes(&mut salt);
+        let kek = key.derive(&salt)?;
+        let wrapped = seal(&kek, &self.key, key.source().as_bytes())?;
+        Ok((STANDARD.encode(salt), STANDARD.encode(wrapped)))
+    }
+
+    /// Recovers the data key stored by [`CredentialVault::wrap`]. This fails when `key` is not
+    /// the one it was wrapped with.
+    pub(crate) fn unwrap(key: &VaultKey, salt: &str, wrapped: &str) -> Result<Self, NeptisError> {
+        let invalid = || NeptisError::Str("The vault key data is corrupt!".into());
+        let salt = STANDARD.decode(salt).map_err(|_| invalid())?;
+        let wrapped = STANDARD.decode(wrapped).map_err(|_| invalid())?;
+        let kek = key.derive(&salt)?;
+        let data = open(&kek, &wrapped, key.source().as_bytes()).ok_or(NeptisError::Str(
+            match key {
+                VaultKey::Passphrase(_) => "The vault passphrase is incorrect!",
+                VaultKey::KeyFile(_) => "The vault key file does not match!",
+            }
+            .into(),
+        ))?;
+        Ok(CredentialVault {
+            key: data.try_into().map_err(|_| invalid())?,
+        })
+    }
+
+    pub fn is_encrypted(value: &str) -> bool {
+        value.starts_with(VAULT_PREFIX)
+    }
+
+    /// Encrypts a value for `table.column`. Values which are already encrypted are kept.
+    pub fn encrypt(&self, table: &str, column: &str, value: &str) -> Result<String, NeptisError> {
+        if Self::is_encrypted(value) {
+            return Ok(value.to_string());
+        }
+        let data = seal(
+            &self.key,
+            value.as_bytes(),
+            column_aad(table, column).as_bytes(),
+        )?;
+        Ok(format!("{VAULT_PREFIX}{}", STANDARD.encode(data)))
+    }
+
+    /// Decrypts a value read from `table.column`. Plaintext values are returned as they are.
+    pub fn decrypt(&self, table: &str, column: &str, value: &str) -> Result<String, NeptisError> {
+        let Some(data) = value.strip_prefix(VAULT_PREFIX) else {
+            return Ok(value.to_string());
+        };
+        let err = || NeptisError::Str(format!("Failed to decrypt {table}.{column}!"));
+        let data = STANDARD.decode(data).map_err(|_| err())?;
+        let data = open(&self.key, &data, column_aad(table, column).as_bytes()).ok_or_else(err)?;
+        String::from_utf8(data).map_err(|_| err())
+    }
+}
+
+fn column_aad(table: &str, column: &str) -> String {
+    format!("{table}.{column}")
+}
+
+/// Encrypts `msg`, returning the random nonce followed by the ciphertext.
+fn seal(key: &[u8], msg: &[u8], aad: &[u8]) -> Result<Vec<u8>, NeptisError> {
+    let mut nonce = [0u8; NONCE_LEN];
+    rng().fill_bytes(&mut nonce);
+    let data = Aes256Gcm::new_from_slice(key)
+        .ok()
+        .and_then(|x| x.encrypt((&nonce).into(), Payload { msg, aad }).ok())
+        .ok_or(NeptisError::Str(
+            "Failed to encrypt with the vault key!".into(),
+        ))?;
+    Ok([nonce.as_slice(), &data].concat())
+}
+
+fn open(key: &[u8], data: &[u8], aad: &[u8]) -> Option<Vec<u8>> {
+    if data.len() < NONCE_LEN {
+        return None;
+    }
+    let (nonce, msg) = data.split_at(NONCE_LEN);
+    Aes256Gcm::new_from_slice(key)
+        .ok()?
+        .decrypt(nonce.into(), Payload { msg, aad })
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    const TABLE: &str = "server_items";
+
+    fn temp_key_file() -> (PathBuf, VaultKey) {
+        let path = env::temp_dir().join(format!("neptis-vault-{}.key", Uuid::new_v4()));
+        let key = VaultKey::create_key_file(&path).unwrap();
+        (path, key)
+    }
+
+    #[test]
+    fn wrapped_keys_round_trip() {
+        let vault = CredentialVault::generate();
+        let passphrase = VaultKey::Passphrase("correct horse".into());
+        let (path, key_file) = temp_key_file();
+
+        for key in [&passphrase, &key_file] {
+            let (salt, wrapped) = vault.wrap(key).unwrap();
+            let unwrapped = CredentialVault::unwrap(key, &salt, &wrapped).unwrap();
+            let value = vault.encrypt(TABLE, "user_password", "hunter2").unwrap();
+            let plain = unwrapped.decrypt(TABLE, "user_password", &value).unwrap();
+            assert_eq!(plain, "hunter2");
+        }
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn wrong_keys_are_rejected() {
+        let vault = CredentialVault::generate();
+        let key = VaultKey::Passphrase("correct horse".into());
+        let (salt, wrapped) = vault.wrap(&key).unwrap();
+        let wrong = VaultKey::Passphrase("battery staple".into());
+        assert!(CredentialVault::unwrap(&wrong, &salt, &wrapped).is_err());
+
+        let (path, key_file) = temp_key_file();
+        let (other_path, other_file) = temp_key_file();
+        let (salt, wrapped) = vault.wrap(&key_file).unwrap();
+        assert!(CredentialVault::unwrap(&other_file, &salt, &wrapped).is_err());
+        fs::remove_file(path).unwrap();
+        fs::remove_file(other_path).unwrap();
+    }
+
+    #[test]
+    fn values_are_bound_to_their_column() {
+        let vault = CredentialVault::generate();
+        let value = vault.encrypt(TABLE, "user_password", "hunter2").unwrap();
+        assert!(vault.decrypt(TABLE, "server_password", &value).is_err());
+        let moved = vault.decrypt("transfer_jobs_internal", "user_password", &value);
+        assert!(moved.is_err());
+
+        let other = CredentialVault::generate();
+        assert!(other.decrypt(TABLE, "user_password", &value).is_err());
+    }
+
+    #[test]
+    fn plaintext_passes_through() {
+        let vault = CredentialVault::generate();
+        assert!(!CredentialVault::is_encrypted("hunter2"));
+        let plain = vault.decrypt(TABLE, "user_password", "hunter2").unwrap();
+        assert_eq!(plain, "hunter2");
+    }
+
+    #[test]
+    fn encrypting_twice_keeps_the_value() {
+        let vault = CredentialVault::generate();
+        let value = vault.encrypt(TABLE, "user_password", "hunter2").unwrap();
+        assert!(CredentialVault::is_encrypted(&value));
+        let again = vault.encrypt(TABLE, "user_password", &value).unwrap();
+        assert_eq!(again, value);
+        let plain = vault.decrypt(TABLE, "user_password", &again).unwrap();
+        assert_eq!(plain, "hunter2");
+    }
+}
@@ -1,19 +1,24 @@
 use base64::{Engine, prelude::BASE64_STANDARD};
+use futures::TryStreamExt;
+use itertools::Itertools;
 use moka::sync::Cache;
 use serde::{Deserialize, Serialize};
 use std::{
     collections::HashMap,
-    path::{Path, PathBuf},
-    sync::{Arc, RwLock},
+    path::{Component, Path, PathBuf},
+    sync::{Arc, Mutex, RwLock},
     time::{Duration, SystemTime},
 };
 use tokio::runtime::Runtime;
 
 use crate::{
     apis::{
-        api::WebApi,
-        dtos::{NodeDto, PostForFileApi, PutForFileApi},
+        NeptisError,
+        api::{DUMP_CHUNK_BYTES, UPLOAD_CHUNK_BYTES, WebApi},
+        dtos::{NodeDto, PostForFileApi, PutForFileApi, SharedMount},
     },
+    file_size::FileSize,
+    models::FilePutDto,
     from_dto_time, to_dto_time
 };
 
@@ -21,7 +26,13 @@ pub struct NeptisFS {
     api: Arc<RwLock<Option<WebApi>>>,
     rt: Arc<Runtime>,
     cache_lookup: Cache<PathBuf, Vec<FsNode>>,
-    cache_dump: Cache<PathBuf, Arc<Vec<u8>>>,
+    cache_dump: Cache<(PathBuf, u64), Arc<Vec<u8>>>,
+    cache_shared: Cache<(), Arc<Vec<SharedMount>>>,
+    cache_shared_file: Cache<PathBuf, Arc<Vec<u8>>>,
+    cache_snapshot: Cache<PathBuf, Arc<Vec<u8>>>,
+    /// Files of shared points which have been written but not uploaded yet, see
+    /// [`NeptisFS::do_flush`].
+    pending_shared: Mutex<HashMap<PathBuf, Vec<u8>>>,
 }
 
 #[derive(Clone, Debug)]
@@ -85,6 +96,43 @@ const FS_DURATION: Duration = Duration::from_secs(0);
 
 const MAX_CACHE_SIZE: u64 = 1024 * 1024 * 1024;
 
+/// The largest file which is read from a snapshot or a shared point. Their APIs can only send
+/// a whole file, which is kept in memory and cached between reads, so larger files are refused
+/// instead of being downloaded again for every read.
+pub const MAX_WHOLE_FILE_BYTES: u64 = 256 * 1024 * 1024;
+
+/// Prefix for the root folders holding points shared by other users, such as `/@alice`.
+/// These folders only exist on the client, and files inside them go through the data API.
+pub const SHARED_PREFIX: &str = "@";
+
+/// Name of the virtual, read-only folder inside each point which holds its snapshots, such as
+/// `/<point>/.snapshots/<snapshot-id>/`.
+pub const SNAPSHOTS_DIR: &str = ".snapshots";
+
+/// A path inside the virtual `/@<owner>` folder which groups the points shared by one user.
+enum SharedPath {
+    /// The `/@<owner>` folder itself, listing the points shared by the owner.
+    Owner { owner: String },
+    /// A file or folder inside a shared point, relative to the point root.
+    Tree {
+        owner: String,
+        point: String,
+        rel: String,
+    },
+}
+
+/// A path inside the virtual snapshot folder of a point.
+enum SnapshotPath {
+    /// The `.snapshots` folder itself, listing every snapshot of the point.
+    List { point: String },
+    /// A file or folder inside one snapshot, relative to the snapshot root.
+    Tree {
+        point: String,
+        id: String,
+        rel: String,
+    },
+}
+
 impl NeptisFS {
     pub fn new(api: Arc<RwLock<Option<WebApi>>>, rt: Arc<Runtime>) -> Self {
         let cache_dump = Cache::builder()
@@ -100,11 +148,35 @@ impl NeptisFS {
             .max_capacity(MAX_CACHE_SIZE)
             .time_to_live(Duration::from_secs(10))
             .build();
+        let cache_shared = Cache::builder()
+            .time_to_live(Duration::from_secs(10))
+            .build();
+        // The data API only downloads whole files, so shared files are kept between reads.
+        let cache_shared_file = Cache::builder()
+            .support_invalidation_closures()
+            .weigher(|_, value: &Arc<Vec<u8>>| -> u32 {
+                value.len().try_into().unwrap_or(u32::MAX)
+            })
+            .max_capacity(MAX_CACHE_SIZE)
+            .time_to_live(Duration::from_secs(10))
+            .build();
+        // Snapshots never change, so their files can be kept for longer.
+        let cache_snapshot = Cache::builder()
+            .weigher(|_, value: &Arc<Vec<u8>>| -> u32 {
+                value.len().try_into().unwrap_or(u32::MAX)
+            })
+            .max_capacity(MAX_CACHE_SIZE)
+            .time_to_idle(Duration::from_secs(300))
+            .build();
         NeptisFS {
             api,
             rt,
             cache_dump,
             cache_lookup,
+            cache_shared,
+            cache_shared_file,
+            cache_snapshot,
+            pending_shared: Mutex::new(HashMap::new()),
         }
     }
 
@@ -116,12 +188,17 @@ impl NeptisFS {
 
         let _ = self
             .cache_dump
-            .invalidate_entries_if(move |x, _| x.starts_with(&p1));
+            .invalidate_entries_if(move |(x, _), _| x.starts_with(&p1));
 
         let p2 = parent.clone();
         let _ = self
             .cache_lookup
             .invalidate_entries_if(move |x, _| x.starts_with(&p2));
+
+        let p3 = parent.clone();
+        let _ = self
+            .cache_shared_file
+            .invalidate_entries_if(move |x, _| x.starts_with(&p3));
     }
 
     fn generic_dir_attr() -> GenericFileAttr {
@@ -141,6 +218,14 @@ impl NeptisFS {
             flags: 0,
         }
     }
+    fn read_only(mut attr: GenericFileAttr) -> GenericFileAttr {
+        attr.perm = match attr.kind {
+            GenericFileType::Directory => 0o555,
+            _ => 0o444,
+        };
+        attr
+    }
+
     fn to_attr(node: &NodeDto) -> GenericFileAttr {
         GenericFileAttr {
             size: node.bytes,
@@ -173,15 +258,381 @@ impl NeptisFS {
         let def = PathBuf::from("/");
         let parent = path.parent().unwrap_or(&def);
         let name = path.file_name().ok_or(libc::ENOENT)?;
-        self.do_readdir(parent)
+        let mut ret = self
+            .do_readdir(parent)
             .ok_or(libc::ENETUNREACH)?
             .into_iter()
             .find(|x| x.path == name)
-            .ok_or(libc::ENOENT)
+            .ok_or(libc::ENOENT)?;
+        if let Some(data) = self.pending_shared.lock().unwrap().get(path) {
+            ret.attr.size = data.len() as u64;
+            ret.attr.blocks = ret.attr.size / BLOCK_SIZE;
+        }
+        Ok(ret)
+    }
+
+    fn shared_mounts(&self) -> Arc<Vec<SharedMount>> {
+        if let Some(ret) = self.cache_shared.get(&()) {
+            return ret;
+        }
+        let ret = {
+            let m_api = &*self.api.read().unwrap();
+            m_api
+                .as_ref()
+                .and_then(|api| {
+                    self.rt
+                        .block_on(async { api.get_shared_mounts().await })
+                        .ok()
+                })
+                .unwrap_or_default()
+        };
+        let arc = Arc::new(ret);
+        self.cache_shared.insert((), arc.clone());
+        arc
+    }
+
+    /// Returns the share which gives this user access to a point of `owner`.
+    fn shared_mount(&self, owner: &str, point: &str) -> Option<SharedMount> {
+        self.shared_mounts()
+            .iter()
+            .find(|x| x.owned_by == owner && x.name == point)
+            .cloned()
+    }
+
+    /// Returns the shared location if the path is inside a `/@<owner>` folder.
+    fn shared_path(path: &Path) -> Option<SharedPath> {
+        let mut parts = path.components().skip(1).map(|x| match x {
+            Component::Normal(p) => p.to_str(),
+            _ => None,
+        });
+        let owner = parts.next()??.strip_prefix(SHARED_PREFIX)?.to_string();
+        let Some(point) = parts.next() else {
+            return Some(SharedPath::Owner { owner });
+        };
+        let rel = parts.collect::<Option<Vec<_>>>()?.join("/");
+        Some(SharedPath::Tree {
+            owner,
+            point: point?.to_string(),
+            rel: format!("/{}", rel),
+        })
+    }
+
+    /// Returns the snapshot location if the path is inside the `.snapshots` folder of a point.
+    fn snapshot_path(path: &Path) -> Option<SnapshotPath> {
+        let mut parts = path.components().skip(1).map(|x| match x {
+            Component::Normal(p) => p.to_str(),
+            _ => None,
+        });
+        let point = parts.next()??;
+        if point.starts_with(SHARED_PREFIX) || parts.next()? != Some(SNAPSHOTS_DIR) {
+            return None;
+        }
+        let Some(id) = parts.next() else {
+            return Some(SnapshotPath::List {
+                point: point.to_string(),
+            });
+        };
+        let rel = parts.collect::<Option<Vec<_>>>()?.join("/");
+        Some(SnapshotPath::Tree {
+            point: point.to_string(),
+            id: id?.to_string(),
+            rel: format!("/{}", rel),
+        })
+    }
+
+    fn is_snapshot(path: &Path) -> bool {
+        Self::snapshot_path(path).is_some()
+    }
+
+    /// Returns true if the path cannot be changed, such as anything inside a snapshot, or a
+    /// point shared by another user without write access.
+    pub fn is_read_only(&self, path: &Path) -> bool {
+        if Self::is_snapshot(path) {
+            return true;
+        }
+        match Self::shared_path(path) {
+            Some(SharedPath::Owner { .. }) => true,
+            Some(SharedPath::Tree { owner, point, .. }) => !self
+                .shared_mount(&owner, &point)
+                .is_some_and(|x| x.can_write),
+            None => false,
+        }
+    }
+
+    fn do_readdir_shared(&self, path: &Path, s_path: SharedPath) -> Option<Vec<FsNode>> {
+        let mut output = vec![
+            FsNode {
+                path: PathBuf::from(""),
+                attr: Self::generic_dir_attr(),
+            },
+            FsNode {
+                path: PathBuf::from(".."),
+                attr: Self::generic_dir_attr(),
+            },
+        ];
+        let (owner, point, rel) = match s_path {
+            SharedPath::Owner { owner } => {
+                output.extend(
+                    self.shared_mounts()
+                        .iter()
+                        .filter(|x| x.owned_by == owner)
+                        .map(|x| FsNode {
+                            path: PathBuf::from(x.name.as_str()),
+                            attr: Self::share_attr(Self::generic_dir_attr(), x),
+                        }),
+                );
+                return Some(output);
+            }
+            SharedPath::Tree { owner, point, rel } => (owner, point, rel),
+        };
+        let share = self.shared_mount(&owner, &point)?;
+        if let Some(x) = self.cache_lookup.get(path) {
+            output.extend(x);
+            return Some(output);
+        }
+        let ret = {
+            let m_api = &*self.api.read().unwrap();
+            let api = m_api.as_ref()?;
+            self.rt
+                .block_on(async { api.browse_data(&owner, &point, &rel).await })
+                .ok()?
+                .into_iter()
+                .map(|x| FsNode {
+                    path: PathBuf::from(x.path.rsplit('/').next().unwrap_or(&x.path)),
+                    attr: Self::share_attr(Self::to_attr(&x), &share),
+                })
+                .collect::<Vec<_>>()
+        };
+        self.cache_lookup.insert(path.to_path_buf(), ret.clone());
+        output.extend(ret);
+        Some(output)
+    }
+
+    fn share_attr(attr: GenericFileAttr, share: &SharedMount) -> GenericFileAttr {
+        if share.can_write {
+            attr
+        } else {
+            Self::read_only(attr)
+        }
+    }
+
+    /// Downloads a whole file from a shared point, since the data API cannot read a range.
+    fn do_dump_shared(&self, path: &Path) -> Option<Arc<Vec<u8>>> {
+        if let Some(data) = self.pending_shared.lock().unwrap().get(path) {
+            return Some(Arc::new(data.clone()));
+        }
+        if let Some(ret) = self.cache_shared_file.get(path) {
+            return Some(ret);
+        }
+        let SharedPath::Tree { owner, point, rel } = Self::shared_path(path)? else {
+            return None;
+        };
+        let ret = {
+            let m_api = &*self.api.read().unwrap();
+            let api = m_api.as_ref()?;
+            self.rt
+                .block_on(async { api.dump_data_file(&owner, &point, &rel).await })
+                .ok()?
+        };
+        let arc = Arc::new(ret);
+        self.cache_shared_file
+            .insert(path.to_path_buf(), arc.clone());
+        Some(arc)
+    }
+
+    /// Returns true if the file can only be downloaded whole, from a snapshot or shared point.
+    fn is_whole_file(path: &Path) -> bool {
+        Self::is_snapshot(path) || Self::shared_path(path).is_some()
+    }
+
+    /// Fails if the file is too large to download whole, see [`MAX_WHOLE_FILE_BYTES`].
+    fn check_whole_size(&self, path: &Path) -> Result<(), NeptisError> {
+        let size = self
+            .do_find(path)
+            .map_err(|_| NeptisError::Str("The file does not exist!".into()))?
+            .attr
+            .size;
+        if size > MAX_WHOLE_FILE_BYTES {
+            return Err(NeptisError::Str(format!(
+                "'{}' is {}, but files in snapshots and shared points can only be read up to {}!",
+                path.display(),
+                FileSize::prettify(size),
+                FileSize::prettify(MAX_WHOLE_FILE_BYTES)
+            )));
+        }
+        Ok(())
+    }
+
+    /// Downloads a whole file which cannot be read by range, from a snapshot or shared point.
+    fn do_dump_whole(&self, path: &Path) -> Result<Arc<Vec<u8>>, NeptisError> {
+        self.check_whole_size(path)?;
+        if Self::is_snapshot(path) {
+            self.do_dump_snapshot(path)
+        } else {
+            self.do_dump_shared(path)
+        }
+        .ok_or(NeptisError::Str("Failed to download the file!".into()))
+    }
+
+    /// Sends a change to a file in a shared point through the data API.
+    fn do_put_shared(&self, owner: &str, point: &str, dto: FilePutDto) -> Option<()> {
+        let m_api = &*self.api.read().unwrap();
+        let api = m_api.as_ref()?;
+        self.rt
+            .block_on(async { api.put_data_file(owner, point, dto).await })
+            .ok()
+    }
+
+    /// Applies [`NeptisFS::do_write`] to a shared point. The data API only replaces whole
+    /// files, so writes are applied to a copy of the file which is downloaded once, and only
+    /// uploaded by [`NeptisFS::do_flush`].
+    fn do_write_shared(
+        &self,
+        path: &Path,
+        new_path: Option<&Path>,
+        offset: Option<u64>,
+        data: Option<&[u8]>,
+        t_len: Option<u64>,
+    ) -> Option<()> {
+        let SharedPath::Tree { owner, point, rel } = Self::shared_path(path)? else {
+            return None;
+        };
+        if data.is_some() || t_len.is_some() {
+            if !self.pending_shared.lock().unwrap().contains_key(path) {
+                // Truncating the whole file does not need its old content.
+                let content = if t_len == Some(0) && data.is_none() {
+                    vec![]
+                } else {
+                    self.do_dump_whole(path).ok()?.to_vec()
+                };
+                self.pending_shared
+                    .lock()
+                    .unwrap()
+                    .entry(path.to_path_buf())
+                    .or_insert(content);
+            }
+            let mut pending = self.pending_shared.lock().unwrap();
+            let content = pending.get_mut(path)?;
+            if let Some(data) = data {
+                let start = offset.unwrap_or(0) as usize;
+                let end = start + data.len();
+                if content.len() < end {
+                    content.resize(end, 0);
+                }
+                content[start..end].copy_from_slice(data);
+            }
+            if let Some(t_len) = t_len {
+                content.resize(t_len as usize, 0);
+            }
+        }
+        if let Some(np) = new_path {
+            // Files can only be renamed within the same shared point.
+            let SharedPath::Tree {
+                owner: n_owner,
+                point: n_point,
+                rel: n_rel,
+            } = Self::shared_path(np)?
+            else {
+                return None;
+            };
+            if n_owner != owner || n_point != point {
+                return None;
+            }
+            self.do_flush(path)?;
+            self.do_put_shared(
+                &owner,
+                &point,
+                FilePutDto {
+                    path: Some(Some(rel)),
+                    new_path: Some(Some(n_rel)),
+                    ..FilePutDto::new()
+                },
+            )?;
+        }
+        Some(())
+    }
+
+    fn do_readdir_snapshot(&self, path: &Path, s_path: SnapshotPath) -> Option<Vec<FsNode>> {
+        let mut output = vec![
+            FsNode {
+                path: PathBuf::from(""),
+                attr: Self::read_only(Self::generic_dir_attr()),
+            },
+            FsNode {
+                path: PathBuf::from(".."),
+                attr: Self::read_only(Self::generic_dir_attr()),
+            },
+        ];
+        if let Some(x) = self.cache_lookup.get(path) {
+            output.extend(x);
+            return Some(output);
+        }
+        let ret = {
+            let m_api = &*self.api.read().unwrap();
+            let api = m_api.as_ref()?;
+            match s_path {
+                SnapshotPath::List { point } => self
+                    .rt
+                    .block_on(async { api.get_all_snapshots(&point).await })
+                    .ok()?
+                    .into_iter()
+                    .map(|x| {
+                        let mut attr = Self::read_only(Self::generic_dir_attr());
+                        attr.mtime = from_dto_time!(x.time);
+                        attr.ctime = attr.mtime;
+                        attr.crtime = attr.mtime;
+                        FsNode {
+                            path: PathBuf::from(x.id),
+                            attr,
+                        }
+                    })
+                    .collect::<Vec<_>>(),
+                SnapshotPath::Tree { point, id, rel } => self
+                    .rt
+                    .block_on(async { api.browse_snapshot(&point, &id, &rel).await })
+                    .ok()?
+                    .into_iter()
+                    .map(|x| FsNode {
+                        path: PathBuf::from(x.path.rsplit('/').next().unwrap_or(&x.path)),
+                        attr: Self::read_only(Self::to_attr(&x)),
+                    })
+                    .collect::<Vec<_>>(),
+            }
+        };
+        self.cache_lookup.insert(path.to_path_buf(), ret.clone());
+        output.extend(ret);
+        Some(output)
+    }
+
+    /// Downloads a whole file from a snapshot, which is cached since it never changes.
+    fn do_dump_snapshot(&self, path: &Path) -> Option<Arc<Vec<u8>>> {
+        if let Some(ret) = self.cache_snapshot.get(path) {
+            return Some(ret);
+        }
+        let SnapshotPath::Tree { point, id, rel } = Self::snapshot_path(path)? else {
+            return None;
+        };
+        let ret = {
+            let m_api = &*self.api.read().unwrap();
+            let api = m_api.as_ref()?;
+            self.rt
+                .block_on(async { api.dump_snapshot_file(&point, &id, &rel).await })
+                .ok()?
+        };
+        let arc = Arc::new(ret);
+        self.cache_snapshot.insert(path.to_path_buf(), arc.clone());
+        Some(arc)
     }
 
     // WORKING 5-3-25
     pub fn do_readdir(&self, path: &Path) -> Option<Vec<FsNode>> {
+        if let Some(s_path) = Self::snapshot_path(path) {
+            return self.do_readdir_snapshot(path, s_path);
+        }
+        if let Some(s_path) = Self::shared_path(path) {
+            return self.do_readdir_shared(path, s_path);
+        }
+
         let mut output = Vec::new();
 
         // Always include "." and ".." entries (relative paths)
@@ -255,44 +706,165 @@ impl NeptisFS {
             }
         }
 
+        let is_point = path.parent().is_some_and(|x| x.parent().is_none());
+        if is_point {
+            output.push(FsNode {
+                path: PathBuf::from(SNAPSHOTS_DIR),
+                attr: Self::read_only(Self::generic_dir_attr()),
+            });
+        }
+
+        if path.parent().is_none() {
+            // Points shared by other users are grouped under a folder for each owner.
+            for owner in self.shared_mounts().iter().map(|x| &x.owned_by).unique() {
+                output.push(FsNode {
+                    path: PathBuf::from(format!("{}{}", SHARED_PREFIX, owner)),
+                    attr: Self::generic_dir_attr(),
+                });
+            }
+        }
+
         Some(output)
     }
 
+    fn do_dump_chunk(&self, path: &Path, index: u64) -> Option<Arc<Vec<u8>>> {
+        let key = (path.to_path_buf(), index);
+        if let Some(ret) = self.cache_dump.get(&key) {
+            return Some(ret);
+        }
+        let ret = {
+            let m_api = &*self.api.read().unwrap();
+            if let Some(api) = m_api {
+                self.rt.block_on(async move {
+                    api.dump_file_bytes(
+                        &path.to_str().unwrap().replace("\\", "/"),
+                        index * DUMP_CHUNK_BYTES as u64,
+                        DUMP_CHUNK_BYTES,
+                    )
+                    .await
+                    .ok()
+                })
+            } else {
+                None
+            }
+        }?;
+        let arc = Arc::new(ret);
+        self.cache_dump.insert(key, arc.clone());
+        Some(arc)
+    }
+
+    /// Reads `size` bytes starting at `offset`, fetching only the chunks which cover the window.
     pub fn do_dump(&self, path: &Path, offset: u64, size: usize) -> Option<Arc<Vec<u8>>> {
-        match self.cache_dump.get(path) {
-            Some(ret) => Some(ret),
-            None => {
-                let ret = {
-                    let m_api = &*self.api.read().unwrap();
-                    if let Some(api) = m_api {
-                        self.rt.block_on(async move {
-                            api.dump_file(
-                                &path.to_str().unwrap().replace("\\", "/"),
-                                Some(offset),
-                                if size == usize::MAX {
-                                    None
-                                } else {
-                                    Some(size as usize)
-                                },
-                            )
-                            .await
-                            .ok()
-                        })
-                    } else {
-                        None
-                    }
-                    .map(|x| BASE64_STANDARD.decode(x).ok())
-                    .flatten()
-                };
-                if let Some(r) = ret {
-                    let arc = Arc::new(r);
-                    self.cache_dump.insert(path.to_path_buf(), arc.clone());
-                    Some(arc)
-                } else {
-                    None
-                }
+        if Self::is_whole_file(path) {
+            let data = self.do_dump_whole(path).ok()?;
+            let start = (offset as usize).min(data.len());
+            let end = start.saturating_add(size).min(data.len());
+            return Some(Arc::new(data[start..end].to_vec()));
+        }
+        let mut ret = Vec::new();
+        let mut index = offset / DUMP_CHUNK_BYTES as u64;
+        let mut skip = (offset % DUMP_CHUNK_BYTES as u64) as usize;
+        while ret.len() < size {
+            let chunk = self.do_dump_chunk(path, index)?;
+            if skip >= chunk.len() {
+                break;
+            }
+            let take = (chunk.len() - skip).min(size - ret.len());
+            ret.extend_from_slice(&chunk[skip..skip + take]);
+            if chunk.len() < DUMP_CHUNK_BYTES {
+                break; // end of file
             }
+            skip = 0;
+            index += 1;
         }
+        Some(Arc::new(ret))
+    }
+
+    /// Streams a file from `offset` to the end, handing each chunk to `on_chunk` as it arrives.
+    /// Returns the final offset, which is the full file size when the stream completes.
+    pub fn do_stream(
+        &self,
+        path: &Path,
+        offset: u64,
+        mut on_chunk: impl FnMut(&[u8]) -> std::io::Result<()>,
+    ) -> Result<u64, NeptisError> {
+        if Self::is_whole_file(path) {
+            let data = self.do_dump_whole(path)?;
+            let start = (offset as usize).min(data.len());
+            on_chunk(&data[start..])?;
+            return Ok(data.len() as u64);
+        }
+        let m_api = &*self.api.read().unwrap();
+        let api = m_api
+            .as_ref()
+            .ok_or(NeptisError::Str("API is not valid!".into()))?;
+        let p_str = path.to_str().unwrap().replace("\\", "/");
+        self.rt.block_on(async {
+            let mut stream = Box::pin(api.dump_file_stream(&p_str, offset, DUMP_CHUNK_BYTES));
+            let mut total = offset;
+            while let Some(chunk) = stream.try_next().await? {
+                on_chunk(&chunk)?;
+                total += chunk.len() as u64;
+            }
+            Ok(total)
+        })
+    }
+
+    /// Returns the size of a remote file, or `None` if it does not exist.
+    pub fn do_size(&self, path: &Path) -> Option<u64> {
+        if Self::is_whole_file(path) {
+            return self.do_find(path).ok().map(|x| x.attr.size);
+        }
+        let m_api = &*self.api.read().unwrap();
+        let api = m_api.as_ref()?;
+        let p_str = path.to_str().unwrap().replace("\\", "/");
+        self.rt
+            .block_on(async { api.get_file_size(&p_str).await })
+            .ok()
+            .flatten()
+    }
+
+    /// Uploads a local file in chunks, optionally resuming from the size already on the server.
+    pub fn do_upload(
+        &self,
+        path: &Path,
+        local: &Path,
+        resume: bool,
+        mut on_progress: impl FnMut(u64),
+    ) -> Result<u64, NeptisError> {
+        if let Some(SharedPath::Tree { owner, point, rel }) = Self::shared_path(path) {
+            // The data API has no resumable upload, so the whole file is sent at once.
+            let data = std::fs::read(local)?;
+            let size = data.len() as u64;
+            self.do_put_shared(
+                &owner,
+                &point,
+                FilePutDto {
+                    path: Some(Some(rel)),
+                    base64_content: Some(Some(BASE64_STANDARD.encode(&data))),
+                    is_directory: Some(false),
+                    ..FilePutDto::new()
+                },
+            )
+            .ok_or(NeptisError::Str("Failed to upload the shared file!".into()))?;
+            self.delete_cache(path);
+            on_progress(size);
+            return Ok(size);
+        }
+        let file = std::fs::File::open(local)?;
+        let ret = {
+            let m_api = &*self.api.read().unwrap();
+            let api = m_api
+                .as_ref()
+                .ok_or(NeptisError::Str("API is not valid!".into()))?;
+            let p_str = path.to_str().unwrap().replace("\\", "/");
+            self.rt.block_on(async {
+                api.upload_file(&p_str, file, resume, UPLOAD_CHUNK_BYTES, on_progress)
+                    .await
+            })
+        };
+        self.delete_cache(path);
+        ret
     }
 
     pub fn do_write(
@@ -305,7 +877,9 @@ impl NeptisFS {
         mtime: Option<SystemTime>,
         t_len: Option<u64>,
     ) -> Option<()> {
-        {
+        if Self::shared_path(path).is_some() {
+            self.do_write_shared(path, new_path, offset, data, t_len)
+        } else {
             let m_api = &*self.api.read().unwrap();
             if let Some(api) = m_api {
                 self.rt.block_on(async move {
@@ -332,8 +906,42 @@ impl NeptisFS {
         Some(())
     }
 
+    /// Uploads a file of a shared point which has been written since it was opened. Other
+    /// files are written as soon as [`NeptisFS::do_write`] is called, so there is nothing to do.
+    pub fn do_flush(&self, path: &Path) -> Option<()> {
+        let Some(content) = self.pending_shared.lock().unwrap().remove(path) else {
+            return Some(());
+        };
+        let SharedPath::Tree { owner, point, rel } = Self::shared_path(path)? else {
+            return None;
+        };
+        self.do_put_shared(
+            &owner,
+            &point,
+            FilePutDto {
+                path: Some(Some(rel)),
+                base64_content: Some(Some(BASE64_STANDARD.encode(&content))),
+                is_directory: Some(false),
+                ..FilePutDto::new()
+            },
+        )?;
+        self.delete_cache(path);
+        Some(())
+    }
+
     pub fn do_create(&self, path: &Path, is_dir: bool) -> Option<()> {
-        {
+        if let Some(SharedPath::Tree { owner, point, rel }) = Self::shared_path(path) {
+            self.do_put_shared(
+                &owner,
+                &point,
+                FilePutDto {
+                    path: Some(Some(rel)),
+                    base64_content: (!is_dir).then(|| Some(String::new())),
+                    is_directory: Some(is_dir),
+                    ..FilePutDto::new()
+                },
+            )
+        } else {
             let m_api = &*self.api.read().unwrap();
             if let Some(api) = m_api {
                 self.rt.block_on(async move {
@@ -355,7 +963,17 @@ impl NeptisFS {
     }
 
     pub fn do_delete(&self, path: &Path) -> Option<()> {
-        {
+        if let Some(SharedPath::Tree { owner, point, rel }) = Self::shared_path(path) {
+            self.pending_shared
+                .lock()
+                .unwrap()
+                .retain(|x, _| !x.starts_with(path));
+            let m_api = &*self.api.read().unwrap();
+            let api = m_api.as_ref()?;
+            self.rt
+                .block_on(async { api.delete_data_file(&owner, &point, &rel).await })
+                .ok()
+        } else {
             let m_api = &*self.api.read().unwrap();
             if let Some(api) = m_api {
                 self.rt
@@ -420,9 +1038,17 @@ impl FilesystemMT for NeptisFS {
         self.do_find(path).map(|x| (FS_DURATION, x.attr.into()))
     }
 
-    fn truncate(&self, _req: RequestInfo, path: &Path, _fh: Option<u64>, size: u64) -> ResultEmpty {
+    fn truncate(&self, _req: RequestInfo, path: &Path, fh: Option<u64>, size: u64) -> ResultEmpty {
+        if self.is_read_only(path) {
+            return Err(libc::EROFS);
+        }
         self.do_write(path, None, None, None, None, None, Some(size))
-            .ok_or(libc::ENETUNREACH)
+            .ok_or(libc::ENETUNREACH)?;
+        // Without an open file, nothing else is going to upload the change.
+        if fh.is_none() {
+            self.do_flush(path).ok_or(libc::ENETUNREACH)?;
+        }
+        Ok(())
     }
 
     fn utimens(
@@ -433,21 +1059,33 @@ impl FilesystemMT for NeptisFS {
         atime: Option<std::time::SystemTime>,
         mtime: Option<std::time::SystemTime>,
     ) -> ResultEmpty {
+        if self.is_read_only(path) {
+            return Err(libc::EROFS);
+        }
         self.do_write(path, None, None, None, atime, mtime, None)
             .ok_or(libc::ENETUNREACH)
     }
 
     fn mkdir(&self, _req: RequestInfo, parent: &Path, name: &OsStr, _mode: u32) -> ResultEntry {
         let path = parent.join(name);
+        if self.is_read_only(&path) {
+            return Err(libc::EROFS);
+        }
         self.do_create(&path, true).ok_or(libc::ENETUNREACH)?;
         self.do_find(&path).map(|x| (FS_DURATION, x.attr.into()))
     }
 
     fn unlink(&self, _req: RequestInfo, parent: &Path, name: &OsStr) -> ResultEmpty {
+        if self.is_read_only(&parent.join(name)) {
+            return Err(libc::EROFS);
+        }
         self.do_delete(&parent.join(name)).ok_or(libc::ENETUNREACH)
     }
 
     fn rmdir(&self, _req: RequestInfo, parent: &Path, name: &OsStr) -> ResultEmpty {
+        if self.is_read_only(&parent.join(name)) {
+            return Err(libc::EROFS);
+        }
         self.do_delete(&parent.join(name)).ok_or(libc::ENETUNREACH)
     }
 
@@ -459,6 +1097,9 @@ impl FilesystemMT for NeptisFS {
         newparent: &Path,
         newname: &OsStr,
     ) -> ResultEmpty {
+        if self.is_read_only(&parent.join(name)) || self.is_read_only(&newparent.join(newname)) {
+            return Err(libc::EROFS);
+        }
         self.do_write(
             &parent.join(name),
             Some(&newparent.join(newname)),
@@ -472,6 +1113,12 @@ impl FilesystemMT for NeptisFS {
     }
 
     fn open(&self, _req: RequestInfo, _path: &Path, _flags: u32) -> ResultOpen {
+        if self.is_read_only(_path) && (_flags as i32 & libc::O_ACCMODE) != libc::O_RDONLY {
+            return Err(libc::EROFS);
+        }
+        if Self::is_whole_file(_path) && self.check_whole_size(_path).is_err() {
+            return Err(libc::EFBIG);
+        }
         Ok((42, _flags))
     }
 
@@ -484,14 +1131,8 @@ impl FilesystemMT for NeptisFS {
         size: u32,
         callback: impl FnOnce(ResultSlice<'_>) -> CallbackResult,
     ) -> CallbackResult {
-        if let Some(full_data) = self.do_dump(path, 0, usize::MAX) {
-            let start = offset as usize;
-            let end = (start + size as usize).min(full_data.len());
-            if start >= full_data.len() {
-                return callback(Ok(&[]));
-            }
-            let slice = &full_data[start..end];
-            callback(Ok(slice))
+        if let Some(data) = self.do_dump(path, offset, size as usize) {
+            callback(Ok(data.as_slice()))
         } else {
             callback(Err(libc::ENETDOWN))
         }
@@ -506,6 +1147,9 @@ impl FilesystemMT for NeptisFS {
         data: Vec<u8>,
         _flags: u32,
     ) -> ResultWrite {
+        if self.is_read_only(path) {
+            return Err(libc::EROFS);
+        }
         self.do_write(
             path,
             None,
@@ -519,20 +1163,24 @@ impl FilesystemMT for NeptisFS {
         .ok_or(libc::ENETUNREACH)
     }
 
+    fn flush(&self, _req: RequestInfo, path: &Path, _fh: u64, _lock_owner: u64) -> ResultEmpty {
+        self.do_flush(path).ok_or(libc::ENETUNREACH)
+    }
+
     fn release(
         &self,
         _req: RequestInfo,
-        _path: &Path,
+        path: &Path,
         _fh: u64,
         _flags: u32,
         _lock_owner: u64,
         _flush: bool,
     ) -> ResultEmpty {
-        Ok(())
+        self.do_flush(path).ok_or(libc::ENETUNREACH)
     }
 
-    fn fsync(&self, _req: RequestInfo, _path: &Path, _fh: u64, _datasync: bool) -> ResultEmpty {
-        Ok(())
+    fn fsync(&self, _req: RequestInfo, path: &Path, _fh: u64, _datasync: bool) -> ResultEmpty {
+        self.do_flush(path).ok_or(libc::ENETUNREACH)
     }
 
     fn opendir(&self, _req: RequestInfo, _path: &Path, _flags: u32) -> ResultOpen {
@@ -576,6 +1224,9 @@ impl FilesystemMT for NeptisFS {
         flags: u32,
     ) -> ResultCreate {
         let path = parent.join(name);
+        if self.is_read_only(&path) {
+            return Err(libc::EROFS);
+        }
         self.do_create(&path, false).ok_or(libc::ENETUNREACH)?;
         self.do_find(&path).map(|x| CreatedEntry {
             ttl: FS_DURATION,
@@ -1,21 +1,25 @@
 ﻿use crate::ipc::errors::ApiError;
-use crate::prelude::{DbController, WebApi};
+use crate::models::WsNotificationDto;
+use crate::prelude::{DbController, NotificationOptions, NotificationSubscription, WebApi};
 use crate::rolling_secret::RollingSecret;
 use chrono::{NaiveDateTime, Utc};
 use notify_rust::Notification;
 use std::sync::Arc;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::runtime::Runtime;
 
 struct IPCSession {
-    api: WebApi,
-    errors: u64,
+    api: Arc<WebApi>,
+    feed: NotificationSubscription,
 }
 
 impl IPCSession {
-    pub fn new(api: WebApi) -> Self {
-        Self { api, errors: 0 }
+    pub fn new(api: WebApi, rt: &Runtime) -> Self {
+        let api = Arc::new(api);
+        let feed =
+            NotificationSubscription::spawn(api.clone(), NotificationOptions::default(), rt.handle());
+        Self { api, feed }
     }
 }
 
@@ -40,7 +44,9 @@ pub struct IPCMessageReceiver {
     blacklists: Vec<IPCSessionBlacklist>,
 }
 
-const MAX_ERRORS: u64 = 5;
+/// How often the saved servers are re-read to pick up added, removed or changed logins, and
+/// new messages are polled.
+const SYNC_INTERVAL: Duration = Duration::from_secs(15);
 
 impl IPCMessageReceiver {
     pub fn new(db: Arc<DbController>, rt: Arc<Runtime>) -> Self {
@@ -55,13 +61,25 @@ impl IPCMessageReceiver {
     pub fn _thread_iter(&mut self) -> Result<(), ApiError> {
         // First, attempt to pull all sessions from the database.
         self.blacklists
-            .retain(|x| x.expire_date < Utc::now().naive_utc());
+            .retain(|x| x.expire_date > Utc::now().naive_utc());
 
-        for (endpoint, user_name, user_pass, key) in self
+        let servers = self
             .rt
-            .block_on(async { self.db.get_all_servers().await })?
+            .block_on(async { self.db.get_all_servers().await })?;
+
+        // Stop listening to servers which were removed or lost their login.
+        self.sessions.retain(|x| {
+            servers.iter().any(|y| {
+                y.server_endpoint == x.api.get_endpoint()
+                    && y.user_name.as_deref() == Some(x.api.get_username().as_str())
+                    && y.user_password.as_deref() == Some(x.api.get_password().as_str())
+            })
+        });
+
+        for (endpoint, user_name, user_pass, key, policy) in servers
             .into_iter()
             .filter_map(|x| {
+                let policy = x.envelope_policy();
                 if let Some(user_name) = x.user_name
                     && let Some(user_pass) = x.user_password
                     && !self
@@ -75,6 +93,7 @@ impl IPCMessageReceiver {
                         user_pass,
                         x.server_password
                             .and_then(|x| RollingSecret::from_string(&x)),
+                        policy,
                     ))
                 } else {
                     None
@@ -90,10 +109,11 @@ impl IPCMessageReceiver {
                     && x.api.get_password() == user_pass
             }) {
                 // 7-9-25: DO NOT TRIGGER A WAKEUP HERE!
-                let api = WebApi::new(&endpoint, &user_name, &user_pass, key);
+                let api = WebApi::new(&endpoint, &user_name, &user_pass, key)
+                    .with_envelope_policy(policy);
                 if self.rt.block_on(async { api.get_info().await }).is_ok() {
                     self.sessions.retain(|x| x.api.get_endpoint() != endpoint);
-                    self.sessions.push(IPCSession::new(api));
+                    self.sessions.push(IPCSession::new(api, &self.rt));
                 }
                 else {
                     // Put the server on a temporary blacklist to try again later. This
@@ -103,36 +123,85 @@ impl IPCMessageReceiver {
             }
         }
 
-        // Do not keep pinging the same server if it disconnects!
-        self.sessions.retain(|x| x.errors <= MAX_ERRORS);
+        Ok(())
+    }
 
-        // Next, try to pull all new messages and send a notification.
+    /// Shows every notification which arrived since the last call, and marks it as read on
+    /// the server so it is not shown again.
+    pub fn _drain_notifications(&mut self) {
         for session in self.sessions.iter_mut() {
+            while let Some(notification) = session.feed.try_recv() {
+                println!("New notification received!");
+                let _ = Notification::new()
+                    .summary(&Self::get_summary(&notification))
+                    .body(&Self::get_body(&notification))
+                    .appname("Neptis")
+                    .show();
+                if let Some(id) = notification.id
+                    && let Err(e) = self
+                        .rt
+                        .block_on(async { session.api.get_one_notification(id).await })
+                {
+                    println!("Failed to mark the notification as read: {}", e);
+                }
+            }
+        }
+    }
+
+    /// Shows every new message. Messages are a separate feed from notifications, so they are
+    /// still polled alongside it.
+    pub fn _poll_messages(&mut self) {
+        for session in self.sessions.iter() {
             if let Ok(new_messages) = self
                 .rt
                 .block_on(async { session.api.get_all_messages(true).await })
             {
-                for notification in new_messages {
+                for message in new_messages {
                     println!("New message received!");
                     let _ = Notification::new()
-                        .summary(&notification.subject.unwrap_or("New Message".into()))
-                        .body(&notification.message)
+                        .summary(&message.subject.unwrap_or("New Message".into()))
+                        .body(&message.message)
                         .appname("Neptis")
                         .show();
                 }
-            } else {
-                session.errors += 1;
             }
         }
-        Ok(())
+    }
+
+    fn get_summary(dto: &WsNotificationDto) -> String {
+        dto.subject
+            .clone()
+            .flatten()
+            .or(dto.r#type.clone().flatten())
+            .unwrap_or("New Notification".into())
+    }
+
+    fn get_body(dto: &WsNotificationDto) -> String {
+        let mut ret = dto.description.clone().flatten().unwrap_or_default();
+        if let Some(result) = dto.result.clone().flatten() {
+            if !ret.is_empty() {
+                ret += "\n";
+            }
+            ret += &result;
+        }
+        if let Some(by) = dto.action_by.clone().flatten() {
+            ret += &format!("\n(by {})", by);
+        }
+        ret
     }
 
     pub fn handle_blocking(&mut self) {
+        let mut last_sync: Option<Instant> = None;
         loop {
-            if let Err(e) = self._thread_iter() {
-                println!("Failed to run notify: {}", e);
+            if last_sync.is_none_or(|x| x.elapsed() >= SYNC_INTERVAL) {
+                if let Err(e) = self._thread_iter() {
+                    println!("Failed to run notify: {}", e);
+                }
+                self._poll_messages();
+                last_sync = Some(Instant::now());
             }
-            thread::sleep(Duration::from_secs(15));
+            self._drain_notifications();
+            thread::sleep(Duration::from_millis(250));
         }
     }
 }
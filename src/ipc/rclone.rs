@@ -4,7 +4,8 @@ use crate::db::sync_models::{
 };
 use crate::ipc::errors::ApiError;
 use crate::prelude::{
-    DbController, JobStatus, PostForAutoScheduleStartDto, TransferJobInternalDto, WebApi,
+    DbController, JobWaitError, JobWaitOptions, PostForAutoScheduleStartDto,
+    TransferJobInternalDto, WebApi,
 };
 use crate::rolling_secret::RollingSecret;
 use base64::Engine;
@@ -17,6 +18,7 @@ use std::io::{BufRead, BufReader};
 use std::net::IpAddr;
 use std::path::Path;
 use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::{Receiver, Sender, channel};
 use std::sync::{Mutex, MutexGuard};
 use std::time::{Duration, SystemTime};
@@ -47,6 +49,12 @@ const FILE_NAME: &'static str = "rclone.exe";
 #[cfg(not(target_os = "windows"))]
 const FILE_NAME: &'static str = "rclone";
 
+/// The longest time to follow a server backup started after a sync finishes.
+const BACKUP_WAIT_TIMEOUT: Duration = Duration::from_secs(12 * 60 * 60);
+
+/// The rclone remote for the SMB share, set up through the `RCLONE_CONFIG_NEPTIS_*` variables.
+const SMB_REMOTE: &str = "neptis";
+
 #[derive(Clone)]
 pub struct RCloneSettings {
     working_path: PathBuf,
@@ -459,7 +467,8 @@ impl RCloneClient {
                             .server_password
                             .clone()
                             .and_then(|x| RollingSecret::from_string(&x)),
-                    );
+                    )
+                    .with_envelope_policy(server_item.envelope_policy());
                     res = rt.block_on(async move { api.get_info().await });
                     if res.is_ok() {
                         break;
@@ -524,19 +533,23 @@ impl RCloneClient {
                             .server_password
                             .clone()
                             .and_then(|x| RollingSecret::from_string(&x)),
-                    );
+                    )
+                    .with_envelope_policy(server_item.envelope_policy());
                     match rt.block_on(async {
                         api.post_one_backup(&point_name, false).await.map(|x| x.id)
                     }) {
                         Ok(b_id) => {
-                            // Attempt to read the status from the server until completion.
-                            thread::sleep(Duration::from_secs(2)); // *** wait for server init.
-                            loop {
-                                if let Ok(b_job) =
-                                    rt.block_on(async { api.get_one_job(b_id).await })
-                                {
+                            // Follow the backup on the server until it finishes, forwarding
+                            // its progress and any kill request.
+                            let cancel = Arc::new(AtomicBool::new(false));
+                            let opts = JobWaitOptions::default()
+                                .with_start_delay(Duration::from_secs(2))
+                                .with_poll_interval(Duration::from_secs(5))
+                                .with_timeout(BACKUP_WAIT_TIMEOUT)
+                                .with_cancel(cancel.clone())
+                                .with_progress(|progress| {
                                     let stat = RCloneStat {
-                                        bytes: b_job.used_bytes as u64,
+                                        bytes: progress.used_bytes,
                                         speed: 0,
                                         checks: 0,
                                         deletes: 0,
@@ -548,28 +561,37 @@ impl RCloneClient {
                                         server_side_copy_bytes: 0,
                                         server_side_move_bytes: 0,
                                         server_side_moves: 0,
-                                        total_bytes: b_job.total_bytes.unwrap_or(0) as u64,
+                                        total_bytes: progress.total_bytes.unwrap_or(0),
                                         total_checks: 0,
                                         total_transfers: 0,
                                         on_backup: true,
                                     };
                                     mark_message("", false, Some(stat));
-                                    for error in b_job.errors.iter() {
+                                    for error in progress.new_errors.iter() {
                                         mark_message(
                                             &format!("Backup Job: {}", error),
                                             false,
                                             None,
                                         );
                                     }
-
-                                    if b_job.job_status == JobStatus::Failed {
-                                        mark_message("Backup Job reported a failure.", true, None);
-                                        return;
-                                    } else if b_job.job_status == JobStatus::Successful {
-                                        break;
-                                    } else {
-                                        thread::sleep(Duration::from_secs(5));
+                                    if s_rx.try_recv().is_ok() {
+                                        cancel.store(true, Ordering::Relaxed);
+                                        let _ = r_tx.send(true);
                                     }
+                                });
+                            match rt.block_on(async { api.wait_for_job(b_id, opts).await }) {
+                                Ok(_) => {}
+                                Err(JobWaitError::Cancelled(_)) => {
+                                    mark_message("Operation cancelled", true, None);
+                                    return;
+                                }
+                                Err(JobWaitError::Failed(_)) => {
+                                    mark_message("Backup Job reported a failure.", true, None);
+                                    return;
+                                }
+                                Err(e) => {
+                                    mark_message(&format!("Backup Job: {}", e), true, None);
+                                    return;
                                 }
                             }
                         }
@@ -729,22 +751,15 @@ impl RCloneClient {
         let exe_path = self.settings.exe_path();
         let exe_path_str = exe_path.to_str().unwrap();
 
-        let host_id = BASE64_STANDARD.encode(&server.server_name).replace("=", "");
-        let mut c_entry = String::new();
-        {
-            let host = Self::_find_smb_address(&server.server_endpoint)?;
-            let pass = cmd!(exe_path_str, "obscure", &job.dto.smb_password).read()?;
-            c_entry += &format!("[{}]\n", &host_id);
-            c_entry += &format!("type = smb\n");
-            c_entry += &format!("host = {}\n", host);
-            c_entry += &format!("user = {}\n", &job.dto.smb_user_name);
-            c_entry += &format!("pass = {}\n", pass);
-        }
+        // The remote is passed through the environment of the rclone process, so the
+        // credentials are never written to disk or shown on the command line.
+        let host = Self::_find_smb_address(&server.server_endpoint)?;
+        let pass = cmd!(exe_path_str, "obscure", "-")
+            .stdin_bytes(job.dto.smb_password.as_bytes())
+            .read()?;
 
+        // Older versions wrote the remote to a temporary config file.
         Self::_remove_old_tmp_files(&self.settings.working_path, false);
-        let mut config_path = self.settings.working_path.join(Uuid::new_v4().to_string());
-        config_path.set_extension("tmp");
-        fs::write(&config_path, c_entry)?;
 
         let out_folder = Self::_parse_smb_path(&job.dto.smb_user_name, &job.dto.smb_folder).ok_or(
             ApiError::BadRequest("You did not correctly put in the SMB folder!".into()),
@@ -753,7 +768,7 @@ impl RCloneClient {
             exe_path_str,
             "sync",
             &job.dto.local_folder,
-            format!("{}:{}", host_id, &out_folder),
+            format!("{}:{}", SMB_REMOTE, &out_folder),
             "--use-json-log",
             "--stats",
             "1s",
@@ -762,7 +777,11 @@ impl RCloneClient {
             "--stats-log-level",
             "NOTICE"
         )
-        .env("RCLONE_CONFIG", config_path.to_str().unwrap())
+        .env("RCLONE_CONFIG", "notfound")
+        .env("RCLONE_CONFIG_NEPTIS_TYPE", "smb")
+        .env("RCLONE_CONFIG_NEPTIS_HOST", host)
+        .env("RCLONE_CONFIG_NEPTIS_USER", &job.dto.smb_user_name)
+        .env("RCLONE_CONFIG_NEPTIS_PASS", pass)
         .stderr_to_stdout();
 
         // Set the start date and attempt to pass it off to the thread.
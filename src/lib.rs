@@ -7,6 +7,8 @@ pub mod db;
 pub mod file_size;
 pub mod filesystem;
 pub mod macros;
+#[cfg(any(test, feature = "mock"))]
+pub mod mock;
 pub mod models;
 pub mod rolling_secret;
 pub mod traits;
@@ -0,0 +1,226 @@
+use std::io::Cursor;
+use std::sync::Arc;
+
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use chrono::Utc;
+use rocket::data::{self, ByteUnit, Data, FromData};
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::http::uri::Origin;
+use rocket::http::{Header, Method, RawStr, Status};
+use rocket::outcome::Outcome;
+use rocket::route::{self, Route};
+use rocket::{Request, Response};
+use serde::de::DeserializeOwned;
+
+use super::errors::MockError;
+use super::state::MockState;
+use crate::rolling_secret::{
+    ENVELOPE_HEADER, EnvelopePart, EnvelopeVersion, RollingSecret, envelope_aad,
+};
+
+/// The largest request body accepted by the mock server.
+const BODY_LIMIT: ByteUnit = ByteUnit::Mebibyte(16);
+
+/// How a request reached the server, cached on the request by [`SecureEnvelope`].
+#[derive(Clone)]
+pub enum Envelope {
+    Plain,
+    /// The request came through `/secure/`, so its body is encrypted and the response must
+    /// be encrypted with the same secret and format. Bodies are bound to the method and the
+    /// decrypted path, and use the key step the server was on when the request arrived.
+    Secure {
+        secret: RollingSecret,
+        version: EnvelopeVersion,
+        method: Method,
+        path: String,
+        step: u64,
+    },
+}
+
+impl Envelope {
+    pub fn of<'r>(req: &'r Request<'_>) -> &'r Envelope {
+        req.local_cache(|| Envelope::Plain)
+    }
+}
+
+/// Unwraps the encrypted `/secure/<path>` form used by clients with a secret.
+///
+/// The decrypted path replaces the request URI before routing, so secure requests reach the
+/// same routes as plain ones. Any query sent in the clear is appended to the decrypted one.
+/// Responses to secure requests, including errors, are encrypted and base64 encoded. Requests
+/// which cannot be decrypted are left on `/secure/`, where [`rejected_routes`] answers them.
+///
+/// Every versioned envelope format is accepted, and each answer carries the envelope header
+/// naming the format used. With `legacy_only` set, the envelope behaves like a server which
+/// predates versioned envelopes: it only reads the CBC format and never sends the header.
+///
+/// The secret is read from the [`MockState`] on every request, so tests can rotate it while
+/// the server runs. During a grace period the previous secret is accepted as well, and the
+/// response is sealed with whichever secret the request used.
+pub struct SecureEnvelope {
+    pub state: Arc<MockState>,
+    pub legacy_only: bool,
+    /// How far the server clock is ahead of the real one, in seconds.
+    pub clock_offset: i64,
+}
+
+impl SecureEnvelope {
+    fn step(&self) -> u64 {
+        RollingSecret::step_at(Utc::now().timestamp() + self.clock_offset)
+    }
+
+    fn unwrap_path(
+        &self,
+        req: &Request<'_>,
+    ) -> Option<(RollingSecret, EnvelopeVersion, String, Origin<'static>)> {
+        let enc = req.uri().path().as_str().strip_prefix("/secure/")?;
+        let current = self.state.secret()?;
+        let enc = RawStr::new(enc).percent_decode().ok()?;
+        let enc = STANDARD.decode(enc.as_bytes()).ok()?;
+        let aad = envelope_aad(EnvelopePart::Path, req.method().as_str(), "");
+        let (secret, version, path) = current.accepted().find_map(|secret| {
+            let (version, path) = if self.legacy_only {
+                (
+                    EnvelopeVersion::LegacyCbc,
+                    secret.decrypt_at(&enc, self.step())?,
+                )
+            } else {
+                // Clients which only speak the legacy format are still served in it.
+                secret.open_at(&enc, &aad, self.step(), true)?
+            };
+            Some((secret.without_previous(), version, path))
+        })?;
+        let path = String::from_utf8(path).ok()?;
+        let mut full = path.clone();
+        if let Some(query) = req.uri().query() {
+            full.push(if full.contains('?') { '&' } else { '?' });
+            full.push_str(query.as_str());
+        }
+        Some((secret, version, path, Origin::parse_owned(full).ok()?))
+    }
+
+    /// The format named in the envelope header of a request which could not be decrypted.
+    fn requested(req: &Request<'_>) -> EnvelopeVersion {
+        req.headers()
+            .get_one(ENVELOPE_HEADER)
+            .and_then(|x| x.parse().ok())
+            .unwrap_or_default()
+    }
+}
+
+#[rocket::async_trait]
+impl Fairing for SecureEnvelope {
+    fn info(&self) -> Info {
+        Info {
+            name: "Secure Envelope",
+            kind: Kind::Request | Kind::Response,
+        }
+    }
+
+    async fn on_request(&self, req: &mut Request<'_>, _: &mut Data<'_>) {
+        if let Some((secret, version, path, uri)) = self.unwrap_path(req) {
+            let method = req.method();
+            req.set_uri(uri);
+            req.local_cache(|| Envelope::Secure {
+                secret,
+                version,
+                method,
+                path,
+                step: self.step(),
+            });
+        }
+    }
+
+    async fn on_response<'r>(&self, req: &'r Request<'_>, res: &mut Response<'r>) {
+        let Envelope::Secure {
+            secret,
+            version,
+            method,
+            path,
+            step,
+        } = Envelope::of(req)
+        else {
+            if !self.legacy_only && req.uri().path().starts_with("/secure/") {
+                let version = Self::requested(req);
+                res.set_header(Header::new(ENVELOPE_HEADER, version.to_string()));
+            }
+            return;
+        };
+        if !self.legacy_only {
+            res.set_header(Header::new(ENVELOPE_HEADER, version.to_string()));
+        }
+        let body = res.body_mut().to_bytes().await.unwrap_or_default();
+        if body.is_empty() {
+            return;
+        }
+        let aad = envelope_aad(EnvelopePart::Response, method.as_str(), path);
+        match secret.seal_at(*version, &body, &aad, *step) {
+            Some(x) => {
+                let enc = STANDARD.encode(x);
+                res.set_sized_body(enc.len(), Cursor::new(enc));
+            }
+            None => {
+                res.set_status(Status::InternalServerError);
+                res.set_sized_body(0, Cursor::new(vec![]));
+            }
+        }
+    }
+}
+
+fn rejected<'r>(req: &'r Request<'_>, _: Data<'r>) -> route::BoxFuture<'r> {
+    let err = MockError::Unauthorized("Failed to decrypt the request".into());
+    route::Outcome::from(req, err).pin()
+}
+
+/// Answers every `/secure/` request the envelope could not decrypt.
+pub fn rejected_routes() -> Vec<Route> {
+    [Method::Get, Method::Post, Method::Put, Method::Delete]
+        .into_iter()
+        .map(|m| Route::new(m, "/<_..>", rejected))
+        .collect()
+}
+
+/// A JSON request body, decrypted first when the request came through the secure envelope.
+pub struct MockJson<T>(pub T);
+
+#[rocket::async_trait]
+impl<'r, T: DeserializeOwned> FromData<'r> for MockJson<T> {
+    type Error = MockError;
+
+    async fn from_data(req: &'r Request<'_>, data: Data<'r>) -> data::Outcome<'r, Self> {
+        let body = match data.open(BODY_LIMIT).into_bytes().await {
+            Ok(x) if x.is_complete() => x.into_inner(),
+            _ => {
+                let err = MockError::BadRequest("The body is too large".into());
+                return Outcome::Error((Status::PayloadTooLarge, err));
+            }
+        };
+        let body = match Envelope::of(req) {
+            Envelope::Plain => Some(body),
+            Envelope::Secure {
+                secret,
+                version,
+                method,
+                path,
+                step,
+            } => {
+                // The body must use the same format as the path, so it cannot be downgraded.
+                let aad = envelope_aad(EnvelopePart::Request, method.as_str(), path);
+                STANDARD
+                    .decode(body.as_slice())
+                    .ok()
+                    .and_then(|x| secret.open_at(&x, &aad, *step, !version.is_authenticated()))
+                    .filter(|x| x.0 == *version)
+                    .map(|x| x.1)
+            }
+        };
+        let Some(body) = body else {
+            let err = MockError::BadRequest("Failed to decrypt the body".into());
+            return Outcome::Error((Status::BadRequest, err));
+        };
+        match serde_json::from_slice(&body) {
+            Ok(x) => Outcome::Success(MockJson(x)),
+            Err(e) => Outcome::Error((Status::BadRequest, MockError::BadRequest(e.to_string()))),
+        }
+    }
+}
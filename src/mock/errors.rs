@@ -0,0 +1,53 @@
+use rocket::http::Status;
+use rocket::response::{Responder, status};
+use rocket::{Catcher, catch, catchers};
+use serde_json::{Value, json};
+use thiserror::Error;
+
+/// An error returned by the mock server, mapped to the status the real server would use.
+/// The message is sent as `{"error": ...}` so the client can surface it.
+#[derive(Debug, Error)]
+pub enum MockError {
+    #[error("{0}")]
+    BadRequest(String),
+
+    #[error("{0}")]
+    Unauthorized(String),
+
+    #[error("{0}")]
+    Forbidden(String),
+
+    #[error("{0}")]
+    NotFound(String),
+
+    #[error("{0}")]
+    Conflict(String),
+}
+
+impl MockError {
+    pub fn status(&self) -> Status {
+        match self {
+            MockError::BadRequest(_) => Status::BadRequest,
+            MockError::Unauthorized(_) => Status::Unauthorized,
+            MockError::Forbidden(_) => Status::Forbidden,
+            MockError::NotFound(_) => Status::NotFound,
+            MockError::Conflict(_) => Status::Conflict,
+        }
+    }
+}
+
+impl<'r> Responder<'r, 'static> for MockError {
+    fn respond_to(self, request: &'r rocket::Request<'_>) -> rocket::response::Result<'static> {
+        status::Custom(self.status(), json!({"error": self.to_string()})).respond_to(request)
+    }
+}
+
+/// Answers failed guards and unknown routes in the same form as [`MockError`].
+#[catch(default)]
+fn default_catcher(status: Status, _: &rocket::Request<'_>) -> Value {
+    json!({"error": status.reason().unwrap_or("Unknown error")})
+}
+
+pub fn get_catchers() -> Vec<Catcher> {
+    catchers![default_catcher]
+}
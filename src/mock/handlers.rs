@@ -0,0 +1,1486 @@
+use std::str::FromStr;
+use std::sync::Arc;
+
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use chrono::{DateTime, Utc};
+use rocket::http::Status;
+use rocket::outcome::Outcome;
+use rocket::request::{self, FromRequest, Request};
+use rocket::serde::json::Json;
+use rocket::{FromForm, Route, State, routes};
+use rocket::{delete, get, post, put};
+use uuid::Uuid;
+
+use super::envelope::MockJson;
+use super::errors::MockError;
+use super::state::{
+    MockMount, MockNode, MockState, MockUser, is_below, split_point, tree_children,
+};
+use crate::apis::api::{
+    AuthOutputDto, SystemSnapshotDto, UserDto, UserForCreateApi, UserForLoginApi, UserForUpdateApi,
+};
+use crate::apis::dtos::{
+    AutoJobDto, JobFilter, JobStatus, JobType, Message, MountDto, NodeDto, PostForBackupApi,
+    PostForFileApi, PostForMessageApi, PostForRestoreApi, PostForSubscriptionApi,
+    PutForAutoJobWebApi, PutForFileApi, PutForMountApi, PutForSnapshotApi, PutForSubscriptionApi,
+    RepoJobDto, SnapshotFileDto, SubscriptionDto,
+};
+use crate::apis::retention::RetentionPolicy;
+use crate::models::{
+    DataPointBrowseGetDto, DataPointDto, DataPointShareDto, FileDto, FileOutputDto, FilePutDto,
+    WsNotificationDto,
+};
+
+type MockResult<T> = Result<Json<T>, MockError>;
+
+/// The user behind the bearer token of a request.
+pub struct MockSession {
+    pub user_name: String,
+    pub is_admin: bool,
+}
+
+impl MockSession {
+    fn require_admin(&self) -> Result<(), MockError> {
+        if self.is_admin {
+            Ok(())
+        } else {
+            Err(MockError::Forbidden(
+                "Only an administrator can do this".into(),
+            ))
+        }
+    }
+
+    /// Allows the request if it is about the user themselves, or they are an administrator.
+    fn require_self(&self, user_name: &str) -> Result<(), MockError> {
+        if self.user_name == user_name {
+            Ok(())
+        } else {
+            self.require_admin()
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for MockSession {
+    type Error = MockError;
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        let state = req
+            .rocket()
+            .state::<Arc<MockState>>()
+            .expect("The mock state is not managed!");
+        let token = req
+            .headers()
+            .get_one("Authorization")
+            .and_then(|x| x.strip_prefix("Bearer "));
+        let data = state.lock();
+        let session = token
+            .and_then(|x| data.tokens.get(x))
+            .and_then(|x| data.users.get(x))
+            .map(|x| MockSession {
+                user_name: x.info.user_name.clone(),
+                is_admin: x.info.is_admin,
+            });
+        match session {
+            Some(x) => Outcome::Success(x),
+            None => Outcome::Error((
+                Status::Unauthorized,
+                MockError::Unauthorized("The token is missing or invalid".into()),
+            )),
+        }
+    }
+}
+
+fn decode_b64(b64: &str) -> Result<Vec<u8>, MockError> {
+    STANDARD
+        .decode(b64)
+        .map_err(|_| MockError::BadRequest("The data is not valid base64".into()))
+}
+
+fn parse_id(sid: &str) -> Result<Uuid, MockError> {
+    Uuid::from_str(sid).map_err(|_| MockError::BadRequest("ID is not valid!".into()))
+}
+
+// ---- Users ----
+
+#[post("/users/auth", data = "<data>")]
+fn post_auth(
+    data: MockJson<UserForLoginApi>,
+    state: &State<Arc<MockState>>,
+) -> MockResult<AuthOutputDto> {
+    Ok(Json(
+        state.lock().login(&data.0.user_name, &data.0.password)?,
+    ))
+}
+
+#[get("/users")]
+fn get_users(s: MockSession, state: &State<Arc<MockState>>) -> MockResult<Vec<UserDto>> {
+    s.require_admin()?;
+    let data = state.lock();
+    let ret = data
+        .users
+        .keys()
+        .map(|x| data.user_dto(x))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Json(ret))
+}
+
+#[get("/users/id/<name>")]
+fn get_user(name: &str, s: MockSession, state: &State<Arc<MockState>>) -> MockResult<UserDto> {
+    s.require_self(name)?;
+    Ok(Json(state.lock().user_dto(name)?))
+}
+
+#[post("/users", data = "<data>")]
+fn post_user(
+    data: MockJson<UserForCreateApi>,
+    s: MockSession,
+    state: &State<Arc<MockState>>,
+) -> MockResult<UserDto> {
+    s.require_admin()?;
+    let dto = data.0;
+    if dto.user_name.trim().is_empty() || dto.password.is_empty() {
+        return Err(MockError::BadRequest(
+            "A user name and password are required".into(),
+        ));
+    }
+    let mut data = state.lock();
+    if data.users.contains_key(&dto.user_name) {
+        return Err(MockError::Conflict(format!(
+            "User '{}' already exists",
+            dto.user_name
+        )));
+    }
+    let mut user = MockUser::new(&dto.user_name, &dto.password, dto.is_admin);
+    user.info.first_name = dto.first_name;
+    user.info.last_name = dto.last_name;
+    user.info.max_data_bytes = dto.max_data_bytes.max(0) as usize;
+    user.info.max_repo_bytes = dto.max_snapshot_bytes.max(0) as usize;
+    data.users.insert(dto.user_name.clone(), user);
+    Ok(Json(data.user_dto(&dto.user_name)?))
+}
+
+#[put("/users/id/<name>", data = "<data>")]
+fn put_user(
+    name: &str,
+    data: MockJson<UserForUpdateApi>,
+    s: MockSession,
+    state: &State<Arc<MockState>>,
+) -> MockResult<UserDto> {
+    s.require_self(name)?;
+    let dto = data.0;
+    if dto.is_admin.is_some() {
+        s.require_admin()?;
+    }
+    let mut data = state.lock();
+    let user = data.user_mut(name)?;
+    if let Some(x) = dto.first_name {
+        user.info.first_name = x;
+    }
+    if let Some(x) = dto.last_name {
+        user.info.last_name = x;
+    }
+    if let Some(x) = dto.is_admin {
+        user.info.is_admin = x;
+    }
+    if let Some(x) = dto.max_data_bytes {
+        user.info.max_data_bytes = x.max(0) as usize;
+    }
+    if let Some(x) = dto.max_snapshot_bytes {
+        user.info.max_repo_bytes = x.max(0) as usize;
+    }
+    if let Some(x) = dto.password {
+        user.password = x;
+    }
+    Ok(Json(data.user_dto(name)?))
+}
+
+#[delete("/users/id/<name>")]
+fn delete_user(name: &str, s: MockSession, state: &State<Arc<MockState>>) -> Result<(), MockError> {
+    s.require_admin()?;
+    let mut data = state.lock();
+    data.user(name)?;
+    data.users.remove(name);
+    data.mounts.retain(|x| x.owner != name);
+    data.tokens.retain(|_, v| v != name);
+    Ok(())
+}
+
+#[put("/users/pass", data = "<data>")]
+fn put_pass(
+    data: MockJson<String>,
+    s: MockSession,
+    state: &State<Arc<MockState>>,
+) -> Result<(), MockError> {
+    if data.0.is_empty() {
+        return Err(MockError::BadRequest("The password cannot be empty".into()));
+    }
+    state.lock().user_mut(&s.user_name)?.password = data.0;
+    Ok(())
+}
+
+#[put("/users/smb", data = "<data>")]
+fn put_smb(
+    data: MockJson<String>,
+    s: MockSession,
+    state: &State<Arc<MockState>>,
+) -> Result<(), MockError> {
+    state.lock().user_mut(&s.user_name)?.smb_password = Some(data.0);
+    Ok(())
+}
+
+#[delete("/users/smb")]
+fn delete_smb(s: MockSession, state: &State<Arc<MockState>>) -> Result<(), MockError> {
+    state.lock().user_mut(&s.user_name)?.smb_password = None;
+    Ok(())
+}
+
+// ---- System ----
+
+#[get("/sys/info")]
+fn get_info(_s: MockSession, state: &State<Arc<MockState>>) -> MockResult<SystemSnapshotDto> {
+    let mut ret = state.lock().system.clone();
+    ret.timestamp = Utc::now().naive_utc();
+    Ok(Json(ret))
+}
+
+#[post("/sys/shutdown")]
+fn post_shutdown(s: MockSession, state: &State<Arc<MockState>>) -> Result<(), MockError> {
+    s.require_admin()?;
+    state.lock().shutdowns += 1;
+    Ok(())
+}
+
+#[post("/sys/restart")]
+fn post_restart(s: MockSession, state: &State<Arc<MockState>>) -> Result<(), MockError> {
+    s.require_admin()?;
+    state.lock().restarts += 1;
+    Ok(())
+}
+
+/// The server is safe to stop while no job is running.
+#[get("/sys/safe")]
+fn get_safe(_s: MockSession, state: &State<Arc<MockState>>) -> MockResult<bool> {
+    let data = state.lock();
+    Ok(Json(!data.jobs.iter().any(|x| {
+        matches!(x.job_status, JobStatus::NotStarted | JobStatus::Running)
+    })))
+}
+
+// ---- Points ----
+
+#[get("/mounts")]
+fn get_mounts(s: MockSession, state: &State<Arc<MockState>>) -> MockResult<Vec<MountDto>> {
+    let data = state.lock();
+    Ok(Json(
+        data.mounts
+            .iter()
+            .filter(|x| x.owner == s.user_name)
+            .map(|x| x.to_dto())
+            .collect(),
+    ))
+}
+
+#[put("/mounts/id/<name>", data = "<data>")]
+fn put_mount(
+    name: &str,
+    data: MockJson<PutForMountApi>,
+    s: MockSession,
+    state: &State<Arc<MockState>>,
+) -> MockResult<RepoJobDto> {
+    if name.is_empty() || name.starts_with(['.', '@']) || name.contains('/') {
+        return Err(MockError::BadRequest(format!(
+            "'{}' is not a valid point name",
+            name
+        )));
+    }
+    let mut m_data = state.lock();
+    if m_data.mount(&s.user_name, name).is_err() {
+        m_data.mounts.push(MockMount::new(&s.user_name, name));
+    }
+    let mount = m_data.mount_mut(&s.user_name, name)?;
+    mount.data_bytes = data.0.data_bytes;
+    mount.repo_bytes = data.0.repo_bytes;
+    Ok(Json(m_data.add_job(
+        &s.user_name,
+        name,
+        JobType::PointAdjust,
+        None,
+        vec![],
+    )))
+}
+
+#[delete("/mounts/id/<name>")]
+fn delete_mount(
+    name: &str,
+    s: MockSession,
+    state: &State<Arc<MockState>>,
+) -> MockResult<RepoJobDto> {
+    let mut data = state.lock();
+    data.mount(&s.user_name, name)?;
+    let job = data.add_job(&s.user_name, name, JobType::PointDelete, None, vec![]);
+    data.mounts
+        .retain(|x| !(x.owner == s.user_name && x.name == name));
+    Ok(Json(job))
+}
+
+#[post("/mounts/id/<name>/backup?<lock>&<dry_run>", data = "<data>")]
+fn post_backup(
+    name: &str,
+    lock: Option<bool>,
+    dry_run: Option<bool>,
+    data: Option<MockJson<PostForBackupApi>>,
+    s: MockSession,
+    state: &State<Arc<MockState>>,
+) -> MockResult<RepoJobDto> {
+    let dto = data.map(|x| x.0).unwrap_or_default();
+    let mut m_data = state.lock();
+    let mount = m_data.mount_mut(&s.user_name, name)?;
+    if dry_run.unwrap_or(dto.dry_run) {
+        let messages = mount
+            .files
+            .iter()
+            .filter(|(_, v)| !v.is_dir)
+            .map(|(k, _)| format!("+ {}", k))
+            .collect();
+        return Ok(Json(m_data.add_job(
+            &s.user_name,
+            name,
+            JobType::Backup,
+            None,
+            messages,
+        )));
+    }
+    let tags = PutForSnapshotApi::clean_tags(dto.tags.unwrap_or_default());
+    let id = mount.take_snapshot(dto.label, dto.description, tags, lock.unwrap_or(false));
+    Ok(Json(m_data.add_job(
+        &s.user_name,
+        name,
+        JobType::Backup,
+        Some(id),
+        vec![],
+    )))
+}
+
+#[post("/mounts/id/<name>/check")]
+fn post_check(name: &str, s: MockSession, state: &State<Arc<MockState>>) -> MockResult<RepoJobDto> {
+    let mut data = state.lock();
+    data.mount(&s.user_name, name)?;
+    Ok(Json(data.add_job(
+        &s.user_name,
+        name,
+        JobType::Check,
+        None,
+        vec!["no errors were found".into()],
+    )))
+}
+
+#[post("/mounts/id/<name>/prune", data = "<data>")]
+fn post_prune(
+    name: &str,
+    data: MockJson<RetentionPolicy>,
+    s: MockSession,
+    state: &State<Arc<MockState>>,
+) -> MockResult<RepoJobDto> {
+    let policy = data.0;
+    policy
+        .validate()
+        .map_err(|e| MockError::BadRequest(e.to_string()))?;
+    let mut m_data = state.lock();
+    let mount = m_data.mount_mut(&s.user_name, name)?;
+    let infos = mount
+        .snapshots
+        .iter()
+        .map(|x| x.info.clone())
+        .collect::<Vec<_>>();
+    let plan = policy.apply(&infos);
+    mount
+        .snapshots
+        .retain(|x| !plan.remove.iter().any(|r| r.id == x.info.id));
+    let messages = plan.remove.iter().map(|x| format!("- {}", x.id)).collect();
+    Ok(Json(m_data.add_job(
+        &s.user_name,
+        name,
+        JobType::Prune,
+        None,
+        messages,
+    )))
+}
+
+#[get("/mounts/id/<name>/snapshots")]
+fn get_snapshots(
+    name: &str,
+    s: MockSession,
+    state: &State<Arc<MockState>>,
+) -> MockResult<Vec<SnapshotFileDto>> {
+    let data = state.lock();
+    let mount = data.mount(&s.user_name, name)?;
+    Ok(Json(
+        mount.snapshots.iter().map(|x| x.info.clone()).collect(),
+    ))
+}
+
+#[get("/mounts/id/<name>/snapshots/<id>")]
+fn get_snapshot(
+    name: &str,
+    id: &str,
+    s: MockSession,
+    state: &State<Arc<MockState>>,
+) -> MockResult<SnapshotFileDto> {
+    let data = state.lock();
+    Ok(Json(
+        data.mount(&s.user_name, name)?.snapshot(id)?.info.clone(),
+    ))
+}
+
+#[put("/mounts/id/<name>/snapshots/<id>", data = "<data>")]
+fn put_snapshot(
+    name: &str,
+    id: &str,
+    data: MockJson<PutForSnapshotApi>,
+    s: MockSession,
+    state: &State<Arc<MockState>>,
+) -> Result<(), MockError> {
+    let dto = data.0;
+    let mut m_data = state.lock();
+    let snapshot = m_data.mount_mut(&s.user_name, name)?.snapshot_mut(id)?;
+    if let Some(x) = dto.label {
+        snapshot.info.label = x;
+    }
+    if let Some(x) = dto.description {
+        snapshot.info.description = (!x.is_empty()).then_some(x);
+    }
+    if let Some(x) = dto.tags {
+        snapshot.info.tags = PutForSnapshotApi::clean_tags(x);
+    }
+    Ok(())
+}
+
+#[delete("/mounts/id/<name>/snapshots/<id>")]
+fn delete_snapshot(
+    name: &str,
+    id: &str,
+    s: MockSession,
+    state: &State<Arc<MockState>>,
+) -> Result<(), MockError> {
+    let mut data = state.lock();
+    let mount = data.mount_mut(&s.user_name, name)?;
+    if mount.snapshot(id)?.info.locked {
+        return Err(MockError::Conflict(format!("Snapshot '{}' is locked", id)));
+    }
+    mount.snapshots.retain(|x| x.info.id != id);
+    Ok(())
+}
+
+#[post("/mounts/id/<name>/snapshots/<id>/lock")]
+fn lock_snapshot(
+    name: &str,
+    id: &str,
+    s: MockSession,
+    state: &State<Arc<MockState>>,
+) -> Result<(), MockError> {
+    let mut data = state.lock();
+    data.mount_mut(&s.user_name, name)?
+        .snapshot_mut(id)?
+        .info
+        .locked = true;
+    Ok(())
+}
+
+#[delete("/mounts/id/<name>/snapshots/<id>/lock")]
+fn unlock_snapshot(
+    name: &str,
+    id: &str,
+    s: MockSession,
+    state: &State<Arc<MockState>>,
+) -> Result<(), MockError> {
+    let mut data = state.lock();
+    data.mount_mut(&s.user_name, name)?
+        .snapshot_mut(id)?
+        .info
+        .locked = false;
+    Ok(())
+}
+
+/// Restores a snapshot into its point, or the target of the request. The job messages list
+/// every change as `+`, `M` or `-` followed by the path, and a dry-run changes nothing.
+#[post("/mounts/id/<name>/snapshots/<id>/restore?<dry_run>", data = "<data>")]
+fn post_restore(
+    name: &str,
+    id: &str,
+    dry_run: Option<bool>,
+    data: Option<MockJson<PostForRestoreApi>>,
+    s: MockSession,
+    state: &State<Arc<MockState>>,
+) -> MockResult<RepoJobDto> {
+    let dto = data.map(|x| x.0).unwrap_or_default();
+    let dry_run = dry_run.unwrap_or(dto.dry_run);
+    let target_user = dto.target_user.unwrap_or(s.user_name.clone());
+    let target_name = dto.target_name.unwrap_or(name.to_string());
+    s.require_self(&target_user)?;
+
+    let paths = dto.paths.unwrap_or_default();
+    let in_scope = |p: &str| paths.is_empty() || paths.iter().any(|x| is_below(p, x));
+
+    let mut m_data = state.lock();
+    let files = m_data
+        .mount(&s.user_name, name)?
+        .snapshot(id)?
+        .files
+        .clone();
+    let target = m_data.mount_mut(&target_user, &target_name)?;
+
+    let mut messages = vec![];
+    for (path, node) in files.iter().filter(|(k, _)| in_scope(k)) {
+        match target.files.get(path) {
+            None => messages.push(format!("+ {}", path)),
+            Some(x) if x.is_dir != node.is_dir || x.data != node.data => {
+                messages.push(format!("M {}", path))
+            }
+            _ => {}
+        }
+    }
+    for path in target.files.keys() {
+        if in_scope(path) && !files.contains_key(path) {
+            messages.push(format!("- {}", path));
+        }
+    }
+    if !dry_run {
+        target
+            .files
+            .retain(|k, _| !in_scope(k) || files.contains_key(k));
+        for (path, node) in files.into_iter().filter(|(k, _)| in_scope(k)) {
+            target.files.insert(path, node);
+        }
+    }
+    Ok(Json(m_data.add_job(
+        &target_user,
+        &target_name,
+        JobType::Restore,
+        Some(id.to_string()),
+        messages,
+    )))
+}
+
+#[get("/mounts/id/<name>/jobs")]
+fn get_mount_jobs(
+    name: &str,
+    s: MockSession,
+    state: &State<Arc<MockState>>,
+) -> MockResult<Vec<RepoJobDto>> {
+    let data = state.lock();
+    data.mount(&s.user_name, name)?;
+    Ok(Json(
+        data.jobs
+            .iter()
+            .rev()
+            .filter(|x| x.point_owned_by == s.user_name && x.point_name == name)
+            .cloned()
+            .collect(),
+    ))
+}
+
+/// The server-side job filters, as sent by [`JobFilter::to_query`].
+#[derive(FromForm)]
+struct JobQuery {
+    point: Option<String>,
+    status: Option<String>,
+    #[field(name = "type")]
+    job_type: Option<String>,
+    start: Option<String>,
+    end: Option<String>,
+}
+
+impl JobQuery {
+    fn to_filter(&self) -> Result<JobFilter, MockError> {
+        fn parse_date(x: &Option<String>) -> Result<Option<DateTime<Utc>>, MockError> {
+            x.as_deref()
+                .map(|x| {
+                    DateTime::parse_from_rfc3339(x)
+                        .map(|x| x.to_utc())
+                        .map_err(|_| MockError::BadRequest(format!("'{}' is not a valid date", x)))
+                })
+                .transpose()
+        }
+        let bad = |x: &str| MockError::BadRequest(format!("'{}' is not a valid filter", x));
+        Ok(JobFilter {
+            point: self.point.clone(),
+            status: self
+                .status
+                .as_deref()
+                .map(|x| JobStatus::from_str(x).map_err(|_| bad(x)))
+                .transpose()?,
+            job_type: self
+                .job_type
+                .as_deref()
+                .map(|x| JobType::from_str(x).map_err(|_| bad(x)))
+                .transpose()?,
+            start: parse_date(&self.start)?,
+            end: parse_date(&self.end)?,
+            search: None,
+        })
+    }
+}
+
+#[get("/mounts/jobs?<n>&<o>&<filter..>")]
+fn get_jobs(
+    n: Option<usize>,
+    o: Option<usize>,
+    filter: JobQuery,
+    s: MockSession,
+    state: &State<Arc<MockState>>,
+) -> MockResult<Vec<RepoJobDto>> {
+    let filter = filter.to_filter()?;
+    let data = state.lock();
+    Ok(Json(
+        data.jobs
+            .iter()
+            .rev()
+            .filter(|x| x.point_owned_by == s.user_name && filter.matches(x))
+            .skip(o.unwrap_or(0))
+            .take(n.unwrap_or(usize::MAX))
+            .cloned()
+            .collect(),
+    ))
+}
+
+#[get("/mounts/jobs/<id>")]
+fn get_job(id: &str, s: MockSession, state: &State<Arc<MockState>>) -> MockResult<RepoJobDto> {
+    let id = parse_id(id)?;
+    let data = state.lock();
+    data.jobs
+        .iter()
+        .find(|x| x.id == id && x.point_owned_by == s.user_name)
+        .cloned()
+        .map(Json)
+        .ok_or(MockError::NotFound(format!("Job '{}' does not exist", id)))
+}
+
+#[get("/mounts/id/<name>/autojobs")]
+fn get_auto_jobs(
+    name: &str,
+    s: MockSession,
+    state: &State<Arc<MockState>>,
+) -> MockResult<Vec<AutoJobDto>> {
+    let data = state.lock();
+    Ok(Json(data.mount(&s.user_name, name)?.auto_jobs.clone()))
+}
+
+#[put("/mounts/id/<name>/autojobs", data = "<data>")]
+fn put_auto_job(
+    name: &str,
+    data: MockJson<PutForAutoJobWebApi>,
+    s: MockSession,
+    state: &State<Arc<MockState>>,
+) -> MockResult<AutoJobDto> {
+    let dto = data.0;
+    let now = Utc::now().naive_utc();
+    let mut m_data = state.lock();
+    let mount = m_data.mount_mut(&s.user_name, name)?;
+    let job = match mount
+        .auto_jobs
+        .iter_mut()
+        .find(|x| x.task_name == dto.task_name)
+    {
+        Some(x) => x,
+        None => {
+            mount.auto_jobs.push(AutoJobDto {
+                task_name: dto.task_name.clone(),
+                date_created: now,
+                ..Default::default()
+            });
+            mount.auto_jobs.last_mut().unwrap()
+        }
+    };
+    job.cron_schedule = dto.cron_schedule;
+    job.enabled = dto.enabled;
+    job.job_type = dto.job_type;
+    job.retention = dto.retention;
+    job.date_modified = now;
+    Ok(Json(job.clone()))
+}
+
+#[delete("/mounts/id/<name>/autojobs", data = "<data>")]
+fn delete_auto_job(
+    name: &str,
+    data: MockJson<String>,
+    s: MockSession,
+    state: &State<Arc<MockState>>,
+) -> Result<(), MockError> {
+    let mut m_data = state.lock();
+    let mount = m_data.mount_mut(&s.user_name, name)?;
+    let len = mount.auto_jobs.len();
+    mount.auto_jobs.retain(|x| x.task_name != data.0);
+    if mount.auto_jobs.len() == len {
+        return Err(MockError::NotFound(format!(
+            "Auto job '{}' does not exist",
+            data.0
+        )));
+    }
+    Ok(())
+}
+
+// ---- Files ----
+
+/// Lists a directory. The root lists the points of the user.
+#[post("/mounts/browse", data = "<data>")]
+fn browse(
+    data: MockJson<String>,
+    s: MockSession,
+    state: &State<Arc<MockState>>,
+) -> MockResult<Vec<NodeDto>> {
+    let m_data = state.lock();
+    let (name, rel) = split_point(&data.0);
+    if name.is_empty() {
+        return Ok(Json(
+            m_data
+                .mounts
+                .iter()
+                .filter(|x| x.owner == s.user_name)
+                .map(|x| NodeDto {
+                    path: format!("/{}", x.name),
+                    ..MockNode::dir().to_node("")
+                })
+                .collect(),
+        ));
+    }
+    let mount = m_data.mount(&s.user_name, &name)?;
+    if !rel.is_empty() && !mount.files.get(&rel).is_some_and(|x| x.is_dir) {
+        return Err(MockError::NotFound(format!(
+            "Directory '{}' does not exist",
+            data.0
+        )));
+    }
+    Ok(Json(
+        tree_children(&mount.files, &rel)
+            .map(|(k, v)| v.to_node(&format!("/{}{}", name, k)))
+            .collect(),
+    ))
+}
+
+#[get("/mounts/dump?<o>&<n>", data = "<data>")]
+fn dump(
+    o: Option<u64>,
+    n: Option<u64>,
+    data: MockJson<String>,
+    s: MockSession,
+    state: &State<Arc<MockState>>,
+) -> Result<String, MockError> {
+    let m_data = state.lock();
+    let (name, rel) = split_point(&data.0);
+    let node = m_data
+        .mount(&s.user_name, &name)?
+        .files
+        .get(&rel)
+        .filter(|x| !x.is_dir)
+        .ok_or(MockError::NotFound(format!(
+            "File '{}' does not exist",
+            data.0
+        )))?;
+    let start = (o.unwrap_or(0) as usize).min(node.data.len());
+    let end = start
+        .saturating_add(n.unwrap_or(u64::MAX).min(usize::MAX as u64) as usize)
+        .min(node.data.len());
+    Ok(STANDARD.encode(&node.data[start..end]))
+}
+
+/// Writes, truncates, touches and then renames a file, in that order.
+#[put("/mounts/file", data = "<data>")]
+fn put_file(
+    data: MockJson<PutForFileApi>,
+    s: MockSession,
+    state: &State<Arc<MockState>>,
+) -> Result<(), MockError> {
+    let dto = data.0;
+    let mut m_data = state.lock();
+    let (name, rel) = split_point(&dto.path);
+    let mount = m_data.mount_mut(&s.user_name, &name)?;
+    let node = mount
+        .files
+        .get_mut(&rel)
+        .ok_or(MockError::NotFound(format!(
+            "'{}' does not exist",
+            dto.path
+        )))?;
+    if let Some(ref b64) = dto.base64 {
+        node.write_at(dto.offset.unwrap_or(0), &decode_b64(b64)?);
+    }
+    if let Some(t_len) = dto.t_len {
+        node.data.resize(t_len as usize, 0);
+        node.mtime = Utc::now().naive_utc();
+    }
+    if let Some(x) = dto.atime {
+        node.atime = x;
+    }
+    if let Some(x) = dto.mtime {
+        node.mtime = x;
+    }
+    if let Some(ref new_path) = dto.new_path {
+        let (new_name, new_rel) = split_point(new_path);
+        if new_name != name || new_rel.is_empty() {
+            return Err(MockError::BadRequest(
+                "Files can only be moved within a point".into(),
+            ));
+        }
+        mount.rename(&rel, &new_rel);
+    }
+    Ok(())
+}
+
+#[post("/mounts/file", data = "<data>")]
+fn post_file(
+    data: MockJson<PostForFileApi>,
+    s: MockSession,
+    state: &State<Arc<MockState>>,
+) -> Result<(), MockError> {
+    let dto = data.0;
+    let mut m_data = state.lock();
+    let (name, rel) = split_point(&dto.path);
+    let mount = m_data.mount_mut(&s.user_name, &name)?;
+    if rel.is_empty() || mount.files.contains_key(&rel) {
+        return Err(MockError::Conflict(format!(
+            "'{}' already exists",
+            dto.path
+        )));
+    }
+    let parent = rel.rsplit_once('/').map(|x| x.0).unwrap_or_default();
+    if !parent.is_empty() && !mount.files.get(parent).is_some_and(|x| x.is_dir) {
+        return Err(MockError::NotFound(format!(
+            "The parent of '{}' does not exist",
+            dto.path
+        )));
+    }
+    let mut node = if dto.is_dir {
+        MockNode::dir()
+    } else {
+        MockNode::file(vec![])
+    };
+    if let Some(ref b64) = dto.base64 {
+        node.write_at(dto.offset.unwrap_or(0), &decode_b64(b64)?);
+    }
+    mount.files.insert(rel, node);
+    Ok(())
+}
+
+#[delete("/mounts/file", data = "<data>")]
+fn delete_file(
+    data: MockJson<String>,
+    s: MockSession,
+    state: &State<Arc<MockState>>,
+) -> Result<(), MockError> {
+    let mut m_data = state.lock();
+    let (name, rel) = split_point(&data.0);
+    let mount = m_data.mount_mut(&s.user_name, &name)?;
+    if rel.is_empty() || !mount.files.contains_key(&rel) {
+        return Err(MockError::NotFound(format!("'{}' does not exist", data.0)));
+    }
+    mount.files.retain(|k, _| !is_below(k, &rel));
+    Ok(())
+}
+
+/// Splits a repository browser path like `/<user>/<point>/<snapshot>/<rest>`.
+fn split_repo_path(path: &str) -> Result<(String, String, String, String), MockError> {
+    let mut parts = path.trim_matches('/').splitn(4, '/');
+    let (Some(user), Some(point), Some(snapshot)) = (parts.next(), parts.next(), parts.next())
+    else {
+        return Err(MockError::BadRequest(format!(
+            "'{}' is not a snapshot path",
+            path
+        )));
+    };
+    let rest = parts.next().unwrap_or_default().trim_end_matches('/');
+    let rel = if rest.is_empty() {
+        String::new()
+    } else {
+        format!("/{}", rest)
+    };
+    Ok((user.into(), point.into(), snapshot.into(), rel))
+}
+
+/// Lists a snapshot directory, including the directory itself, down to `depth` levels.
+#[get("/repos/files", data = "<data>")]
+fn get_repo_files(
+    data: MockJson<DataPointBrowseGetDto>,
+    s: MockSession,
+    state: &State<Arc<MockState>>,
+) -> MockResult<Vec<FileDto>> {
+    let path = data.0.path.flatten().unwrap_or_default();
+    let depth = data.0.depth.unwrap_or(1).max(0) as usize;
+    let (user, point, snapshot, rel) = split_repo_path(&path)?;
+    s.require_self(&user)?;
+    let m_data = state.lock();
+    let files = &m_data.mount(&user, &point)?.snapshot(&snapshot)?.files;
+    let root = format!("/{}/{}/{}", user, point, snapshot);
+    let this = match files.get(&rel) {
+        Some(x) if x.is_dir => x.clone(),
+        None if rel.is_empty() => MockNode::dir(),
+        _ => {
+            return Err(MockError::NotFound(format!(
+                "Directory '{}' does not exist",
+                path
+            )));
+        }
+    };
+    let mut ret = vec![this.to_file(&format!("{}{}", root, rel))];
+    ret.extend(
+        files
+            .iter()
+            .filter(|(k, _)| *k != &rel && is_below(k, &rel))
+            .filter(|(k, _)| k[rel.len()..].matches('/').count() <= depth)
+            .map(|(k, v)| v.to_file(&format!("{}{}", root, k))),
+    );
+    Ok(Json(ret))
+}
+
+#[get("/repos/dump", data = "<data>")]
+fn get_repo_dump(
+    data: MockJson<String>,
+    s: MockSession,
+    state: &State<Arc<MockState>>,
+) -> MockResult<FileOutputDto> {
+    let (user, point, snapshot, rel) = split_repo_path(&data.0)?;
+    s.require_self(&user)?;
+    let m_data = state.lock();
+    let node = m_data
+        .mount(&user, &point)?
+        .snapshot(&snapshot)?
+        .files
+        .get(&rel)
+        .ok_or(MockError::NotFound(format!("'{}' does not exist", data.0)))?;
+    Ok(Json(FileOutputDto {
+        path: Some(Some(data.0.clone())),
+        data: (!node.is_dir).then(|| Some(STANDARD.encode(&node.data))),
+        is_gzip: Some(false),
+        is_base64: Some(true),
+        is_directory: Some(node.is_dir),
+    }))
+}
+
+// ---- Data points ----
+
+/// Splits a data API path like `/<user>/<point>/<rest>` into the owner, the point name and
+/// the path relative to the point, which is empty for the point itself.
+fn split_data_path(path: &str) -> Result<(String, String, String), MockError> {
+    let Some((user, rest)) = path.trim_matches('/').split_once('/') else {
+        return Err(MockError::BadRequest(format!(
+            "'{}' is not a data point path",
+            path
+        )));
+    };
+    let (point, rel) = split_point(rest);
+    Ok((user.into(), point, rel))
+}
+
+/// Returns the points the user owns, and those which have been shared with them.
+#[get("/datas")]
+fn get_datas(s: MockSession, state: &State<Arc<MockState>>) -> MockResult<Vec<DataPointDto>> {
+    let data = state.lock();
+    Ok(Json(
+        data.mounts
+            .iter()
+            .filter(|x| x.can_access(&s.user_name, false))
+            .map(|x| x.to_data_point())
+            .collect(),
+    ))
+}
+
+/// Lists the shares of a point, or only the one of `user`. A user the point has been shared
+/// with only sees their own share.
+#[get("/datas/<owner>/<name>/shares?<user>")]
+fn get_shares(
+    owner: &str,
+    name: &str,
+    user: Option<&str>,
+    s: MockSession,
+    state: &State<Arc<MockState>>,
+) -> MockResult<Vec<DataPointShareDto>> {
+    let data = state.lock();
+    let mount = if s.is_admin {
+        data.mount(owner, name)?
+    } else {
+        data.shared_mount(&s.user_name, owner, name, false)?
+    };
+    let user = if s.is_admin || mount.owner == s.user_name {
+        user
+    } else {
+        Some(s.user_name.as_str())
+    };
+    Ok(Json(
+        mount
+            .shares
+            .iter()
+            .filter(|x| {
+                user.is_none_or(|u| x.user_name.as_ref().and_then(|x| x.as_deref()) == Some(u))
+            })
+            .cloned()
+            .collect(),
+    ))
+}
+
+/// Shares a point with a user, or changes what they can do with it.
+#[put("/datas/<owner>/<name>/shares", data = "<data>")]
+fn put_share(
+    owner: &str,
+    name: &str,
+    data: MockJson<DataPointShareDto>,
+    s: MockSession,
+    state: &State<Arc<MockState>>,
+) -> MockResult<DataPointShareDto> {
+    s.require_self(owner)?;
+    let dto = data.0;
+    let mut m_data = state.lock();
+    let user = dto
+        .user_name
+        .clone()
+        .flatten()
+        .ok_or(MockError::BadRequest("The share has no user".into()))?;
+    m_data.user(&user)?;
+    let mount = m_data.mount_mut(owner, name)?;
+    mount
+        .shares
+        .retain(|x| x.user_name.as_ref().and_then(|x| x.as_deref()) != Some(user.as_str()));
+    mount.shares.push(dto.clone());
+    Ok(Json(dto))
+}
+
+#[delete("/datas/<owner>/<name>/shares?<user>")]
+fn delete_share(
+    owner: &str,
+    name: &str,
+    user: &str,
+    s: MockSession,
+    state: &State<Arc<MockState>>,
+) -> Result<(), MockError> {
+    s.require_self(owner)?;
+    let mut data = state.lock();
+    let mount = data.mount_mut(owner, name)?;
+    if mount.share(user).is_none() {
+        return Err(MockError::NotFound(format!(
+            "Point '{}' is not shared with '{}'",
+            name, user
+        )));
+    }
+    mount
+        .shares
+        .retain(|x| x.user_name.as_ref().and_then(|x| x.as_deref()) != Some(user));
+    Ok(())
+}
+
+/// Lists a directory of a point, including the directory itself, down to `depth` levels.
+#[get("/datas/files", data = "<data>")]
+fn get_data_files(
+    data: MockJson<DataPointBrowseGetDto>,
+    s: MockSession,
+    state: &State<Arc<MockState>>,
+) -> MockResult<Vec<FileDto>> {
+    let path = data.0.path.flatten().unwrap_or_default();
+    let depth = data.0.depth.unwrap_or(1).max(0) as usize;
+    let (user, point, rel) = split_data_path(&path)?;
+    let m_data = state.lock();
+    let files = &m_data
+        .shared_mount(&s.user_name, &user, &point, false)?
+        .files;
+    let root = format!("/{}/{}", user, point);
+    let this = match files.get(&rel) {
+        Some(x) if x.is_dir => x.clone(),
+        None if rel.is_empty() => MockNode::dir(),
+        _ => {
+            return Err(MockError::NotFound(format!(
+                "Directory '{}' does not exist",
+                path
+            )));
+        }
+    };
+    let mut ret = vec![this.to_file(&format!("{}{}", root, rel))];
+    ret.extend(
+        files
+            .iter()
+            .filter(|(k, _)| *k != &rel && is_below(k, &rel))
+            .filter(|(k, _)| k[rel.len()..].matches('/').count() <= depth)
+            .map(|(k, v)| v.to_file(&format!("{}{}", root, k))),
+    );
+    Ok(Json(ret))
+}
+
+#[get("/datas/dump", data = "<data>")]
+fn get_data_dump(
+    data: MockJson<String>,
+    s: MockSession,
+    state: &State<Arc<MockState>>,
+) -> MockResult<FileOutputDto> {
+    let (user, point, rel) = split_data_path(&data.0)?;
+    let m_data = state.lock();
+    let node = m_data
+        .shared_mount(&s.user_name, &user, &point, false)?
+        .files
+        .get(&rel)
+        .ok_or(MockError::NotFound(format!("'{}' does not exist", data.0)))?;
+    Ok(Json(FileOutputDto {
+        path: Some(Some(data.0.clone())),
+        data: (!node.is_dir).then(|| Some(STANDARD.encode(&node.data))),
+        is_gzip: Some(false),
+        is_base64: Some(true),
+        is_directory: Some(node.is_dir),
+    }))
+}
+
+/// Creates or replaces a whole file or directory, and then renames it if asked to.
+#[put("/datas/files", data = "<data>")]
+fn put_data_file(
+    data: MockJson<FilePutDto>,
+    s: MockSession,
+    state: &State<Arc<MockState>>,
+) -> Result<(), MockError> {
+    let dto = data.0;
+    let path = dto.path.flatten().unwrap_or_default();
+    let (user, point, rel) = split_data_path(&path)?;
+    if rel.is_empty() {
+        return Err(MockError::BadRequest(
+            "The point itself cannot be changed".into(),
+        ));
+    }
+    let mut m_data = state.lock();
+    let mount = m_data.shared_mount_mut(&s.user_name, &user, &point, true)?;
+    if dto.is_directory == Some(true) {
+        mount.files.entry(rel.clone()).or_insert_with(MockNode::dir);
+    } else if let Some(b64) = dto.base64_content.flatten() {
+        let data = decode_b64(&b64)?;
+        let node = mount
+            .files
+            .entry(rel.clone())
+            .or_insert_with(|| MockNode::file(vec![]));
+        node.data = data;
+        node.mtime = Utc::now().naive_utc();
+    }
+    if let Some(new_path) = dto.new_path.flatten() {
+        let (new_user, new_point, new_rel) = split_data_path(&new_path)?;
+        if new_user != user || new_point != point || new_rel.is_empty() {
+            return Err(MockError::BadRequest(
+                "Files can only be moved within a point".into(),
+            ));
+        }
+        if !mount.files.contains_key(&rel) {
+            return Err(MockError::NotFound(format!("'{}' does not exist", path)));
+        }
+        mount.rename(&rel, &new_rel);
+    }
+    Ok(())
+}
+
+#[delete("/datas/files", data = "<data>")]
+fn delete_data_file(
+    data: MockJson<String>,
+    s: MockSession,
+    state: &State<Arc<MockState>>,
+) -> Result<(), MockError> {
+    let (user, point, rel) = split_data_path(&data.0)?;
+    let mut m_data = state.lock();
+    let mount = m_data.shared_mount_mut(&s.user_name, &user, &point, true)?;
+    if rel.is_empty() || !mount.files.contains_key(&rel) {
+        return Err(MockError::NotFound(format!("'{}' does not exist", data.0)));
+    }
+    mount.files.retain(|k, _| !is_below(k, &rel));
+    Ok(())
+}
+
+// ---- Subscriptions ----
+
+#[get("/subscriptions")]
+fn get_subscriptions(
+    s: MockSession,
+    state: &State<Arc<MockState>>,
+) -> MockResult<Vec<SubscriptionDto>> {
+    Ok(Json(state.lock().user(&s.user_name)?.subscriptions.clone()))
+}
+
+#[get("/subscriptions/<sid>")]
+fn get_subscription(
+    sid: &str,
+    s: MockSession,
+    state: &State<Arc<MockState>>,
+) -> MockResult<SubscriptionDto> {
+    let id = parse_id(sid)?;
+    state
+        .lock()
+        .user(&s.user_name)?
+        .subscriptions
+        .iter()
+        .find(|x| x.id == id)
+        .cloned()
+        .map(Json)
+        .ok_or(MockError::NotFound(format!(
+            "Subscription '{}' does not exist",
+            sid
+        )))
+}
+
+#[post("/subscriptions", data = "<data>")]
+fn post_subscription(
+    data: MockJson<PostForSubscriptionApi>,
+    s: MockSession,
+    state: &State<Arc<MockState>>,
+) -> Result<(), MockError> {
+    let dto = data.0;
+    state
+        .lock()
+        .user_mut(&s.user_name)?
+        .subscriptions
+        .push(SubscriptionDto {
+            id: Uuid::new_v4(),
+            mode: dto.mode,
+            endpoint: dto.endpoint,
+            triggers: dto.triggers,
+            enabled: dto.enabled,
+        });
+    Ok(())
+}
+
+#[put("/subscriptions/<sid>", data = "<data>")]
+fn put_subscription(
+    sid: &str,
+    data: MockJson<PutForSubscriptionApi>,
+    s: MockSession,
+    state: &State<Arc<MockState>>,
+) -> MockResult<SubscriptionDto> {
+    let id = parse_id(sid)?;
+    let dto = data.0;
+    let mut m_data = state.lock();
+    let sub = m_data
+        .user_mut(&s.user_name)?
+        .subscriptions
+        .iter_mut()
+        .find(|x| x.id == id)
+        .ok_or(MockError::NotFound(format!(
+            "Subscription '{}' does not exist",
+            sid
+        )))?;
+    if let Some(x) = dto.mode {
+        sub.mode = x;
+    }
+    if let Some(x) = dto.endpoint {
+        sub.endpoint = x;
+    }
+    if let Some(x) = dto.triggers {
+        sub.triggers = x;
+    }
+    if let Some(x) = dto.enabled {
+        sub.enabled = x;
+    }
+    Ok(Json(sub.clone()))
+}
+
+#[delete("/subscriptions/<sid>")]
+fn delete_subscription(
+    sid: &str,
+    s: MockSession,
+    state: &State<Arc<MockState>>,
+) -> Result<(), MockError> {
+    let id = parse_id(sid)?;
+    let mut data = state.lock();
+    let subs = &mut data.user_mut(&s.user_name)?.subscriptions;
+    let len = subs.len();
+    subs.retain(|x| x.id != id);
+    if subs.len() == len {
+        return Err(MockError::NotFound(format!(
+            "Subscription '{}' does not exist",
+            sid
+        )));
+    }
+    Ok(())
+}
+
+// ---- Messages ----
+
+/// Returns the messages sent to everyone or to the user, optionally only unread ones.
+#[get("/messages?<new>")]
+fn get_messages(
+    new: Option<bool>,
+    s: MockSession,
+    state: &State<Arc<MockState>>,
+) -> MockResult<Vec<Message>> {
+    let data = state.lock();
+    Ok(Json(
+        data.messages
+            .iter()
+            .filter(|x| x.sent_to.as_ref().is_none_or(|t| *t == s.user_name))
+            .filter(|x| !new.unwrap_or(false) || !x.read_by.contains(&s.user_name))
+            .cloned()
+            .collect(),
+    ))
+}
+
+/// Returns one message and marks it as read by the user.
+#[get("/messages/<sid>")]
+fn get_message(sid: &str, s: MockSession, state: &State<Arc<MockState>>) -> MockResult<Message> {
+    let id = parse_id(sid)?;
+    let mut data = state.lock();
+    let msg = data
+        .messages
+        .iter_mut()
+        .find(|x| x.id == id && x.sent_to.as_ref().is_none_or(|t| *t == s.user_name))
+        .ok_or(MockError::NotFound(format!(
+            "Message '{}' does not exist",
+            sid
+        )))?;
+    if !msg.read_by.contains(&s.user_name) {
+        msg.read_by.push(s.user_name.clone());
+    }
+    Ok(Json(msg.clone()))
+}
+
+#[post("/messages", data = "<data>")]
+fn post_message(
+    data: MockJson<PostForMessageApi>,
+    s: MockSession,
+    state: &State<Arc<MockState>>,
+) -> Result<(), MockError> {
+    let dto = data.0;
+    let mut m_data = state.lock();
+    if let Some(ref to) = dto.sent_to {
+        m_data.user(to)?;
+    }
+    m_data.messages.push(Message {
+        id: Uuid::new_v4(),
+        sent_from: s.user_name,
+        sent_to: dto.sent_to,
+        subject: None,
+        message: dto.message,
+        sent_date: Utc::now().naive_utc(),
+        read_by: vec![],
+        important: false,
+    });
+    Ok(())
+}
+
+// ---- Notifications ----
+
+#[derive(FromForm)]
+struct NotificationQuery {
+    #[field(name = "unreadOnly")]
+    unread_only: Option<bool>,
+}
+
+fn is_for(dto: &WsNotificationDto, user_name: &str) -> bool {
+    dto.send_to.as_ref().and_then(|x| x.as_deref()) == Some(user_name)
+}
+
+#[get("/notifications?<query..>")]
+fn get_notifications(
+    query: NotificationQuery,
+    s: MockSession,
+    state: &State<Arc<MockState>>,
+) -> MockResult<Vec<WsNotificationDto>> {
+    let data = state.lock();
+    Ok(Json(
+        data.notifications
+            .iter()
+            .filter(|x| is_for(x, &s.user_name))
+            .filter(|x| !query.unread_only.unwrap_or(false) || x.is_read != Some(true))
+            .cloned()
+            .collect(),
+    ))
+}
+
+/// Returns one notification and marks it as read.
+#[get("/notifications/<sid>")]
+fn get_notification(
+    sid: &str,
+    s: MockSession,
+    state: &State<Arc<MockState>>,
+) -> MockResult<WsNotificationDto> {
+    let id = parse_id(sid)?;
+    let mut data = state.lock();
+    let dto = data
+        .notifications
+        .iter_mut()
+        .find(|x| x.id == Some(id) && is_for(x, &s.user_name))
+        .ok_or(MockError::NotFound(format!(
+            "Notification '{}' does not exist",
+            sid
+        )))?;
+    dto.is_read = Some(true);
+    Ok(Json(dto.clone()))
+}
+
+#[delete("/notifications/<sid>")]
+fn delete_notification(
+    sid: &str,
+    s: MockSession,
+    state: &State<Arc<MockState>>,
+) -> Result<(), MockError> {
+    let id = parse_id(sid)?;
+    let mut data = state.lock();
+    let len = data.notifications.len();
+    data.notifications
+        .retain(|x| !(x.id == Some(id) && is_for(x, &s.user_name)));
+    if data.notifications.len() == len {
+        return Err(MockError::NotFound(format!(
+            "Notification '{}' does not exist",
+            sid
+        )));
+    }
+    Ok(())
+}
+
+pub fn get_routes() -> Vec<Route> {
+    routes![
+        post_auth,
+        get_users,
+        get_user,
+        post_user,
+        put_user,
+        delete_user,
+        put_pass,
+        put_smb,
+        delete_smb,
+        get_info,
+        post_shutdown,
+        post_restart,
+        get_safe,
+        get_mounts,
+        put_mount,
+        delete_mount,
+        post_backup,
+        post_check,
+        post_prune,
+        get_snapshots,
+        get_snapshot,
+        put_snapshot,
+        delete_snapshot,
+        lock_snapshot,
+        unlock_snapshot,
+        post_restore,
+        get_mount_jobs,
+        get_jobs,
+        get_job,
+        get_auto_jobs,
+        put_auto_job,
+        delete_auto_job,
+        browse,
+        dump,
+        put_file,
+        post_file,
+        delete_file,
+        get_repo_files,
+        get_repo_dump,
+        get_datas,
+        get_shares,
+        put_share,
+        delete_share,
+        get_data_files,
+        get_data_dump,
+        put_data_file,
+        delete_data_file,
+        get_subscriptions,
+        get_subscription,
+        post_subscription,
+        put_subscription,
+        delete_subscription,
+        get_messages,
+        get_message,
+        post_message,
+        get_notifications,
+        get_notification,
+        delete_notification
+    ]
+}
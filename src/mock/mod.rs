@@ -0,0 +1,232 @@
+//! An in-memory stand-in for the Neptis server, used to test the client, the FUSE layer and
+//! the GUI flows without a real server.
+//!
+//! The mock serves the `/mounts`, `/datas`, `/users`, `/sys`, `/subscriptions`, `/messages` and
+//! `/notifications` routes (plus the snapshot browser under `/repos`) from memory, and accepts
+//! the encrypted `/secure/` envelope when it is given a [`RollingSecret`]. Jobs finish as soon
+//! as they are started; tests can change them, or seed any other state, through
+//! [`MockState::lock`].
+
+pub mod envelope;
+pub mod errors;
+pub mod handlers;
+pub mod state;
+
+use std::net::{IpAddr, Ipv4Addr};
+use std::sync::Arc;
+
+use chrono::{TimeDelta, Utc};
+use rocket::config::LogLevel;
+use rocket::fairing::AdHoc;
+use rocket::http::Header;
+use rocket::{Config, Shutdown};
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+
+use self::envelope::SecureEnvelope;
+use self::state::{MockData, MockMount, MockNode, MockState, MockUser};
+use crate::apis::NeptisError;
+use crate::apis::api::WebApi;
+use crate::apis::capabilities::Feature;
+use crate::models::DataPointShareDto;
+use crate::rolling_secret::RollingSecret;
+
+/// Builds a mock server with some initial users and points.
+#[derive(Default)]
+pub struct MockServer {
+    port: u16,
+    secret: Option<RollingSecret>,
+    legacy_envelope: bool,
+    clock_offset: i64,
+    data: MockData,
+}
+
+impl MockServer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Listens on a fixed port instead of a random free one.
+    pub fn with_port(mut self, port: u16) -> Self {
+        self.port = port;
+        self
+    }
+
+    /// Requires every request to use the encrypted `/secure/` envelope with this secret.
+    pub fn with_secret(mut self, secret: RollingSecret) -> Self {
+        self.secret = Some(secret);
+        self
+    }
+
+    /// Only accepts the legacy CBC envelope, like servers which predate versioned envelopes.
+    pub fn with_legacy_envelope(mut self) -> Self {
+        self.legacy_envelope = true;
+        self
+    }
+
+    /// Runs the server clock this many seconds ahead of the real one (or behind, when
+    /// negative). It is used for the rolling key and the `Date` header of every response.
+    pub fn with_clock_offset(mut self, secs: i64) -> Self {
+        self.clock_offset = secs;
+        self
+    }
+
+    /// Reports this API version from `/sys/info` instead of the crate version.
+    pub fn with_api_version(mut self, version: &str) -> Self {
+        self.data.system.api_version = version.to_string();
+        self
+    }
+
+    /// Advertises an explicit feature list from `/sys/info`, as newer servers do.
+    pub fn with_features(mut self, features: &[Feature]) -> Self {
+        self.data.system.features = Some(features.iter().map(|x| x.key().to_string()).collect());
+        self
+    }
+
+    pub fn with_user(mut self, user_name: &str, password: &str, is_admin: bool) -> Self {
+        self.data.users.insert(
+            user_name.to_string(),
+            MockUser::new(user_name, password, is_admin),
+        );
+        self
+    }
+
+    pub fn with_mount(mut self, owner: &str, name: &str) -> Self {
+        self.data.mounts.push(MockMount::new(owner, name));
+        self
+    }
+
+    /// Shares a point added with [`MockServer::with_mount`] with another user, who can read
+    /// it and also write to it when `can_write` is set.
+    pub fn with_share(mut self, owner: &str, name: &str, user_name: &str, can_write: bool) -> Self {
+        if let Some(mount) = self
+            .data
+            .mounts
+            .iter_mut()
+            .find(|x| x.owner == owner && x.name == name)
+        {
+            mount.shares.push(DataPointShareDto {
+                user_name: Some(Some(user_name.to_string())),
+                can_read: Some(true),
+                can_write: Some(can_write),
+                ..DataPointShareDto::new()
+            });
+        }
+        self
+    }
+
+    /// Adds a file to a point added with [`MockServer::with_mount`], creating any missing
+    /// parent directories. The path is relative to the point.
+    pub fn with_file(mut self, owner: &str, name: &str, path: &str, data: &[u8]) -> Self {
+        if let Some(mount) = self
+            .data
+            .mounts
+            .iter_mut()
+            .find(|x| x.owner == owner && x.name == name)
+        {
+            let path = format!("/{}", path.trim_matches('/'));
+            let mut dir = path.rsplit_once('/').map(|x| x.0).unwrap_or_default();
+            while !dir.is_empty() {
+                mount
+                    .files
+                    .entry(dir.to_string())
+                    .or_insert_with(MockNode::dir);
+                dir = dir.rsplit_once('/').map(|x| x.0).unwrap_or_default();
+            }
+            mount.files.insert(path, MockNode::file(data));
+        }
+        self
+    }
+
+    /// Starts the server on the current runtime and waits until it accepts connections.
+    pub async fn spawn(self) -> Result<MockHandle, NeptisError> {
+        let mut config = Config::release_default();
+        config.port = self.port;
+        config.address = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
+        config.log_level = LogLevel::Off;
+        config.cli_colors = false;
+        config.shutdown.ctrlc = false;
+        #[cfg(unix)]
+        config.shutdown.signals.clear();
+
+        let state = Arc::new(MockState::new(self.secret, self.data));
+        let clock_offset = self.clock_offset;
+        let (tx, rx) = oneshot::channel();
+        let rocket = rocket::custom(&config)
+            .manage(state.clone())
+            .attach(SecureEnvelope {
+                state: state.clone(),
+                legacy_only: self.legacy_envelope,
+                clock_offset: self.clock_offset,
+            })
+            .register("/", errors::get_catchers())
+            .mount("/api", handlers::get_routes())
+            .mount("/secure", envelope::rejected_routes())
+            .attach(AdHoc::on_response("Mock Clock", move |_, res| {
+                let now = Utc::now() + TimeDelta::seconds(clock_offset);
+                let date = now.format("%a, %d %b %Y %H:%M:%S GMT").to_string();
+                Box::pin(async move {
+                    res.set_header(Header::new("Date", date));
+                })
+            }))
+            .attach(AdHoc::on_liftoff("Mock Ready", move |r| {
+                let port = r.config().port;
+                Box::pin(async move {
+                    let _ = tx.send(port);
+                })
+            }))
+            .ignite()
+            .await
+            .map_err(|e| NeptisError::Str(e.to_string()))?;
+
+        let shutdown = rocket.shutdown();
+        let task = tokio::spawn(async move {
+            let _ = rocket.launch().await;
+        });
+        let port = rx
+            .await
+            .map_err(|_| NeptisError::Str("The mock server failed to start!".into()))?;
+        Ok(MockHandle {
+            base_url: format!("http://127.0.0.1:{}/api", port),
+            state,
+            shutdown,
+            task,
+        })
+    }
+}
+
+/// A running mock server, which is stopped when dropped.
+pub struct MockHandle {
+    base_url: String,
+    state: Arc<MockState>,
+    shutdown: Shutdown,
+    task: JoinHandle<()>,
+}
+
+impl MockHandle {
+    /// Returns the API base URL, in the form expected by [`WebApi::new`].
+    pub fn base_url(&self) -> String {
+        self.base_url.clone()
+    }
+
+    pub fn state(&self) -> &Arc<MockState> {
+        &self.state
+    }
+
+    /// Returns a client for this server, using its secret if it has one.
+    pub fn api(&self, user_name: &str, password: &str) -> WebApi {
+        WebApi::new(self.base_url(), user_name, password, self.state.secret())
+    }
+
+    /// Stops the server and waits for it to finish.
+    pub async fn stop(mut self) {
+        self.shutdown.clone().notify();
+        let _ = (&mut self.task).await;
+    }
+}
+
+impl Drop for MockHandle {
+    fn drop(&mut self) {
+        self.shutdown.clone().notify();
+    }
+}
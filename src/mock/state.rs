@@ -0,0 +1,545 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Mutex, MutexGuard, RwLock};
+
+use chrono::{Local, NaiveDateTime, TimeDelta, Utc};
+use uuid::Uuid;
+
+use super::errors::MockError;
+use crate::apis::api::{AuthOutputDto, PointUsage, SystemSnapshotDto, UserDto, VGStatDto};
+use crate::apis::dtos::{
+    AutoJobDto, JobStatus, JobType, Message, MountDto, NodeDto, RepoJobDto, SnapshotFileDto,
+    SnapshotSummary, SubscriptionDto,
+};
+use crate::models::{DataPointDto, DataPointShareDto, FileDto, WsNotificationDto};
+use crate::rolling_secret::RollingSecret;
+
+/// How long a token handed out by the mock server stays valid.
+pub const TOKEN_LIFETIME: TimeDelta = TimeDelta::hours(1);
+
+/// A file or directory stored by the mock server.
+#[derive(Clone, Debug)]
+pub struct MockNode {
+    pub is_dir: bool,
+    pub data: Vec<u8>,
+    pub atime: NaiveDateTime,
+    pub ctime: NaiveDateTime,
+    pub mtime: NaiveDateTime,
+}
+
+impl MockNode {
+    pub fn dir() -> Self {
+        let now = Utc::now().naive_utc();
+        MockNode {
+            is_dir: true,
+            data: vec![],
+            atime: now,
+            ctime: now,
+            mtime: now,
+        }
+    }
+
+    pub fn file(data: impl Into<Vec<u8>>) -> Self {
+        MockNode {
+            is_dir: false,
+            data: data.into(),
+            ..Self::dir()
+        }
+    }
+
+    /// Writes `data` at `offset`, growing the file with zeros if needed.
+    pub fn write_at(&mut self, offset: u64, data: &[u8]) {
+        let start = offset as usize;
+        if self.data.len() < start + data.len() {
+            self.data.resize(start + data.len(), 0);
+        }
+        self.data[start..start + data.len()].copy_from_slice(data);
+        self.mtime = Utc::now().naive_utc();
+    }
+
+    pub fn to_node(&self, path: &str) -> NodeDto {
+        NodeDto {
+            path: path.to_string(),
+            atime: self.atime,
+            ctime: self.ctime,
+            mtime: self.mtime,
+            is_dir: self.is_dir,
+            bytes: self.data.len() as u64,
+        }
+    }
+
+    /// Converts the node into the form returned by the repository browser.
+    pub fn to_file(&self, path: &str) -> FileDto {
+        let name = path.rsplit('/').next().unwrap_or(path).to_string();
+        FileDto {
+            access_date: Some(self.atime.and_utc().to_rfc3339()),
+            modify_date: Some(self.mtime.and_utc().to_rfc3339()),
+            create_date: Some(self.ctime.and_utc().to_rfc3339()),
+            level: None,
+            size_bytes: Some(Some(self.data.len() as i64)),
+            name: Some(Some(name)),
+            path: Some(Some(path.to_string())),
+            is_directory: Some(self.is_dir),
+        }
+    }
+}
+
+/// The files of a point or snapshot, keyed by their path relative to its root. The root
+/// itself is not stored.
+pub type MockTree = BTreeMap<String, MockNode>;
+
+/// Returns the direct children of `dir` in a tree.
+pub fn tree_children<'a>(
+    tree: &'a MockTree,
+    dir: &'a str,
+) -> impl Iterator<Item = (&'a String, &'a MockNode)> + 'a {
+    let prefix = format!("{}/", dir.trim_end_matches('/'));
+    let len = prefix.len();
+    tree.range(prefix.clone()..)
+        .take_while(move |(k, _)| k.starts_with(&prefix))
+        .filter(move |(k, _)| !k[len..].contains('/'))
+}
+
+/// Splits a path like `/<point>/<rest>` into the point name and the path relative to it,
+/// which is empty for the point itself.
+pub fn split_point(path: &str) -> (String, String) {
+    let trimmed = path.trim_matches('/');
+    let (name, rest) = trimmed.split_once('/').unwrap_or((trimmed, ""));
+    let rest = rest.trim_end_matches('/');
+    let rel = if rest.is_empty() {
+        String::new()
+    } else {
+        format!("/{}", rest)
+    };
+    (name.to_string(), rel)
+}
+
+/// Returns true if `path` is `dir` itself or anything below it.
+pub fn is_below(path: &str, dir: &str) -> bool {
+    let dir = dir.trim_end_matches('/');
+    dir.is_empty() || path == dir || path.strip_prefix(dir).is_some_and(|x| x.starts_with('/'))
+}
+
+pub struct MockSnapshot {
+    pub info: SnapshotFileDto,
+    pub files: MockTree,
+}
+
+pub struct MockMount {
+    pub owner: String,
+    pub name: String,
+    pub data_bytes: i64,
+    pub repo_bytes: i64,
+    pub date_created: NaiveDateTime,
+    pub files: MockTree,
+    pub snapshots: Vec<MockSnapshot>,
+    pub auto_jobs: Vec<AutoJobDto>,
+    /// The users the point has been shared with, through the data API.
+    pub shares: Vec<DataPointShareDto>,
+}
+
+impl MockMount {
+    pub fn new(owner: &str, name: &str) -> Self {
+        MockMount {
+            owner: owner.to_string(),
+            name: name.to_string(),
+            data_bytes: 1024 * 1024 * 1024,
+            repo_bytes: 1024 * 1024 * 1024,
+            date_created: Utc::now().naive_utc(),
+            files: MockTree::new(),
+            snapshots: vec![],
+            auto_jobs: vec![],
+            shares: vec![],
+        }
+    }
+
+    /// Returns the share given to a user, if the point has been shared with them.
+    pub fn share(&self, user_name: &str) -> Option<&DataPointShareDto> {
+        self.shares
+            .iter()
+            .find(|x| x.user_name.as_ref().and_then(|x| x.as_deref()) == Some(user_name))
+    }
+
+    /// Returns true if the user owns the point, or it has been shared with them for reading
+    /// (or writing, when `write` is set).
+    pub fn can_access(&self, user_name: &str, write: bool) -> bool {
+        self.owner == user_name
+            || self
+                .share(user_name)
+                .is_some_and(|x| x.can_read == Some(true) && (!write || x.can_write == Some(true)))
+    }
+
+    pub fn usage(&self) -> PointUsage {
+        let data_used = self.files.values().map(|x| x.data.len()).sum::<usize>();
+        let repo_used = self
+            .snapshots
+            .iter()
+            .flat_map(|x| x.files.values())
+            .map(|x| x.data.len())
+            .sum::<usize>();
+        PointUsage {
+            b_data_total: self.data_bytes as usize,
+            b_data_used: data_used,
+            b_data_avail: (self.data_bytes as usize).saturating_sub(data_used),
+            b_repo_total: self.repo_bytes as usize,
+            b_repo_used: repo_used,
+            b_repo_avail: (self.repo_bytes as usize).saturating_sub(repo_used),
+        }
+    }
+
+    pub fn to_dto(&self) -> MountDto {
+        MountDto {
+            name: self.name.clone(),
+            owned_by: self.owner.clone(),
+            usage: self.usage(),
+            date_created: self.date_created,
+            data_accessed: self.date_created,
+            repo_accessed: self.date_created,
+        }
+    }
+
+    pub fn to_data_point(&self) -> DataPointDto {
+        let usage = self.usage();
+        DataPointDto {
+            max_bytes: Some(usage.b_data_total as i64),
+            used_bytes: Some(usage.b_data_used as i64),
+            free_bytes: Some(usage.b_data_avail as i64),
+            is_repository: Some(false),
+            ..DataPointDto::new(self.owner.clone(), self.name.clone())
+        }
+    }
+
+    pub fn snapshot(&self, id: &str) -> Result<&MockSnapshot, MockError> {
+        self.snapshots
+            .iter()
+            .find(|x| x.info.id == id)
+            .ok_or(MockError::NotFound(format!(
+                "Snapshot '{}' does not exist",
+                id
+            )))
+    }
+
+    pub fn snapshot_mut(&mut self, id: &str) -> Result<&mut MockSnapshot, MockError> {
+        self.snapshots
+            .iter_mut()
+            .find(|x| x.info.id == id)
+            .ok_or(MockError::NotFound(format!(
+                "Snapshot '{}' does not exist",
+                id
+            )))
+    }
+
+    /// Moves a file or directory, with everything below it, to another path of the point.
+    pub fn rename(&mut self, rel: &str, new_rel: &str) {
+        let moved = self
+            .files
+            .keys()
+            .filter(|x| is_below(x, rel))
+            .cloned()
+            .collect::<Vec<_>>();
+        for old in moved {
+            let node = self.files.remove(&old).unwrap();
+            self.files
+                .insert(format!("{}{}", new_rel, &old[rel.len()..]), node);
+        }
+    }
+
+    /// Copies the current files into a new snapshot and returns its ID.
+    pub fn take_snapshot(
+        &mut self,
+        label: Option<String>,
+        description: Option<String>,
+        tags: Vec<String>,
+        locked: bool,
+    ) -> String {
+        let id = Uuid::new_v4().simple().to_string();
+        let now = Local::now();
+        let summary = SnapshotSummary {
+            files_new: self.files.values().filter(|x| !x.is_dir).count() as u64,
+            total_files_processed: self.files.values().filter(|x| !x.is_dir).count() as u64,
+            total_bytes_processed: self.files.values().map(|x| x.data.len() as u64).sum(),
+            total_dirs_processed: self.files.values().filter(|x| x.is_dir).count() as u64,
+            command: "mock backup".into(),
+            backup_start: now,
+            backup_end: now,
+            ..Default::default()
+        };
+        self.snapshots.push(MockSnapshot {
+            info: SnapshotFileDto {
+                time: now.naive_utc(),
+                program_version: format!("neptis-mock {}", env!("CARGO_PKG_VERSION")),
+                parent: self.snapshots.last().map(|x| x.info.id.clone()),
+                tree: Uuid::new_v4().simple().to_string(),
+                label: label.unwrap_or_default(),
+                paths: vec!["/".into()],
+                tags,
+                original: None,
+                summary: Some(summary),
+                description,
+                id: id.clone(),
+                locked,
+            },
+            files: self.files.clone(),
+        });
+        id
+    }
+}
+
+pub struct MockUser {
+    pub info: UserDto,
+    pub password: String,
+    pub smb_password: Option<String>,
+    pub subscriptions: Vec<SubscriptionDto>,
+}
+
+impl MockUser {
+    pub fn new(user_name: &str, password: &str, is_admin: bool) -> Self {
+        MockUser {
+            info: UserDto {
+                user_name: user_name.to_string(),
+                first_name: user_name.to_string(),
+                last_name: String::new(),
+                create_date: Utc::now().naive_utc(),
+                is_admin,
+                max_data_bytes: usize::MAX,
+                max_repo_bytes: usize::MAX,
+                ..Default::default()
+            },
+            password: password.to_string(),
+            smb_password: None,
+            subscriptions: vec![],
+        }
+    }
+}
+
+/// Everything the mock server knows. Tests can seed or inspect it through
+/// [`MockState::lock`].
+pub struct MockData {
+    pub users: BTreeMap<String, MockUser>,
+    pub mounts: Vec<MockMount>,
+    /// Every job ever started, oldest first.
+    pub jobs: Vec<RepoJobDto>,
+    pub messages: Vec<Message>,
+    /// The notifications for every user, who is named in `send_to`.
+    pub notifications: Vec<WsNotificationDto>,
+    /// The user name for each valid token.
+    pub tokens: HashMap<String, String>,
+    pub system: SystemSnapshotDto,
+    /// The number of shutdown and restart requests received.
+    pub shutdowns: usize,
+    pub restarts: usize,
+}
+
+impl Default for MockData {
+    fn default() -> Self {
+        let vg = |name: &str| VGStatDto {
+            vg_name: name.to_string(),
+            drive_total: 1,
+            lv_total: 0,
+            b_allocated: 0,
+            b_free: 1024 * 1024 * 1024 * 1024,
+            b_blk_size: 4096,
+        };
+        MockData {
+            users: BTreeMap::new(),
+            mounts: vec![],
+            jobs: vec![],
+            messages: vec![],
+            notifications: vec![],
+            tokens: HashMap::new(),
+            system: SystemSnapshotDto {
+                api_version: env!("CARGO_PKG_VERSION").into(),
+                cpus: vec![],
+                timestamp: Utc::now().naive_utc(),
+                os_name: Some("Neptis Mock".into()),
+                kernel_type: None,
+                hostname: Some("localhost".into()),
+                total_memory_bytes: 0,
+                used_memory_bytes: 0,
+                total_swap_bytes: 0,
+                used_swap_bytes: 0,
+                disks: vec![],
+                networks: vec![],
+                temperatures: vec![],
+                smb_connections: None,
+                smb_handles: None,
+                data_info: vg("data"),
+                repo_info: vg("repo"),
+                features: None,
+            },
+            shutdowns: 0,
+            restarts: 0,
+        }
+    }
+}
+
+impl MockData {
+    pub fn login(&mut self, user_name: &str, password: &str) -> Result<AuthOutputDto, MockError> {
+        if self
+            .users
+            .get(user_name)
+            .is_none_or(|x| x.password != password)
+        {
+            return Err(MockError::Unauthorized(
+                "The user name or password is incorrect".into(),
+            ));
+        }
+        let token = Uuid::new_v4().simple().to_string();
+        self.tokens.insert(token.clone(), user_name.to_string());
+        Ok(AuthOutputDto {
+            token,
+            expire_date: Utc::now().naive_utc() + TOKEN_LIFETIME,
+        })
+    }
+
+    /// Invalidates every token, as if the server was restarted.
+    pub fn revoke_tokens(&mut self) {
+        self.tokens.clear();
+    }
+
+    pub fn user(&self, user_name: &str) -> Result<&MockUser, MockError> {
+        self.users.get(user_name).ok_or(MockError::NotFound(format!(
+            "User '{}' does not exist",
+            user_name
+        )))
+    }
+
+    pub fn user_mut(&mut self, user_name: &str) -> Result<&mut MockUser, MockError> {
+        self.users
+            .get_mut(user_name)
+            .ok_or(MockError::NotFound(format!(
+                "User '{}' does not exist",
+                user_name
+            )))
+    }
+
+    /// Returns the user with the usage of their points filled in.
+    pub fn user_dto(&self, user_name: &str) -> Result<UserDto, MockError> {
+        let user = self.user(user_name)?;
+        let mut ret = user.info.clone();
+        ret.is_smb = user.smb_password.is_some();
+        ret.all_point_usage = PointUsage::default();
+        for mount in self.mounts.iter().filter(|x| x.owner == user_name) {
+            ret.all_point_usage += mount.usage();
+        }
+        ret.free_data_bytes = ret
+            .max_data_bytes
+            .saturating_sub(ret.all_point_usage.b_data_total);
+        ret.free_repo_bytes = ret
+            .max_repo_bytes
+            .saturating_sub(ret.all_point_usage.b_repo_total);
+        Ok(ret)
+    }
+
+    pub fn mount(&self, owner: &str, name: &str) -> Result<&MockMount, MockError> {
+        self.mounts
+            .iter()
+            .find(|x| x.owner == owner && x.name == name)
+            .ok_or(MockError::NotFound(format!(
+                "Point '{}' does not exist",
+                name
+            )))
+    }
+
+    pub fn mount_mut(&mut self, owner: &str, name: &str) -> Result<&mut MockMount, MockError> {
+        self.mounts
+            .iter_mut()
+            .find(|x| x.owner == owner && x.name == name)
+            .ok_or(MockError::NotFound(format!(
+                "Point '{}' does not exist",
+                name
+            )))
+    }
+
+    /// Returns a point the user owns or which has been shared with them, see
+    /// [`MockMount::can_access`].
+    pub fn shared_mount(
+        &self,
+        user_name: &str,
+        owner: &str,
+        name: &str,
+        write: bool,
+    ) -> Result<&MockMount, MockError> {
+        let mount = self.mount(owner, name)?;
+        if !mount.can_access(user_name, write) {
+            return Err(MockError::Forbidden(format!(
+                "Point '{}' has not been shared with '{}'",
+                name, user_name
+            )));
+        }
+        Ok(mount)
+    }
+
+    pub fn shared_mount_mut(
+        &mut self,
+        user_name: &str,
+        owner: &str,
+        name: &str,
+        write: bool,
+    ) -> Result<&mut MockMount, MockError> {
+        self.shared_mount(user_name, owner, name, write)?;
+        self.mount_mut(owner, name)
+    }
+
+    /// Records a job which has already finished successfully, and returns it.
+    pub fn add_job(
+        &mut self,
+        owner: &str,
+        name: &str,
+        job_type: JobType,
+        snapshot_id: Option<String>,
+        messages: Vec<String>,
+    ) -> RepoJobDto {
+        let now = Utc::now().naive_utc();
+        let bytes = self
+            .mount(owner, name)
+            .map(|x| x.usage().b_data_used as i64)
+            .unwrap_or(0);
+        let job = RepoJobDto {
+            id: Uuid::new_v4(),
+            title: None,
+            snapshot_id,
+            point_owned_by: owner.to_string(),
+            point_name: name.to_string(),
+            job_type,
+            job_status: JobStatus::Successful,
+            used_bytes: bytes,
+            total_bytes: Some(bytes),
+            errors: vec![],
+            messages,
+            create_date: now,
+            end_date: Some(now),
+            auto_job: None,
+        };
+        self.jobs.push(job.clone());
+        job
+    }
+}
+
+/// The shared state of a mock server.
+pub struct MockState {
+    secret: RwLock<Option<RollingSecret>>,
+    data: Mutex<MockData>,
+}
+
+impl MockState {
+    pub fn new(secret: Option<RollingSecret>, data: MockData) -> Self {
+        MockState {
+            secret: RwLock::new(secret),
+            data: Mutex::new(data),
+        }
+    }
+
+    pub fn secret(&self) -> Option<RollingSecret> {
+        self.secret.read().unwrap().clone()
+    }
+
+    /// Replaces the secret, as an admin installing a new one on the server would. A rotated
+    /// secret keeps accepting its previous one until the grace period ends.
+    pub fn set_secret(&self, secret: Option<RollingSecret>) {
+        *self.secret.write().unwrap() = secret;
+    }
+
+    pub fn lock(&self) -> MutexGuard<'_, MockData> {
+        self.data.lock().unwrap()
+    }
+}
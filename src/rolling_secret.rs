@@ -1,30 +1,169 @@
 use aes::cipher::{BlockDecryptMut, BlockEncryptMut};
 use aes::Aes256;
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::Aes256Gcm;
 use base64::{engine::general_purpose::STANDARD, Engine as _};
 use cbc::cipher::block_padding::Pkcs7;
 use cbc::cipher::KeyIvInit;
 use cbc::{Decryptor, Encryptor};
-use chrono::Utc;
+use chacha20poly1305::ChaCha20Poly1305;
+use chrono::{DateTime, TimeDelta, Utc};
+use qrcode::render::unicode::Dense1x2;
+use qrcode::{EcLevel, QrCode};
 use rand::{rng, Rng, RngCore};
 use sha2::Digest;
 use sha2::{Sha256, Sha512};
 use std::fmt::Display;
-use std::thread;
-use std::time::Duration;
+use std::str::FromStr;
 use std::vec::Vec;
 use totp_rs::Algorithm::SHA512;
 use totp_rs::TOTP;
 
 const CHARACTERS: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
 
+/// The number of seconds each rolling key is valid for.
+pub const STEP_SECS: u64 = 60;
+
+/// How many steps either side of the expected one are tried when decrypting, to tolerate
+/// clock drift between the two ends and data sealed right before the step changed.
+pub const SKEW_STEPS: u64 = 1;
+
 type Aes256CbcEnc = Encryptor<Aes256>;
 type Aes256CbcDec = Decryptor<Aes256>;
 
+/// The bytes every versioned envelope starts with, followed by the version byte.
+pub const ENVELOPE_MAGIC: [u8; 2] = *b"NE";
+
+/// The header used to negotiate the envelope format with the server. The client sends the
+/// format it used, and servers which understand versioned envelopes answer with theirs.
+pub const ENVELOPE_HEADER: &str = "X-Neptis-Envelope";
+
+const NONCE_LEN: usize = 12;
+const TAG_LEN: usize = 16;
+const ENVELOPE_KEY_LABEL: &[u8] = b"neptis-envelope-key";
+
+/// The format of data sealed by a [`RollingSecret`].
+///
+/// Versioned envelopes are laid out as `magic | version | nonce (12) | ciphertext | tag (16)`,
+/// and authenticate both the data and the associated data given when sealing. Legacy data has
+/// no header and is just the IV followed by the CBC ciphertext.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum EnvelopeVersion {
+    /// AES-256-CBC with PKCS7 padding and no authentication, understood by older servers.
+    LegacyCbc,
+    #[default]
+    AesGcm,
+    ChaCha20Poly1305,
+}
+
+impl EnvelopeVersion {
+    pub fn id(&self) -> u8 {
+        match self {
+            EnvelopeVersion::LegacyCbc => 0,
+            EnvelopeVersion::AesGcm => 1,
+            EnvelopeVersion::ChaCha20Poly1305 => 2,
+        }
+    }
+
+    pub fn from_id(id: u8) -> Option<Self> {
+        match id {
+            0 => Some(EnvelopeVersion::LegacyCbc),
+            1 => Some(EnvelopeVersion::AesGcm),
+            2 => Some(EnvelopeVersion::ChaCha20Poly1305),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` when tampering with the sealed data is detected.
+    pub fn is_authenticated(&self) -> bool {
+        *self != EnvelopeVersion::LegacyCbc
+    }
+}
+
+impl Display for EnvelopeVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            EnvelopeVersion::LegacyCbc => "aes-256-cbc",
+            EnvelopeVersion::AesGcm => "aes-256-gcm",
+            EnvelopeVersion::ChaCha20Poly1305 => "chacha20-poly1305",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl FromStr for EnvelopeVersion {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "aes-256-cbc" | "cbc" | "0" => Ok(EnvelopeVersion::LegacyCbc),
+            "aes-256-gcm" | "gcm" | "1" => Ok(EnvelopeVersion::AesGcm),
+            "chacha20-poly1305" | "chacha" | "2" => Ok(EnvelopeVersion::ChaCha20Poly1305),
+            _ => Err(format!("Unknown envelope version '{s}'")),
+        }
+    }
+}
+
+/// The part of a secure exchange an envelope carries, bound into its associated data so one
+/// part cannot be replayed as another.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnvelopePart {
+    /// The encrypted request path. It is bound to the method only, as it is the path.
+    Path,
+    Request,
+    Response,
+}
+
+/// Builds the associated data for one part of a request to `path` (the decrypted path,
+/// such as `/api/mounts`), so a sealed body cannot be moved to another route or method.
+pub fn envelope_aad(part: EnvelopePart, method: &str, path: &str) -> Vec<u8> {
+    let (part, path) = match part {
+        EnvelopePart::Path => ("path", ""),
+        EnvelopePart::Request => ("request", path),
+        EnvelopePart::Response => ("response", path),
+    };
+    format!("neptis/{} {} {}", part, method.to_uppercase(), path).into_bytes()
+}
+
+fn aead_seal<A: Aead + KeyInit>(
+    key: &[u8],
+    nonce: &[u8],
+    msg: &[u8],
+    aad: &[u8],
+) -> Option<Vec<u8>> {
+    A::new_from_slice(key)
+        .ok()?
+        .encrypt(nonce.into(), Payload { msg, aad })
+        .ok()
+}
+
+fn aead_open<A: Aead + KeyInit>(
+    key: &[u8],
+    nonce: &[u8],
+    msg: &[u8],
+    aad: &[u8],
+) -> Option<Vec<u8>> {
+    A::new_from_slice(key)
+        .ok()?
+        .decrypt(nonce.into(), Payload { msg, aad })
+        .ok()
+}
+
+/// The alphabet of [`RollingSecret::fingerprint`], which leaves out easily confused letters.
+const FINGERPRINT_CHARS: &[u8] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
 impl Display for RollingSecret {
+    /// Writes the secret as `key_a§key_b§password`. While a rotation's grace period is open,
+    /// the previous secret and the end of the grace period follow, so the same string can be
+    /// installed on the server and still accept the old secret until then.
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let otp_a_key = STANDARD.encode(self.otp_a.secret.as_slice());
         let otp_b_key = STANDARD.encode(self.otp_b.secret.as_slice());
-        write!(f, "{}", format!("{}§{}§{}", otp_a_key, otp_b_key, self.aes_password))
+        write!(f, "{}§{}§{}", otp_a_key, otp_b_key, self.aes_password)?;
+        if let (Some(previous), Some(until)) = (self.previous(), self.grace_until()) {
+            write!(f, "§{}§{}", previous, until.timestamp())?;
+        }
+        Ok(())
     }
 }
 
@@ -33,16 +172,19 @@ pub struct RollingSecret {
     otp_a: TOTP,
     otp_b: TOTP,
     aes_password: String,
+    /// The secret this one replaced, accepted alongside it until the given time.
+    previous: Option<(Box<RollingSecret>, DateTime<Utc>)>,
 }
 
 impl RollingSecret {
     fn from_key(key_a: &[u8], key_b: &[u8], aes_password: &str) -> Option<Self> {
-        let otp_a = TOTP::new(SHA512, 8, 0, 60, key_a.to_vec()).ok()?;
-        let otp_b = TOTP::new(SHA512, 8, 0, 60, key_b.to_vec()).ok()?;
+        let otp_a = TOTP::new(SHA512, 8, 0, STEP_SECS, key_a.to_vec()).ok()?;
+        let otp_b = TOTP::new(SHA512, 8, 0, STEP_SECS, key_b.to_vec()).ok()?;
         Some(RollingSecret {
             otp_a,
             otp_b,
             aes_password: aes_password.to_string(),
+            previous: None,
         })
     }
 
@@ -74,8 +216,8 @@ impl RollingSecret {
     }
 
     pub fn from_string(encoded: &str) -> Option<Self> {
-        let parts: Vec<&str> = encoded.split('§').collect();
-        if parts.len() != 3 {
+        let parts: Vec<&str> = encoded.trim().split('§').collect();
+        if parts.len() != 3 && parts.len() != 7 {
             return None;
         }
 
@@ -83,27 +225,121 @@ impl RollingSecret {
         let otp2_key = STANDARD.decode(parts[1]).ok()?;
         let aes_password = parts[2].to_string();
 
-        Self::from_key(
+        let ret = Self::from_key(
             otp1_key.as_slice(),
             otp2_key.as_slice(),
             aes_password.as_str(),
-        )
+        )?;
+        if parts.len() == 3 {
+            return Some(ret);
+        }
+        let previous = Self::from_string(&parts[3..6].join("§"))?;
+        let until = DateTime::from_timestamp(parts[6].parse().ok()?, 0)?;
+        Some(ret.with_previous(previous, until))
     }
 
-    pub fn rolling_key(&self) -> Option<Vec<u8>> {
-        let now = Utc::now().timestamp() as u64;
+    /// Generates a new secret which replaces this one. The new secret still accepts this one
+    /// until `grace` has passed, so clients which have not been updated keep working.
+    pub fn rotate(&self, grace: TimeDelta) -> Option<Self> {
+        Some(Self::generate()?.with_previous(self.without_previous(), Utc::now() + grace))
+    }
 
-        // Assume 60-second step
-        let time_until_next_roll = 60 - (now % 60);
+    /// Accepts `previous` alongside this secret until `until`.
+    pub fn with_previous(mut self, previous: RollingSecret, until: DateTime<Utc>) -> Self {
+        self.previous = Some((Box::new(previous.without_previous()), until));
+        self
+    }
 
-        // If we're within 1 seconds of the next step, wait
-        if time_until_next_roll <= 1 {
-            thread::sleep(Duration::from_secs(time_until_next_roll + 1));
+    /// Returns this secret on its own, without any previous secret.
+    pub fn without_previous(&self) -> Self {
+        RollingSecret {
+            previous: None,
+            ..self.clone()
         }
+    }
+
+    /// Returns the secret this one replaced, while its grace period is open.
+    pub fn previous(&self) -> Option<&RollingSecret> {
+        self.previous
+            .as_ref()
+            .filter(|x| x.1 > Utc::now())
+            .map(|x| x.0.as_ref())
+    }
+
+    /// Returns when the previous secret stops being accepted, while its grace period is open.
+    pub fn grace_until(&self) -> Option<DateTime<Utc>> {
+        self.previous
+            .as_ref()
+            .map(|x| x.1)
+            .filter(|x| *x > Utc::now())
+    }
+
+    /// Returns the secrets to try when reading data from a peer: this one first, then the
+    /// previous one during its grace period. Data is always sealed with this one.
+    pub fn accepted(&self) -> impl Iterator<Item = &RollingSecret> {
+        std::iter::once(self).chain(self.previous())
+    }
 
-        let new_now = Utc::now().timestamp() as u64;
-        let otp1 = self.otp_a.generate(new_now).parse::<i64>().ok()?;
-        let otp2 = self.otp_b.generate(new_now).parse::<i64>().ok()?;
+    /// Returns a short code which identifies this secret, such as `7KQ2-M9XD`, so both sides
+    /// of a pairing can check they hold the same secret without comparing the whole string.
+    pub fn fingerprint(&self) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(self.otp_a.secret.as_slice());
+        hasher.update(self.otp_b.secret.as_slice());
+        hasher.update(self.aes_password.as_bytes());
+        let hash = hasher.finalize();
+        // The first 40 bits, five at a time.
+        let bits = hash[..5].iter().fold(0u64, |acc, x| (acc << 8) | *x as u64);
+        let code = (0..8)
+            .rev()
+            .map(|i| FINGERPRINT_CHARS[((bits >> (i * 5)) & 0x1F) as usize] as char)
+            .collect::<String>();
+        format!("{}-{}", &code[..4], &code[4..])
+    }
+
+    /// Renders the string form of this secret as a QR code for the terminal.
+    pub fn to_qr_code(&self) -> Option<String> {
+        let code = QrCode::with_error_correction_level(self.to_string(), EcLevel::L).ok()?;
+        Some(
+            code.render::<Dense1x2>()
+                .dark_color(Dense1x2::Light)
+                .light_color(Dense1x2::Dark)
+                .quiet_zone(true)
+                .build(),
+        )
+    }
+
+    /// Returns the index of the key step at `time` (a Unix timestamp in seconds).
+    pub fn step_at(time: i64) -> u64 {
+        time.max(0) as u64 / STEP_SECS
+    }
+
+    /// Returns the index of the current key step, which changes every `STEP_SECS`.
+    pub fn current_step() -> u64 {
+        Self::step_at(Utc::now().timestamp())
+    }
+
+    /// Returns the steps tried when decrypting data sealed around `step`: the step itself
+    /// first, then its neighbours up to [`SKEW_STEPS`] away.
+    fn candidate_steps(step: u64) -> impl Iterator<Item = u64> {
+        std::iter::once(step).chain((1..=SKEW_STEPS).flat_map(move |d| {
+            [step.checked_sub(d), step.checked_add(d)]
+                .into_iter()
+                .flatten()
+        }))
+    }
+
+    pub fn rolling_key(&self) -> Option<Vec<u8>> {
+        self.key_at(Self::current_step())
+    }
+
+    /// Derives the key for the given step. This never blocks, so it is safe to call from
+    /// async code; steps changing mid-request are handled by the receiver trying the
+    /// adjacent steps instead.
+    pub fn key_at(&self, step: u64) -> Option<Vec<u8>> {
+        let time = step * STEP_SECS;
+        let otp1 = self.otp_a.generate(time).parse::<i64>().ok()?;
+        let otp2 = self.otp_b.generate(time).parse::<i64>().ok()?;
         let otp = otp1 as u64 * otp2 as u64;
 
         let password: String = self.scramble_password(&self.aes_password, otp)?;
@@ -144,7 +380,12 @@ impl RollingSecret {
     }
 
     pub fn encrypt(&self, data: &[u8]) -> Option<Vec<u8>> {
-        let key = self.rolling_key()?;
+        self.encrypt_at(data, Self::current_step())
+    }
+
+    /// Encrypts with AES-256-CBC using the key of `step`, prefixing the random IV.
+    pub fn encrypt_at(&self, data: &[u8], step: u64) -> Option<Vec<u8>> {
+        let key = self.key_at(step)?;
         let mut iv = [0u8; 16];
         rng().fill_bytes(&mut iv);
 
@@ -156,15 +397,232 @@ impl RollingSecret {
         Some(result)
     }
 
+    /// The AEAD key is derived from the rolling key so it is never shared with the legacy mode.
+    fn envelope_key(&self, step: u64) -> Option<Vec<u8>> {
+        let mut hasher = Sha256::new();
+        hasher.update(ENVELOPE_KEY_LABEL);
+        hasher.update(self.key_at(step)?);
+        Some(hasher.finalize().to_vec())
+    }
+
+    /// Seals `data` in the given envelope format, authenticating it together with `aad`.
+    /// The legacy format cannot authenticate anything and ignores `aad`.
+    pub fn seal(&self, version: EnvelopeVersion, data: &[u8], aad: &[u8]) -> Option<Vec<u8>> {
+        self.seal_at(version, data, aad, Self::current_step())
+    }
+
+    /// Seals `data` like [`RollingSecret::seal`], using the key of `step`.
+    pub fn seal_at(
+        &self,
+        version: EnvelopeVersion,
+        data: &[u8],
+        aad: &[u8],
+        step: u64,
+    ) -> Option<Vec<u8>> {
+        let mut nonce = [0u8; NONCE_LEN];
+        rng().fill_bytes(&mut nonce);
+        let sealed = match version {
+            EnvelopeVersion::LegacyCbc => return self.encrypt_at(data, step),
+            EnvelopeVersion::AesGcm => {
+                aead_seal::<Aes256Gcm>(&self.envelope_key(step)?, &nonce, data, aad)?
+            }
+            EnvelopeVersion::ChaCha20Poly1305 => {
+                aead_seal::<ChaCha20Poly1305>(&self.envelope_key(step)?, &nonce, data, aad)?
+            }
+        };
+        let mut result = ENVELOPE_MAGIC.to_vec();
+        result.push(version.id());
+        result.extend(nonce);
+        result.extend(sealed);
+        Some(result)
+    }
+
+    /// Opens data sealed with [`RollingSecret::seal`] or [`RollingSecret::encrypt`], returning
+    /// the format it used.
+    ///
+    /// Data which carries a versioned envelope header is only ever opened as that version, so
+    /// it fails when it does not authenticate. Anything else is decrypted as legacy data, but
+    /// only when `allow_legacy` is set.
+    pub fn open(
+        &self,
+        data: &[u8],
+        aad: &[u8],
+        allow_legacy: bool,
+    ) -> Option<(EnvelopeVersion, Vec<u8>)> {
+        self.open_at(data, aad, Self::current_step(), allow_legacy)
+    }
+
+    /// Opens data like [`RollingSecret::open`], trying the keys of the steps around `step`.
+    pub fn open_at(
+        &self,
+        data: &[u8],
+        aad: &[u8],
+        step: u64,
+        allow_legacy: bool,
+    ) -> Option<(EnvelopeVersion, Vec<u8>)> {
+        let header = ENVELOPE_MAGIC.len() + 1;
+        if data.len() >= header + NONCE_LEN + TAG_LEN
+            && data.starts_with(&ENVELOPE_MAGIC)
+            && let Some(version) = EnvelopeVersion::from_id(data[ENVELOPE_MAGIC.len()])
+            && version.is_authenticated()
+        {
+            let (nonce, sealed) = data[header..].split_at(NONCE_LEN);
+            return Self::candidate_steps(step).find_map(|step| {
+                let key = self.envelope_key(step)?;
+                let opened = match version {
+                    EnvelopeVersion::LegacyCbc => None,
+                    EnvelopeVersion::AesGcm => aead_open::<Aes256Gcm>(&key, nonce, sealed, aad),
+                    EnvelopeVersion::ChaCha20Poly1305 => {
+                        aead_open::<ChaCha20Poly1305>(&key, nonce, sealed, aad)
+                    }
+                };
+                opened.map(|x| (version, x))
+            });
+        }
+        if !allow_legacy {
+            return None;
+        }
+        self.decrypt_at(data, step)
+            .map(|x| (EnvelopeVersion::LegacyCbc, x))
+    }
+
     /// Decrypts AES-256-CBC data, extracting the IV from the first 16 bytes
     pub fn decrypt(&self, encrypted_data: &[u8]) -> Option<Vec<u8>> {
+        self.decrypt_at(encrypted_data, Self::current_step())
+    }
+
+    /// Decrypts AES-256-CBC data, trying the keys of the steps around `step`. Without a MAC,
+    /// a wrong key is only noticed through bad padding, so the given step is tried first.
+    pub fn decrypt_at(&self, encrypted_data: &[u8], step: u64) -> Option<Vec<u8>> {
         if encrypted_data.len() < 16 {
             return None;
         }
-        let key = self.rolling_key()?;
         let (iv, ciphertext) = encrypted_data.split_at(16);
-        let cipher = Aes256CbcDec::new_from_slices(key.as_slice(), iv).ok()?;
-        let mut buffer = ciphertext.to_vec();
-        cipher.decrypt_padded_vec_mut::<Pkcs7>(&mut buffer).ok()
+        Self::candidate_steps(step).find_map(|step| {
+            let key = self.key_at(step)?;
+            let cipher = Aes256CbcDec::new_from_slices(key.as_slice(), iv).ok()?;
+            cipher.decrypt_padded_vec_mut::<Pkcs7>(ciphertext).ok()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VERSIONS: [EnvelopeVersion; 3] = [
+        EnvelopeVersion::LegacyCbc,
+        EnvelopeVersion::AesGcm,
+        EnvelopeVersion::ChaCha20Poly1305,
+    ];
+
+    fn aad() -> Vec<u8> {
+        envelope_aad(EnvelopePart::Request, "put", "/api/mounts")
+    }
+
+    #[test]
+    fn seal_and_open_round_trip() {
+        let secret = RollingSecret::generate().unwrap();
+        let step = RollingSecret::current_step();
+        for version in VERSIONS {
+            let sealed = secret.seal_at(version, b"hello", &aad(), step).unwrap();
+            let opened = secret.open_at(&sealed, &aad(), step, true);
+            assert_eq!(opened, Some((version, b"hello".to_vec())), "{version}");
+        }
+    }
+
+    #[test]
+    fn legacy_data_needs_legacy_allowed() {
+        let secret = RollingSecret::generate().unwrap();
+        let step = RollingSecret::current_step();
+        let sealed = secret
+            .seal_at(EnvelopeVersion::LegacyCbc, b"hello", &aad(), step)
+            .unwrap();
+        assert_eq!(secret.open_at(&sealed, &aad(), step, false), None);
+    }
+
+    #[test]
+    fn tampered_data_fails() {
+        let secret = RollingSecret::generate().unwrap();
+        let step = RollingSecret::current_step();
+        for version in [EnvelopeVersion::AesGcm, EnvelopeVersion::ChaCha20Poly1305] {
+            let mut sealed = secret.seal_at(version, b"hello", &aad(), step).unwrap();
+            let last = sealed.len() - TAG_LEN - 1;
+            sealed[last] ^= 1;
+            // A versioned envelope is never retried as legacy data.
+            let opened = secret.open_at(&sealed, &aad(), step, true);
+            assert_eq!(opened, None, "{version}");
+        }
+    }
+
+    #[test]
+    fn wrong_aad_fails() {
+        let secret = RollingSecret::generate().unwrap();
+        let step = RollingSecret::current_step();
+        let other = envelope_aad(EnvelopePart::Response, "put", "/api/mounts");
+        for version in [EnvelopeVersion::AesGcm, EnvelopeVersion::ChaCha20Poly1305] {
+            let sealed = secret.seal_at(version, b"hello", &aad(), step).unwrap();
+            let opened = secret.open_at(&sealed, &other, step, true);
+            assert_eq!(opened, None, "{version}");
+        }
+    }
+
+    #[test]
+    fn string_round_trips_with_previous() {
+        let old = RollingSecret::generate().unwrap();
+        let until = DateTime::from_timestamp(Utc::now().timestamp() + 3600, 0).unwrap();
+        let new = RollingSecret::generate().unwrap();
+        let secret = new.with_previous(old.clone(), until);
+        let text = secret.to_string();
+        assert_eq!(text.split('§').count(), 7);
+
+        let parsed = RollingSecret::from_string(&text).unwrap();
+        assert_eq!(parsed.fingerprint(), secret.fingerprint());
+        assert_eq!(parsed.previous().unwrap().fingerprint(), old.fingerprint());
+        assert_eq!(parsed.grace_until(), Some(until));
+        assert_eq!(parsed.to_string(), text);
+
+        let alone = RollingSecret::from_string(&secret.without_previous().to_string()).unwrap();
+        assert_eq!(alone.fingerprint(), secret.fingerprint());
+        assert!(alone.previous().is_none());
+    }
+
+    #[test]
+    fn previous_expires_after_grace() {
+        let old = RollingSecret::generate().unwrap();
+        let until = Utc::now() - TimeDelta::seconds(1);
+        let new = RollingSecret::generate().unwrap();
+        let secret = new.with_previous(old.clone(), until);
+        assert!(secret.previous().is_none());
+        assert!(secret.grace_until().is_none());
+        assert_eq!(secret.accepted().count(), 1);
+        assert_eq!(secret.to_string().split('§').count(), 3);
+
+        // Data from the previous secret is only read while the grace period is open.
+        let step = RollingSecret::current_step();
+        let version = EnvelopeVersion::AesGcm;
+        let sealed = old.seal_at(version, b"hello", &aad(), step).unwrap();
+        let open = |x: &RollingSecret| {
+            x.accepted()
+                .find_map(|x| x.open_at(&sealed, &aad(), step, false))
+        };
+        assert_eq!(open(&secret), None);
+        let secret = secret.with_previous(old, Utc::now() + TimeDelta::hours(1));
+        assert!(open(&secret).is_some());
+    }
+
+    #[test]
+    fn adjacent_steps_open() {
+        let secret = RollingSecret::generate().unwrap();
+        let step = RollingSecret::current_step();
+        for version in VERSIONS {
+            let sealed = secret.seal_at(version, b"hello", &aad(), step).unwrap();
+            for other in [step - SKEW_STEPS, step + SKEW_STEPS] {
+                let opened = secret.open_at(&sealed, &aad(), other, true);
+                assert_eq!(opened, Some((version, b"hello".to_vec())), "{version}");
+            }
+            let far = secret.open_at(&sealed, &aad(), step + SKEW_STEPS + 1, true);
+            assert_ne!(far, Some((version, b"hello".to_vec())), "{version}");
+        }
     }
 }
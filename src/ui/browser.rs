@@ -1,6 +1,6 @@
 use std::{
     cmp::Ordering,
-    fs::{self, File},
+    fs::{self, OpenOptions},
     io::{BufWriter, Write},
     path::{Component, Path, PathBuf},
     thread,
@@ -8,7 +8,7 @@ use std::{
 };
 
 use crate::file_size::FileSize;
-use crate::filesystem::{FsNode, NeptisFS};
+use crate::filesystem::{FsNode, NeptisFS, SHARED_PREFIX};
 use crate::prelude::GenericFileType;
 use crate::to_dto_time;
 use chrono::Local;
@@ -42,7 +42,13 @@ impl FileBrowser {
             _ => None,
         });
 
-        match (parts.next(), parts.next()) {
+        // Shared points live under /@<owner>, and are writable when the share allows it.
+        let first = parts.next();
+        if first.is_some_and(|x| x.starts_with(SHARED_PREFIX)) {
+            return parts.next().is_none() || self.fs.is_read_only(path);
+        }
+
+        match (first, parts.next()) {
             (Some(_), Some("data")) => false, // Matches /anything/data or deeper
             _ => true,                        // All others are read-only
         }
@@ -70,39 +76,75 @@ impl FileBrowser {
             .map(|x| PathBuf::from(x))
         {
             Some(base_sp) => {
-                let save_path = base_sp.join(format!(
-                    "{}-{}",
-                    Uuid::new_v4(),
-                    path.file_name()
-                        .map(|x| x.to_str().unwrap())
-                        .unwrap_or("unknown"),
-                ));
+                let name = path
+                    .file_name()
+                    .map(|x| x.to_str().unwrap())
+                    .unwrap_or("unknown");
                 if !fs::exists(&base_sp).unwrap_or(false) {
                     let _ = fs::create_dir_all(&base_sp);
                 }
-                let mut file = match File::create(&save_path) {
+
+                // Partial downloads are kept as "<name>.part" so they can be resumed.
+                let part_path = base_sp.join(format!("{}.part", name));
+                let mut offset = 0;
+                if let Ok(meta) = fs::metadata(&part_path)
+                    && meta.len() < node.attr.size
+                    && Confirm::new(&format!(
+                        "A partial download was found ({} / {}). Do you want to resume",
+                        FileSize::prettify(meta.len()),
+                        FileSize::prettify(node.attr.size)
+                    ))
+                    .with_default(true)
+                    .prompt_skippable()
+                    .expect("Failed to show prompt!")
+                    .unwrap_or(false)
+                {
+                    offset = meta.len();
+                }
+
+                let mut file = match OpenOptions::new()
+                    .create(true)
+                    .write(true)
+                    .append(offset > 0)
+                    .truncate(offset == 0)
+                    .open(&part_path)
+                {
                     Ok(f) => BufWriter::new(f),
                     Err(e) => {
-                        println!("Failed to create file ('{:?}'): {}", &save_path, e);
+                        println!("Failed to create file ('{:?}'): {}", &part_path, e);
                         thread::sleep(Duration::from_secs(1));
                         return;
                     }
                 };
-                match self.fs.do_dump(path, 0, usize::MAX) {
-                    Some(data) => {
-                        if let Err(e) = file.write_all(&data) {
-                            println!("> Failed to write to file: {}", e);
-                        } else {
-                            let _ = file.flush();
-                            println!(
+                let ret = self.fs.do_stream(path, offset, |chunk| {
+                    file.write_all(chunk)?;
+                    offset += chunk.len() as u64;
+                    print!(
+                        "\r> Downloading: {} / {}",
+                        FileSize::prettify(offset),
+                        FileSize::prettify(node.attr.size),
+                    );
+                    std::io::stdout().flush()
+                });
+                println!();
+                match ret.and_then(|x| Ok(file.flush().map(|_| x)?)) {
+                    Ok(total) => {
+                        let mut save_path = base_sp.join(name);
+                        if save_path.exists() {
+                            save_path = base_sp.join(format!("{}-{}", Uuid::new_v4(), name));
+                        }
+                        drop(file);
+                        match fs::rename(&part_path, &save_path) {
+                            Ok(_) => println!(
                                 "> Downloaded: {} / {}",
-                                FileSize::prettify(data.len() as u64),
+                                FileSize::prettify(total),
                                 FileSize::prettify(node.attr.size),
-                            );
+                            ),
+                            Err(e) => println!("> Failed to write to file: {}", e),
                         }
                     }
-                    None => {
-                        println!("> Error reading from source");
+                    Err(e) => {
+                        println!("> Error reading from source (progress has been saved): {}", e);
                     }
                 }
                 thread::sleep(Duration::from_secs(1));
@@ -111,6 +153,76 @@ impl FileBrowser {
         }
     }
 
+    pub fn do_upload(&self, parent: &Path) {
+        clearscreen::clear().expect("Failed to clear screen!");
+        let Some(local) = Text::new("Please enter the local file to upload")
+            .with_validator(required!())
+            .with_validator(|s: &str| {
+                if Path::new(s).is_file() {
+                    Ok(Validation::Valid)
+                } else {
+                    Ok(Validation::Invalid("The path must be a file!".into()))
+                }
+            })
+            .prompt_skippable()
+            .expect("Failed to show prompt!")
+            .map(PathBuf::from)
+        else {
+            return;
+        };
+        let Some(path) = Text::new("Please enter a file name")
+            .with_validator(required!())
+            .with_initial_value(
+                local
+                    .file_name()
+                    .and_then(|x| x.to_str())
+                    .unwrap_or_default(),
+            )
+            .prompt_skippable()
+            .expect("Failed to show prompt!")
+            .map(|x| parent.join(x))
+        else {
+            return;
+        };
+        let total = fs::metadata(&local).map(|x| x.len()).unwrap_or(0);
+        let mut resume = false;
+        if let Some(size) = self.fs.do_size(&path) {
+            resume = size < total
+                && Confirm::new(&format!(
+                    "A partial upload was found ({} / {}). Do you want to resume",
+                    FileSize::prettify(size),
+                    FileSize::prettify(total)
+                ))
+                .with_default(true)
+                .prompt_skippable()
+                .expect("Failed to show prompt!")
+                .unwrap_or(false);
+            if !resume
+                && !Confirm::new("The file already exists. Do you want to overwrite it")
+                    .with_default(false)
+                    .prompt_skippable()
+                    .expect("Failed to show prompt!")
+                    .unwrap_or(false)
+            {
+                return;
+            }
+        }
+        let ret = self.fs.do_upload(&path, &local, resume, |offset| {
+            print!(
+                "\r> Uploading: {} / {}",
+                FileSize::prettify(offset),
+                FileSize::prettify(total),
+            );
+            let _ = std::io::stdout().flush();
+        });
+        println!();
+        match ret {
+            Ok(size) => println!("> Uploaded: {}", FileSize::prettify(size)),
+            Err(e) => println!("> Failed to upload (it can be resumed later): {}", e),
+        }
+        thread::sleep(Duration::from_secs(1));
+    }
+
     pub fn do_stats(&self, node: &FsNode, path: &Path) {
         loop {
             clearscreen::clear().expect("Failed to clear screen!");
@@ -286,6 +398,7 @@ impl FileBrowser {
                 match self
                     .fs
                     .do_write(path, None, None, Some(content.as_bytes()), None, None, None)
+                    .and_then(|_| self.fs.do_flush(path))
                 {
                     Some(_) => println!("> Successfully wrote the data."),
                     None => println!("> Failed to write the data"),
@@ -309,6 +422,7 @@ impl FileBrowser {
         const STR_RW_DELETE: &'static str = "Delete";
         const STR_RW_MKDIR: &'static str = "Create Directory";
         const STR_RW_MKNOD: &'static str = "Create File";
+        const STR_RW_UPLOAD: &'static str = "Upload File";
         const STR_BACK: &'static str = "Go Back";
         const STR_UP: &'static str = "Go Up";
         const PLAINTEXT_EXTENSIONS: &[&str] = &[
@@ -481,6 +595,7 @@ impl FileBrowser {
                         if is_rw {
                             keys.push(STR_RW_MKDIR.to_string());
                             keys.push(STR_RW_MKNOD.to_string());
+                            keys.push(STR_RW_UPLOAD.to_string());
                         }
                         keys
                     })
@@ -495,6 +610,8 @@ impl FileBrowser {
                                 self.do_create(&sel_path, true);
                             } else if f_name == STR_RW_MKNOD.to_string() {
                                 self.do_create(&sel_path, false);
+                            } else if f_name == STR_RW_UPLOAD {
+                                self.do_upload(&sel_path);
                             } else {
                                 let f_node = ret.get(&f_name).expect("Expected file to match!");
                                 let full_path = sel_path.join(f_node.path.clone());
@@ -561,6 +678,7 @@ impl FileBrowser {
                                             if f_node.attr.kind == GenericFileType::Directory {
                                                 actions.push(STR_RW_MKDIR);
                                                 actions.push(STR_RW_MKNOD);
+                                                actions.push(STR_RW_UPLOAD);
                                             }
                                             actions.push(STR_RW_RENAME);
                                             actions.push(STR_RW_DELETE);
@@ -583,6 +701,7 @@ impl FileBrowser {
                                     Some(STR_RW_EDIT) => self.do_edit(f_node, &full_path),
                                     Some(STR_RW_MKDIR) => self.do_create(&full_path, true),
                                     Some(STR_RW_MKNOD) => self.do_create(&full_path, false),
+                                    Some(STR_RW_UPLOAD) => self.do_upload(&full_path),
                                     Some(STR_RW_RENAME) => self.do_rename(&full_path),
                                     Some(STR_RO_SELECT) => {
                                         sel_path = full_path; // go to next level
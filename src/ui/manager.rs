@@ -456,7 +456,7 @@ impl<'a, T: Clone + ToShortIdString + Default, A> ModelManager<'a, T, A> {
             // If the only option is to select, then just do it.
             if self.func_delete_item.is_none()
                 && self.func_update_item.is_none()
-                && self.options.len() <= 0
+                && self.options.is_empty()
             {
                 return Ok(f_items);
             }
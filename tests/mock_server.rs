@@ -0,0 +1,267 @@
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use neptis_rs::mock::{MockHandle, MockServer};
+use neptis_rs::prelude::*;
+use neptis_rs::rolling_secret::{EnvelopeVersion, RollingSecret};
+use tokio::runtime::{Handle, Runtime};
+use tokio::time::timeout;
+use uuid::Uuid;
+
+fn server() -> MockServer {
+    MockServer::new()
+        .with_user("alice", "alice-pass", false)
+        .with_mount("alice", "docs")
+        .with_file("alice", "docs", "notes/readme.txt", b"Hello from the mock!")
+}
+
+#[tokio::test]
+async fn login_accepts_only_the_right_password() {
+    let mock = server().spawn().await.unwrap();
+
+    let api = mock.api("alice", "alice-pass");
+    assert_eq!(api.get_all_mounts().await.unwrap().len(), 1);
+    assert_eq!(mock.state().lock().tokens.len(), 1);
+
+    let api = mock.api("alice", "wrong-pass");
+    assert!(api.get_all_mounts().await.is_err());
+    mock.stop().await;
+}
+
+#[tokio::test]
+async fn mounts_can_be_created_resized_and_deleted() {
+    let mock = server().spawn().await.unwrap();
+    let api = mock.api("alice", "alice-pass");
+
+    let dto = PutForMountApi {
+        data_bytes: 1024,
+        repo_bytes: 2048,
+    };
+    api.put_one_mount("photos", dto).await.unwrap();
+    let names = |x: Vec<MountDto>| x.into_iter().map(|x| x.name).collect::<Vec<_>>();
+    assert_eq!(
+        names(api.get_all_mounts().await.unwrap()),
+        ["docs", "photos"]
+    );
+
+    let dto = PutForMountApi {
+        data_bytes: 4096,
+        repo_bytes: 2048,
+    };
+    api.put_one_mount("photos", dto).await.unwrap();
+    let data_bytes = mock
+        .state()
+        .lock()
+        .mounts
+        .iter()
+        .find(|x| x.name == "photos")
+        .map(|x| x.data_bytes);
+    assert_eq!(data_bytes, Some(4096));
+
+    api.delete_one_mount("photos").await.unwrap();
+    assert_eq!(names(api.get_all_mounts().await.unwrap()), ["docs"]);
+    assert!(api.delete_one_mount("photos").await.is_err());
+    mock.stop().await;
+}
+
+#[tokio::test]
+async fn secure_envelope_round_trips() {
+    let secret = RollingSecret::generate().unwrap();
+    let mock = server().with_secret(secret.clone()).spawn().await.unwrap();
+
+    let api = mock.api("alice", "alice-pass");
+    let mounts = api.get_all_mounts().await.unwrap();
+    assert_eq!(mounts[0].name, "docs");
+    let data = api
+        .dump_file_bytes("/docs/notes/readme.txt", 0, 1024)
+        .await
+        .unwrap();
+    assert_eq!(data, b"Hello from the mock!");
+
+    // A client with a different secret cannot open the envelope.
+    let other = RollingSecret::generate().unwrap();
+    let wrong = WebApi::new(mock.base_url(), "alice", "alice-pass", Some(other));
+    assert!(wrong.get_all_mounts().await.is_err());
+    mock.stop().await;
+}
+
+#[tokio::test]
+async fn skewed_server_clocks_are_followed() {
+    for skew in [70, -70] {
+        let secret = RollingSecret::generate().unwrap();
+        let mock = server()
+            .with_secret(secret)
+            .with_clock_offset(skew)
+            .spawn()
+            .await
+            .unwrap();
+
+        let api = mock.api("alice", "alice-pass");
+        assert_eq!(api.get_all_mounts().await.unwrap().len(), 1);
+        assert!((api.get_clock_offset() - skew).abs() <= 2);
+        let data = api
+            .dump_file_bytes("/docs/notes/readme.txt", 0, 1024)
+            .await
+            .unwrap();
+        assert_eq!(data, b"Hello from the mock!");
+        mock.stop().await;
+    }
+}
+
+#[tokio::test]
+async fn legacy_servers_need_the_server_setting() {
+    let secret = RollingSecret::generate().unwrap();
+    let mock = server()
+        .with_secret(secret)
+        .with_legacy_envelope()
+        .spawn()
+        .await
+        .unwrap();
+    let mut item = ServerItem {
+        server_endpoint: mock.base_url(),
+        ..Default::default()
+    };
+
+    let api = mock
+        .api("alice", "alice-pass")
+        .with_envelope_policy(item.envelope_policy());
+    assert!(api.get_all_mounts().await.is_err());
+
+    item.allow_legacy_envelope = true;
+    let api = mock
+        .api("alice", "alice-pass")
+        .with_envelope_policy(item.envelope_policy());
+    assert_eq!(api.get_all_mounts().await.unwrap().len(), 1);
+    assert_eq!(api.get_envelope(), Some(EnvelopeVersion::LegacyCbc));
+    mock.stop().await;
+}
+
+#[tokio::test]
+async fn rotated_secret_keeps_the_old_one_on_the_server() {
+    let old = RollingSecret::generate().unwrap();
+    let rotation = SecretRotation::rotate(&old, DEFAULT_ROTATION_GRACE).unwrap();
+    // Install the string shown to the user, as they would on the server.
+    let installed = RollingSecret::from_string(&rotation.server_secret().to_string()).unwrap();
+    let mock = server().with_secret(installed).spawn().await.unwrap();
+
+    rotation
+        .confirm(&mock.base_url(), "alice", "alice-pass")
+        .await
+        .unwrap();
+    let stale = WebApi::new(mock.base_url(), "alice", "alice-pass", Some(old));
+    assert_eq!(stale.get_all_mounts().await.unwrap().len(), 1);
+    mock.stop().await;
+}
+
+#[test]
+fn files_are_read_through_the_filesystem() {
+    // NeptisFS blocks on the runtime itself, so it is driven from outside of it.
+    let rt = Arc::new(Runtime::new().unwrap());
+    let mock: MockHandle = rt.block_on(server().spawn()).unwrap();
+    let api = Arc::new(RwLock::new(Some(mock.api("alice", "alice-pass"))));
+    let fs = NeptisFS::new(api, rt.clone());
+
+    let entries = fs.do_readdir(Path::new("/docs/notes")).unwrap();
+    let file = entries
+        .iter()
+        .find(|x| x.path == Path::new("readme.txt"))
+        .unwrap();
+    assert_eq!(file.attr.kind, GenericFileType::RegularFile);
+    assert_eq!(file.attr.size, 20);
+
+    let path = Path::new("/docs/notes/readme.txt");
+    let data = fs.do_dump(path, 0, 1024).unwrap();
+    assert_eq!(data.as_slice(), b"Hello from the mock!");
+    let data = fs.do_dump(path, 6, 4).unwrap();
+    assert_eq!(data.as_slice(), b"from");
+
+    rt.block_on(mock.stop());
+}
+
+#[tokio::test]
+async fn shared_points_are_listed_for_the_user_they_are_shared_with() {
+    let mock = server()
+        .with_user("bob", "bob-pass", false)
+        .with_share("alice", "docs", "bob", false)
+        .spawn()
+        .await
+        .unwrap();
+
+    let bob = mock.api("bob", "bob-pass");
+    assert!(bob.get_all_mounts().await.unwrap().is_empty());
+    let shared = bob.get_shared_mounts().await.unwrap();
+    assert_eq!(shared.len(), 1);
+    assert_eq!(shared[0].owned_by, "alice");
+    assert_eq!(shared[0].name, "docs");
+    assert!(!shared[0].can_write);
+
+    let alice = mock.api("alice", "alice-pass");
+    assert!(alice.get_shared_mounts().await.unwrap().is_empty());
+    mock.stop().await;
+}
+
+#[test]
+fn shared_files_are_uploaded_when_flushed() {
+    let rt = Arc::new(Runtime::new().unwrap());
+    let mock: MockHandle = rt
+        .block_on(
+            server()
+                .with_user("bob", "bob-pass", false)
+                .with_share("alice", "docs", "bob", true)
+                .spawn(),
+        )
+        .unwrap();
+    let api = Arc::new(RwLock::new(Some(mock.api("bob", "bob-pass"))));
+    let fs = NeptisFS::new(api, rt.clone());
+    let stored = || {
+        let data = mock.state().lock();
+        data.mount("alice", "docs").unwrap().files["/notes/readme.txt"]
+            .data
+            .clone()
+    };
+
+    let path = Path::new("/@alice/docs/notes/readme.txt");
+    fs.do_write(path, None, Some(0), Some(b"Howdy"), None, None, None)
+        .unwrap();
+    fs.do_write(path, None, Some(20), Some(b"!!"), None, None, None)
+        .unwrap();
+    assert_eq!(stored(), b"Hello from the mock!");
+    assert_eq!(fs.do_find(path).unwrap().attr.size, 22);
+    let data = fs.do_dump(path, 0, 1024).unwrap();
+    assert_eq!(data.as_slice(), b"Howdy from the mock!!!");
+
+    fs.do_flush(path).unwrap();
+    assert_eq!(stored(), b"Howdy from the mock!!!");
+    rt.block_on(mock.stop());
+}
+
+#[tokio::test]
+async fn shown_notifications_are_marked_read() {
+    let mock = server().spawn().await.unwrap();
+    let id = Uuid::new_v4();
+    mock.state().lock().notifications.push(WsNotificationDto {
+        id: Some(id),
+        send_to: Some(Some("alice".into())),
+        subject: Some(Some("Backup finished".into())),
+        is_read: Some(false),
+        ..WsNotificationDto::new()
+    });
+    let api = Arc::new(mock.api("alice", "alice-pass"));
+    let opts = NotificationOptions {
+        poll_interval: Duration::from_millis(50),
+        ..Default::default()
+    };
+
+    let mut feed = NotificationSubscription::spawn(api.clone(), opts.clone(), &Handle::current());
+    let dto = timeout(Duration::from_secs(5), feed.recv()).await.unwrap();
+    assert_eq!(dto.unwrap().id, Some(id));
+    api.get_one_notification(id).await.unwrap();
+    assert_eq!(mock.state().lock().notifications[0].is_read, Some(true));
+
+    // A new subscription does not deliver it again.
+    let mut feed = NotificationSubscription::spawn(api, opts, &Handle::current());
+    let next = timeout(Duration::from_millis(300), feed.recv()).await;
+    assert!(next.is_err());
+    mock.stop().await;
+}